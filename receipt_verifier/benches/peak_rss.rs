@@ -0,0 +1,81 @@
+//! Peak RSS of reading and canonicalizing a 20 MB receipt, on the streaming path
+//! (`read_receipt_file` + in-place canonicalization) against the old one (`read_to_string`,
+//! `from_str`, then a sorted copy from `canonical_json`): `cargo bench --bench peak_rss`.
+//! Each path runs in its own child process, since peak RSS only grows; Linux only (`VmHWM`).
+
+use receipt_verifier::{canonical_json, read_receipt_file, signed_canonical_string, VerifyOptions};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::process::Command;
+use std::{env, fs};
+
+/// Set in the child processes to the path they measure and the receipt file.
+const PATH_ENV: &str = "PEAK_RSS_PATH";
+const FILE_ENV: &str = "PEAK_RSS_FILE";
+const RECEIPT_BYTES: usize = 20 << 20;
+
+/// A receipt with embedded evidence blobs, about `RECEIPT_BYTES` as JSON.
+fn write_receipt(path: &Path) {
+    let blob = "ab".repeat(512);
+    let evidence: Vec<_> = (0..RECEIPT_BYTES / (blob.len() + 48))
+        .map(|i| json!({ "index": i, "blob": blob, "ok": i % 3 == 0 }))
+        .collect();
+    let receipt = json!({
+        "receipt_id": "rcpt_peak_rss",
+        "policy_hash": format!("0x{}", "ab".repeat(32)),
+        "consent_snapshot_hash": format!("0x{}", "cd".repeat(32)),
+        "evidence": evidence,
+    });
+    fs::write(path, serde_json::to_vec(&receipt).unwrap()).unwrap();
+}
+
+/// Peak resident set size of this process, in KiB.
+fn peak_rss_kib() -> u64 {
+    let status = fs::read_to_string("/proc/self/status").expect("/proc/self/status (Linux only)");
+    let line = status.lines().find(|l| l.starts_with("VmHWM:")).expect("VmHWM in /proc/self/status");
+    line.split_whitespace().nth(1).unwrap().parse().unwrap()
+}
+
+fn measure(which: &str, file: &Path) -> String {
+    let canonical = match which {
+        "streaming" => {
+            let val = read_receipt_file(file).unwrap();
+            let opts = VerifyOptions { max_canonical_bytes: Some(usize::MAX), ..Default::default() };
+            signed_canonical_string(&val, &opts).unwrap()
+        }
+        _ => {
+            let buf = fs::read_to_string(file).unwrap();
+            let val: serde_json::Value = serde_json::from_str(&buf).unwrap();
+            serde_json::to_string(&canonical_json(&val)).unwrap()
+        }
+    };
+    format!("{:x}", Sha256::digest(canonical.as_bytes()))
+}
+
+fn main() {
+    if let (Ok(which), Some(file)) = (env::var(PATH_ENV), env::var_os(FILE_ENV)) {
+        let hash = measure(&which, Path::new(&file));
+        println!("{} {}", peak_rss_kib(), hash);
+        return;
+    }
+    let file = env::temp_dir().join(format!("receipt_verifier_peak_rss_{}.json", std::process::id()));
+    write_receipt(&file);
+    let size = fs::metadata(&file).unwrap().len();
+    println!("peak RSS reading and canonicalizing a {:.1} MB receipt:", size as f64 / 1e6);
+    let mut hashes = Vec::new();
+    for which in ["buffered", "streaming"] {
+        let out = Command::new(env::current_exe().unwrap())
+            .env(PATH_ENV, which)
+            .env(FILE_ENV, &file)
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "{} run failed: {}", which, String::from_utf8_lossy(&out.stderr));
+        let stdout = String::from_utf8(out.stdout).unwrap();
+        let (kib, hash) = stdout.trim().split_once(' ').unwrap();
+        println!("  {:<10} {:>7.1} MB", which, kib.parse::<f64>().unwrap() / 1024.0);
+        hashes.push(hash.to_string());
+    }
+    fs::remove_file(&file).unwrap();
+    assert_eq!(hashes[0], hashes[1], "both paths must produce the same canonical form");
+}
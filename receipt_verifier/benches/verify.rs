@@ -0,0 +1,84 @@
+//! Baseline numbers for the hot paths over small, medium and large receipts:
+//! `cargo bench --features signing`. Compare runs with `-- --save-baseline <name>` and
+//! `-- --baseline <name>`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use k256::ecdsa::SigningKey;
+use receipt_verifier::{canonical_json, receipt_hash_sha256, recover_address, sign_receipt, signer_address, verify_receipt, VerifyOptions};
+use serde_json::json;
+use std::hint::black_box;
+
+const KEY: &str = "0x1111111111111111111111111111111111111111111111111111111111111111";
+const POLICY_HASH: &str = "0xabababababababababababababababababababababababababababababababab";
+const CONSENT_HASH: &str = "0xcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcd";
+
+/// A receipt shaped like the gateway's, padded with `entries` tool-call records.
+fn receipt(entries: usize) -> serde_json::Value {
+    let calls: Vec<_> = (0..entries)
+        .map(|i| {
+            json!({
+                "tool": format!("tool_{i}"),
+                "args": { "query": "x".repeat(32), "limit": i, "offset": i * 10 },
+                "result_hash": format!("0x{:064x}", i),
+                "ok": i % 7 != 0,
+            })
+        })
+        .collect();
+    json!({
+        "receipt_id": "rcpt_bench",
+        "policy_hash": POLICY_HASH,
+        "consent_snapshot_hash": CONSENT_HASH,
+        "issued_at": 1765778272u64,
+        "nonce": 42,
+        "subject": "did:pkh:eip155:1:0x3a66a80ac10721e69b6a9cd56b07282d9a094813",
+        "tool_calls": calls,
+    })
+}
+
+fn receipts() -> Vec<(&'static str, serde_json::Value)> {
+    vec![("small", receipt(1)), ("medium", receipt(100)), ("large", receipt(5000))]
+}
+
+fn bench_canonical(c: &mut Criterion) {
+    let mut group = c.benchmark_group("canonical_json");
+    for (name, val) in receipts() {
+        group.throughput(Throughput::Bytes(serde_json::to_string(&val).unwrap().len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &val, |b, val| b.iter(|| canonical_json(black_box(val))));
+    }
+    group.finish();
+}
+
+fn bench_hash(c: &mut Criterion) {
+    let mut group = c.benchmark_group("receipt_hash_sha256");
+    for (name, val) in receipts() {
+        group.throughput(Throughput::Bytes(serde_json::to_string(&val).unwrap().len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &val, |b, val| b.iter(|| receipt_hash_sha256(black_box(val))));
+    }
+    group.finish();
+}
+
+fn bench_recover(c: &mut Criterion) {
+    let digest = [7u8; 32];
+    let key = SigningKey::from_slice(&hex::decode(&KEY[2..]).unwrap()).unwrap();
+    let (sig, rec_id) = key.sign_prehash_recoverable(&digest).unwrap();
+    let mut sig = sig.to_bytes().to_vec();
+    sig.push(27 + rec_id.to_byte());
+    let sig = format!("0x{}", hex::encode(sig));
+    c.bench_function("recover_address", |b| b.iter(|| recover_address(black_box(&sig), digest).unwrap()));
+}
+
+fn bench_verify(c: &mut Criterion) {
+    let gateway = signer_address(KEY).unwrap();
+    let mut group = c.benchmark_group("verify_receipt");
+    for (name, val) in receipts() {
+        let sig = sign_receipt(&val, KEY, &VerifyOptions::default()).unwrap();
+        group.throughput(Throughput::Bytes(serde_json::to_string(&val).unwrap().len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &val, |b, val| {
+            b.iter(|| verify_receipt(black_box(val), &sig, Some(gateway), POLICY_HASH, CONSENT_HASH).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_canonical, bench_hash, bench_recover, bench_verify);
+criterion_main!(benches);
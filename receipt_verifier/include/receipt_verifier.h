@@ -0,0 +1,33 @@
+/* C API of receipt_verifier (build with `cargo build --release --features ffi`, link
+ * target/release/libreceipt_verifier.{so,dylib}).
+ *
+ * All `const char *` arguments are NUL-terminated UTF-8, borrowed for the call only.
+ * Every `char *` the library returns (return values and *out_err) is owned by the caller
+ * and must be released exactly once with rv_free(), never with free(). */
+#ifndef RECEIPT_VERIFIER_H
+#define RECEIPT_VERIFIER_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Verify a receipt. `gateway` may be NULL or "auto" (any signer), a 0x address, or a DID.
+ * Returns 0 on success, the VerifyError numeric code on failure, or -1 for invalid arguments.
+ * On failure *out_err receives an owned message; on success it is set to NULL.
+ * `out_err` may be NULL. */
+int rv_verify(const char *receipt_json, const char *sig_hex, const char *gateway,
+              const char *policy_hash, const char *consent_hash, char **out_err);
+
+/* Verify as rv_verify and return owned JSON {"public_inputs": ..., "witness": ...},
+ * or NULL on failure with *out_err set as for rv_verify. */
+char *rv_build_public_witness(const char *receipt_json, const char *sig_hex, const char *gateway,
+                              const char *policy_hash, const char *consent_hash, char **out_err);
+
+/* Release a string returned by this library. NULL is ignored. */
+void rv_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
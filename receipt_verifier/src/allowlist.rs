@@ -0,0 +1,35 @@
+//! Gateway allowlist files for `--gateway auto`: the signer is recovered, then must be listed.
+//!
+//! One gateway per line, as accepted by `--gateway` (address, 32-byte felt or DID); blank
+//! lines and `#` comments are skipped.
+//!
+//! ```text
+//! # tenant A gateways
+//! 0x3a66a80ac10721e69b6a9cd56b07282d9a094813
+//! did:pkh:eip155:1:0x2c7536E3605D9C16a7a3D7b1898e529396a65c23  # rotated in 2026-03
+//! ```
+
+use crate::{parse_gateway, VerifyError};
+use std::path::Path;
+
+/// Gateways listed in allowlist `text`; a bad line fails with `Config` naming its number.
+pub fn parse_allowlist(text: &str) -> Result<Vec<[u8; 20]>, VerifyError> {
+    parse_lines(text, "allowlist")
+}
+
+/// `parse_allowlist` of the file at `path`.
+pub fn load_allowlist(path: impl AsRef<Path>) -> Result<Vec<[u8; 20]>, VerifyError> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path).map_err(|e| VerifyError::Config(format!("{}: {}", path.display(), e)))?;
+    parse_lines(&text, &path.display().to_string())
+}
+
+fn parse_lines(text: &str, origin: &str) -> Result<Vec<[u8; 20]>, VerifyError> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i, line.split('#').next().unwrap_or_default().trim()))
+        .filter(|(_, entry)| !entry.is_empty())
+        .map(|(i, entry)| parse_gateway(entry).map_err(|e| VerifyError::Config(format!("{} line {}: {}", origin, i + 1, e))))
+        .collect()
+}
+
@@ -0,0 +1,144 @@
+//! Verifying and proving many receipts in one call.
+
+use crate::{
+    verify_receipt_full, verify_receipt_multi, verify_receipt_with, Proof, Prover, PublicInputs, VerifiedReceipt,
+    VerifyError, VerifyOptions, Witness,
+};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// One `{receipt, signature}` entry of a batch input file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchItem {
+    pub receipt: serde_json::Value,
+    pub signature: String,
+}
+
+/// Parse a batch file: either a JSON array of `BatchItem`s or newline-delimited JSON.
+pub fn parse_batch(input: &str) -> Result<Vec<BatchItem>, VerifyError> {
+    let trimmed = input.trim_start();
+    if trimmed.starts_with('[') {
+        return serde_json::from_str(trimmed).map_err(|e| VerifyError::Serde(e.to_string()));
+    }
+    trimmed
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| serde_json::from_str(l).map_err(|e| VerifyError::Serde(format!("line {}: {}", i + 1, e))))
+        .collect()
+}
+
+/// Verify every `(receipt, signature)` pair in parallel against the same expectations.
+/// Results are returned in input order.
+pub fn verify_batch(
+    receipts: &[(serde_json::Value, String)],
+    expected_gateway: Option<[u8; 20]>,
+    expected_policy_hash: &str,
+    expected_consent_hash: &str,
+    opts: &VerifyOptions,
+) -> Vec<Result<(String, [u8; 20]), VerifyError>> {
+    receipts
+        .par_iter()
+        .map(|(val, sig)| verify_receipt_with(val, sig, expected_gateway, expected_policy_hash, expected_consent_hash, opts))
+        .collect()
+}
+
+/// `verify_batch` accepting any gateway in `expected_gateways`.
+pub fn verify_batch_multi(
+    receipts: &[(serde_json::Value, String)],
+    expected_gateways: &[[u8; 20]],
+    expected_policy_hash: &str,
+    expected_consent_hash: &str,
+    opts: &VerifyOptions,
+) -> Vec<Result<(String, [u8; 20]), VerifyError>> {
+    receipts
+        .par_iter()
+        .map(|(val, sig)| {
+            verify_receipt_multi(val, sig, expected_gateways, expected_policy_hash, expected_consent_hash, opts)
+                .map(|(v, _)| (v.receipt_hash, v.signer))
+        })
+        .collect()
+}
+
+/// Like `verify_batch_multi`, but keeps each `VerifiedReceipt` (for building public inputs and
+/// witnesses). An empty `expected_gateways` accepts any signer.
+pub fn verify_batch_full(
+    receipts: &[(serde_json::Value, String)],
+    expected_gateways: &[[u8; 20]],
+    expected_policy_hash: &str,
+    expected_consent_hash: &str,
+    opts: &VerifyOptions,
+) -> Vec<Result<VerifiedReceipt, VerifyError>> {
+    receipts
+        .par_iter()
+        .map(|(val, sig)| match expected_gateways {
+            [] => verify_receipt_full(val, sig, None, expected_policy_hash, expected_consent_hash, opts),
+            [gw] => verify_receipt_full(val, sig, Some(*gw), expected_policy_hash, expected_consent_hash, opts),
+            gws => verify_receipt_multi(val, sig, gws, expected_policy_hash, expected_consent_hash, opts).map(|(v, _)| v),
+        })
+        .collect()
+}
+
+/// Run `prover` over every item with at most `concurrency` proofs in flight (at least one), so
+/// subprocess and HTTP provers are not started once per receipt all at once. Results are
+/// returned in input order.
+pub fn prove_batch(
+    items: &[(PublicInputs, Witness)],
+    prover: &dyn Prover,
+    concurrency: usize,
+) -> Vec<Result<Proof, VerifyError>> {
+    let prove_all = || items.par_iter().map(|(pi, w)| prover.prove(pi, w)).collect();
+    match rayon::ThreadPoolBuilder::new().num_threads(concurrency.max(1)).build() {
+        Ok(pool) => pool.install(prove_all),
+        // No threads to spare: prove one at a time on this one.
+        Err(_) => items.iter().map(|(pi, w)| prover.prove(pi, w)).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_gateway, verify_proof, HashScheme, PoseidonParams, SigProver};
+
+    const RECEIPT: &str = include_str!("../../scripts/receipts/rcpt_1765778272_0cbf2a85.json");
+
+    #[test]
+    fn mixed_hash_schemes_prove_and_verify_in_one_batch() {
+        let receipt: serde_json::Value = serde_json::from_str(RECEIPT).unwrap();
+        let sig = receipt["receipt_sig"].as_str().unwrap().to_string();
+        let (policy, consent) = (format!("0x{}", "ab".repeat(32)), format!("0x{}", "cd".repeat(32)));
+        let gateway = parse_gateway("0x3A66A80Ac10721e69b6A9Cd56B07282D9a094813").unwrap();
+        let schemes = [HashScheme::Sha256, HashScheme::Poseidon(PoseidonParams::default()), HashScheme::Keccak];
+
+        let items: Vec<_> = schemes
+            .iter()
+            .map(|hash| {
+                let opts = VerifyOptions { hash: hash.clone(), ..Default::default() };
+                let [verified] = verify_batch_full(&[(receipt.clone(), sig.clone())], &[gateway], &policy, &consent, &opts)
+                    .try_into()
+                    .unwrap();
+                verified.unwrap().public_and_witness(&sig)
+            })
+            .collect();
+        let proofs: Vec<Proof> = prove_batch(&items, &SigProver, 2).into_iter().map(Result::unwrap).collect();
+
+        let hashes: Vec<_> = proofs.iter().map(|p| p.public_inputs.receipt_hash.clone()).collect();
+        assert!(hashes[0] != hashes[1] && hashes[1] != hashes[2] && hashes[0] != hashes[2], "{hashes:?}");
+        for (proof, scheme) in proofs.iter().zip(&schemes) {
+            assert_eq!(proof.public_inputs.hash_scheme.as_ref(), Some(scheme));
+            // Default options are sha256; each proof is rechecked under the scheme it records.
+            verify_proof(proof, &receipt, &policy, &consent).unwrap();
+        }
+
+        // Relabelled, the same digests no longer recompute.
+        for (proof, other) in proofs.iter().zip(schemes.iter().cycle().skip(1)) {
+            let mut relabelled = proof.clone();
+            relabelled.public_inputs.hash_scheme = Some(other.clone());
+            let err = verify_proof(&relabelled, &receipt, &policy, &consent).unwrap_err();
+            assert!(matches!(err, VerifyError::ReceiptHashMismatch { .. }), "{err}");
+        }
+        let mut unstamped = proofs[1].clone();
+        unstamped.public_inputs.hash_scheme = None;
+        assert!(matches!(verify_proof(&unstamped, &receipt, &policy, &consent), Err(VerifyError::ReceiptHashMismatch { .. })));
+    }
+}
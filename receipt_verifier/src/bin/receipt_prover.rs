@@ -1,6 +1,35 @@
-use std::io::{self, Read};
-use receipt_verifier::{PublicInputs, Witness, mock_prove, external_prove};
+use clap::Parser;
+use std::io::{self, IsTerminal};
+use receipt_verifier::{Compression, ProveOpts, ProverRegistry, PublicInputs, VerifyError, Witness};
 use serde::Deserialize;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
+
+/// Set for the external prover's process tree, so a LUMINAIR_PROVER_CMD pointing back at this
+/// binary does not recurse.
+const NESTED_ENV: &str = "RECEIPT_PROVER_NESTED";
+
+/// Prover subprocess for receipt_verifier (the LUMINAIR_PROVER_CMD contract).
+///
+/// Reads `{"public_inputs": .., "witness": ..}` JSON (optionally gzipped) on stdin and writes a Proof JSON to stdout.
+/// Runs LUMINAIR_PROVER_CMD; if it is not set or fails, that is an error unless
+/// `--allow-prover-fallback`, which emits a receipt_sig proof stamped `receipt_sig:fallback`
+/// instead. On errors prints `{"error": ".."}` to stderr and exits non-zero.
+#[derive(Parser, Debug)]
+#[command(version)]
+struct Args {
+    /// Compression of stdin: auto|none|gzip (auto detects the gzip header; gzip needs the
+    /// `gzip` feature)
+    #[arg(long, default_value = "auto")]
+    input_compression: Compression,
+    /// Log filter for stderr logs (e.g. debug); defaults to RUST_LOG, else warn
+    #[arg(long)]
+    log_level: Option<String>,
+    /// Fall back to the receipt_sig stub (stamped receipt_sig:fallback) when LUMINAIR_PROVER_CMD
+    /// is not set or fails
+    #[arg(long)]
+    allow_prover_fallback: bool,
+}
 
 #[derive(Deserialize)]
 struct ProverInput {
@@ -8,13 +37,57 @@ struct ProverInput {
     witness: Witness,
 }
 
+fn fail(message: String) -> ! {
+    eprintln!("{}", serde_json::json!({ "error": message }));
+    std::process::exit(1);
+}
+
+/// Byte offset of a 1-based line/column position in `buf`.
+fn byte_offset(buf: &str, line: usize, column: usize) -> usize {
+    let line_start: usize = buf.split_inclusive('\n').take(line.saturating_sub(1)).map(str::len).sum();
+    line_start + column.saturating_sub(1)
+}
+
+/// Log spans and events to stderr (stdout carries the proof), filtered by `level`, else
+/// `RUST_LOG`, else `warn`.
+fn init_tracing(level: Option<&str>) {
+    let filter = match level {
+        Some(level) => EnvFilter::try_new(level).unwrap_or_else(|e| fail(format!("invalid --log-level: {}", e))),
+        None => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+    };
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(io::stderr)
+        .with_ansi(io::stderr().is_terminal())
+        .init();
+}
+
 fn main() -> anyhow::Result<()> {
-    let mut buf = String::new();
-    io::stdin().read_to_string(&mut buf)?;
-    let input: ProverInput = serde_json::from_str(&buf)?;
-    // Try external prover first if set, then fall back to receipt_sig.
-    let proof = external_prove(&input.public_inputs, &input.witness)
-        .unwrap_or_else(|_| mock_prove(&input.public_inputs, &input.witness));
+    let args = Args::parse();
+    init_tracing(args.log_level.as_deref());
+    let bytes = receipt_verifier::read_decompressed(io::stdin(), args.input_compression)
+        .unwrap_or_else(|e| fail(format!("reading stdin: {}", e)));
+    let buf = String::from_utf8(bytes).unwrap_or_else(|e| fail(format!("reading stdin: {}", e)));
+    if buf.trim().is_empty() {
+        fail("no input on stdin".into());
+    }
+    let input: ProverInput = serde_json::from_str(&buf).unwrap_or_else(|e| {
+        fail(format!("invalid input at byte {}: {}", byte_offset(&buf, e.line(), e.column()), e))
+    });
+    let mut provers = ProverRegistry::with_defaults(ProveOpts::default());
+    if args.allow_prover_fallback {
+        provers.allow_fallback(Some(|e| tracing::warn!(error = %e, "external prover failed; using the receipt_sig stub")));
+    }
+    let proof = if std::env::var_os(NESTED_ENV).is_some() {
+        // LUMINAIR_PROVER_CMD points back at this binary, so there is no external prover; the
+        // outer run decides whether to fall back.
+        Err(VerifyError::Prover(format!("LUMINAIR_PROVER_CMD runs receipt_prover itself ({} is set)", NESTED_ENV)))
+    } else {
+        std::env::set_var(NESTED_ENV, "1");
+        provers.get("auto").and_then(|p| p.prove(&input.public_inputs, &input.witness))
+    };
+    let proof = proof.unwrap_or_else(|e| fail(e.to_string()));
     println!("{}", serde_json::to_string(&proof)?);
     Ok(())
 }
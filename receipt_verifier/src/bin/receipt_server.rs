@@ -0,0 +1,208 @@
+//! Long-lived HTTP verification service (`server` feature).
+//!
+//! `POST /verify` takes `{receipt, signature, gateway, policy_hash, consent_hash, prove}` and
+//! answers with the same JSON as `receipt_verifier --format json build-inputs` (`prove` with
+//! `prove: true`); `GET /healthz` returns `ok` and `GET /stats` the canonicalization cache
+//! counters. Request bodies may be gzipped (`Content-Encoding: gzip`, or detected from the gzip
+//! header). With the `metrics` feature, `GET /metrics` serves the verification and proving
+//! metrics in Prometheus text format.
+
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use clap::Parser;
+use receipt_verifier::{
+    parse_gateway, verify_receipt_full, verify_receipt_multi, Compression, ProveOpts, ProverRegistry, VerifierCache,
+    VerifyError, VerifyOptions,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::io::IsTerminal;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
+
+#[derive(Parser, Debug)]
+struct Args {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    listen: String,
+    /// Prover used for requests with `prove: true` (see receipt_verifier prove --prover)
+    #[arg(long, default_value = "auto")]
+    prover: String,
+    /// Kill the external prover after this many seconds
+    #[arg(long, default_value_t = 600)]
+    prover_timeout: u64,
+    /// Let `--prover auto` fall back to the receipt_sig stub (stamped receipt_sig:fallback) when
+    /// the external prover fails or is not set
+    #[arg(long)]
+    allow_prover_fallback: bool,
+    /// Receipts whose canonical form and hash are kept for re-verification (0 disables the cache)
+    #[arg(long, default_value_t = 1024)]
+    cache_size: usize,
+    /// Log filter for stderr logs (e.g. debug, receipt_verifier=trace); defaults to RUST_LOG, else info
+    #[arg(long)]
+    log_level: Option<String>,
+}
+
+struct AppState {
+    provers: ProverRegistry,
+    prover: String,
+    cache: Option<Arc<VerifierCache>>,
+}
+
+#[derive(Deserialize)]
+struct VerifyRequest {
+    receipt: serde_json::Value,
+    signature: String,
+    /// `auto`, an address or DID, or a comma-separated allowlist; defaults to `auto`.
+    gateway: Option<String>,
+    policy_hash: String,
+    consent_hash: String,
+    #[serde(default)]
+    prove: bool,
+}
+
+type Reply = (StatusCode, Json<serde_json::Value>);
+
+/// Histogram buckets (seconds) of the verify and prove latency metrics: recovery takes
+/// milliseconds, external proving up to minutes.
+#[cfg(feature = "metrics")]
+const LATENCY_BUCKETS: &[f64] = &[0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 1.0, 5.0, 30.0, 120.0, 600.0];
+
+fn error_reply(status: StatusCode, err: &VerifyError) -> Reply {
+    tracing::info!(status = status.as_u16(), code = err.code(), error = %err, "request rejected");
+    let error = json!({ "code": err.code(), "numeric_code": err.numeric_code(), "message": err.to_string() });
+    (status, Json(json!({ "ok": false, "error": error })))
+}
+
+/// Verification (and optional proving) for one request; blocking, so run off the async workers.
+fn verify(state: &AppState, req: &VerifyRequest) -> Reply {
+    let gateway = req.gateway.as_deref().unwrap_or("auto");
+    let gateways: Vec<[u8; 20]> = if gateway.eq_ignore_ascii_case("auto") {
+        Vec::new()
+    } else {
+        match gateway.split(',').map(|g| parse_gateway(g.trim())).collect() {
+            Ok(gws) => gws,
+            Err(e) => return error_reply(StatusCode::BAD_REQUEST, &e),
+        }
+    };
+    let opts = VerifyOptions { cache: state.cache.clone(), ..Default::default() };
+    let (policy, consent) = (req.policy_hash.as_str(), req.consent_hash.as_str());
+    let verified = match gateways.as_slice() {
+        [] => verify_receipt_full(&req.receipt, &req.signature, None, policy, consent, &opts),
+        [gw] => verify_receipt_full(&req.receipt, &req.signature, Some(*gw), policy, consent, &opts),
+        gws => verify_receipt_multi(&req.receipt, &req.signature, gws, policy, consent, &opts).map(|(v, _)| v),
+    };
+    let verified = match verified {
+        Ok(v) => v,
+        Err(e) => return error_reply(StatusCode::UNPROCESSABLE_ENTITY, &e),
+    };
+    let (pub_inputs, witness) = verified.public_and_witness(&req.signature);
+    let mut report = json!({
+        "ok": true,
+        "receipt_hash": verified.receipt_hash_hex(),
+        "recovered_address": receipt_verifier::to_checksum_address(verified.signer),
+        "public_inputs": pub_inputs,
+    });
+    if req.prove {
+        let proof = state.provers.get(&state.prover).and_then(|p| p.prove(&pub_inputs, &witness));
+        match proof {
+            Ok(proof) => report["proof"] = json!(proof),
+            Err(e) => return error_reply(StatusCode::BAD_GATEWAY, &e),
+        }
+    }
+    tracing::info!(
+        receipt_hash = %verified.receipt_hash_hex(),
+        recovered_address = %receipt_verifier::to_checksum_address(verified.signer),
+        proved = req.prove,
+        "verified"
+    );
+    (StatusCode::OK, Json(report))
+}
+
+async fn stats_handler(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let cache = state.cache.as_ref().map(|c| json!({ "hits": c.hits(), "misses": c.misses(), "entries": c.len() }));
+    Json(json!({ "cache": cache }))
+}
+
+/// The `/verify` request in `body`, gunzipped first if it is gzip-encoded.
+fn parse_request(headers: &HeaderMap, body: Bytes) -> Result<VerifyRequest, VerifyError> {
+    let gzip = headers.get(header::CONTENT_ENCODING).is_some_and(|v| v.as_bytes().eq_ignore_ascii_case(b"gzip"));
+    let body = receipt_verifier::decompress(body.to_vec(), if gzip { Compression::Gzip } else { Compression::Auto })?;
+    serde_json::from_slice(&body).map_err(|e| VerifyError::Serde(e.to_string()))
+}
+
+async fn verify_handler(State(state): State<Arc<AppState>>, headers: HeaderMap, body: Bytes) -> Reply {
+    let req = match parse_request(&headers, body) {
+        Ok(req) => req,
+        Err(e) => return error_reply(StatusCode::BAD_REQUEST, &e),
+    };
+    let span = tracing::info_span!("request", path = "/verify");
+    tokio::task::spawn_blocking(move || span.in_scope(|| verify(&state, &req))).await.unwrap_or_else(|e| {
+        error_reply(StatusCode::INTERNAL_SERVER_ERROR, &VerifyError::Prover(e.to_string()))
+    })
+}
+
+/// Resolve on Ctrl-C or, on Unix, SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        if let Ok(mut sig) = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            sig.recv().await;
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let filter = match args.log_level.as_deref() {
+        Some(level) => EnvFilter::try_new(level)?,
+        None => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+    };
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::stderr().is_terminal())
+        .init();
+    let mut provers = ProverRegistry::with_defaults(ProveOpts {
+        timeout: Duration::from_secs(args.prover_timeout),
+        ..Default::default()
+    });
+    if args.allow_prover_fallback {
+        provers.allow_fallback(Some(|e| tracing::warn!(error = %e, "external prover failed; using the receipt_sig stub")));
+    }
+    provers.get(&args.prover)?;
+    let cache = (args.cache_size > 0).then(|| Arc::new(VerifierCache::new(args.cache_size)));
+    let state = Arc::new(AppState { provers, prover: args.prover, cache });
+    let app = Router::new()
+        .route("/healthz", get(|| async { "ok" }))
+        .route("/stats", get(stats_handler))
+        .route("/verify", post(verify_handler));
+    #[cfg(feature = "metrics")]
+    let app = {
+        let handle = metrics_exporter_prometheus::PrometheusBuilder::new()
+            .set_buckets(LATENCY_BUCKETS)?
+            .install_recorder()?;
+        app.route("/metrics", get(move || std::future::ready(handle.render())))
+    };
+    let app = app.with_state(state);
+    let listener = tokio::net::TcpListener::bind(&args.listen).await?;
+    tracing::info!(addr = %listener.local_addr()?, "receipt_server listening");
+    axum::serve(listener, app).with_graceful_shutdown(shutdown_signal()).await?;
+    Ok(())
+}
@@ -0,0 +1,128 @@
+//! Self-contained proof artifact for auditors: the proof, the receipt it was produced from and
+//! the expectations it was checked against, re-verifiable offline with `ProofBundle::verify`.
+//! `InputBundle` is the input-side counterpart: a receipt and its expectations in one file.
+
+use crate::{
+    parse_gateway, to_checksum_address, verify_proof_with, verify_receipt_full, Proof, PublicInputs, Receipt,
+    VerifiedReceipt, VerifyError, VerifyOptions, VERIFIER_VERSION,
+};
+use std::path::Path;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofBundle {
+    #[serde(deserialize_with = "migrated_proof")]
+    pub proof: Proof,
+    pub public_inputs: PublicInputs,
+    /// The receipt as received; a `receipt_sig` proof carries the signature but not the signed
+    /// bytes, so it cannot be re-checked without it.
+    pub receipt: serde_json::Value,
+    /// Gateway the verifier required (EIP-55), or `None` if any signer was accepted.
+    pub expected_gateway: Option<String>,
+    pub anchor_tx_hash: Option<String>,
+    pub verifier_version: String,
+    /// Unix seconds when the bundle was assembled.
+    pub timestamp: u64,
+}
+
+/// Bundled proofs go through `Proof::from_value`, so bundles of older proofs still load.
+fn migrated_proof<'de, D: Deserializer<'de>>(d: D) -> Result<Proof, D::Error> {
+    Proof::from_value(serde_json::Value::deserialize(d)?).map_err(serde::de::Error::custom)
+}
+
+impl ProofBundle {
+    /// Bundle `proof` for `receipt`, stamped with this verifier's version and the current time.
+    pub fn new(proof: Proof, receipt: serde_json::Value, expected_gateway: Option<[u8; 20]>) -> Result<Self, VerifyError> {
+        let anchor_tx_hash = Receipt::parse(&receipt)?.anchor_tx_hash().map(str::to_string);
+        Ok(ProofBundle {
+            public_inputs: proof.public_inputs.clone(),
+            proof,
+            receipt,
+            expected_gateway: expected_gateway.map(to_checksum_address),
+            anchor_tx_hash,
+            verifier_version: VERIFIER_VERSION.to_string(),
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        })
+    }
+
+    /// Full offline check of a `receipt_sig` bundle with default options; see `verify_with`.
+    pub fn verify(&self) -> Result<(), VerifyError> {
+        self.verify_with(&VerifyOptions::default())
+    }
+
+    /// Check that the bundle is internally consistent (public inputs, gateway and anchor agree
+    /// with the proof and receipt) and that the proof verifies against the receipt under the
+    /// bundled policy/consent hashes, using the options the receipt was originally checked with.
+    /// Whether those hashes are the ones an auditor expects is left to the auditor.
+    pub fn verify_with(&self, opts: &VerifyOptions) -> Result<(), VerifyError> {
+        let pi = &self.public_inputs;
+        let proof_pi = &self.proof.public_inputs;
+        if (&pi.receipt_hash, &pi.policy_hash, &pi.consent_hash, &pi.gateway_address)
+            != (&proof_pi.receipt_hash, &proof_pi.policy_hash, &proof_pi.consent_hash, &proof_pi.gateway_address)
+        {
+            return Err(VerifyError::Bundle("public inputs differ from the proof's".into()));
+        }
+        if let Some(expected) = self.expected_gateway.as_deref() {
+            if parse_gateway(expected)? != parse_gateway(&pi.gateway_address)? {
+                return Err(VerifyError::AddressMismatch { recovered: pi.gateway_address.clone() });
+            }
+        }
+        let anchor = Receipt::parse(&self.receipt)?.anchor_tx_hash().map(str::to_string);
+        if anchor != self.anchor_tx_hash {
+            return Err(VerifyError::Bundle("anchor_tx_hash differs from the receipt's anchor".into()));
+        }
+        verify_proof_with(&self.proof, &self.receipt, &pi.policy_hash, &pi.consent_hash, opts)
+    }
+}
+
+/// A receipt and everything needed to verify it, as one self-describing JSON object
+/// (`--bundle-in`). Only `receipt` is required: the signature falls back to the receipt's
+/// `receipt_sig`, and missing expectations to the caller's. Unknown keys are rejected so a
+/// misspelled expectation is not silently ignored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InputBundle {
+    pub receipt: serde_json::Value,
+    #[serde(default)]
+    pub signature: Option<String>,
+    /// Expected signer, in any form `parse_gateway` takes; `None` or `"auto"` accepts any.
+    #[serde(default)]
+    pub gateway: Option<String>,
+    #[serde(default)]
+    pub policy_hash: Option<String>,
+    #[serde(default)]
+    pub consent_hash: Option<String>,
+}
+
+impl InputBundle {
+    pub fn from_json(json: &str) -> Result<Self, VerifyError> {
+        serde_json::from_str(json).map_err(|e| VerifyError::Serde(format!("input bundle: {}", e)))
+    }
+
+    /// `from_json` over a file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, VerifyError> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|e| VerifyError::Serde(format!("{}: {}", path.display(), e)))?;
+        InputBundle::from_json(&json)
+    }
+
+    /// `signature`, else the receipt's `receipt_sig`.
+    pub fn signature(&self) -> Option<&str> {
+        self.signature.as_deref().or_else(|| self.receipt.get("receipt_sig").and_then(|v| v.as_str()))
+    }
+
+    /// Verify the bundled receipt against the bundled expectations under `opts`, as
+    /// `verify_receipt_full` does. Both hashes must be in the bundle (`Config` otherwise).
+    pub fn verify(&self, opts: &VerifyOptions) -> Result<VerifiedReceipt, VerifyError> {
+        let (Some(policy_hash), Some(consent_hash)) = (self.policy_hash.as_deref(), self.consent_hash.as_deref()) else {
+            return Err(VerifyError::Config("input bundle needs policy_hash and consent_hash".into()));
+        };
+        let signature = self.signature().ok_or_else(|| VerifyError::Config("input bundle has no signature and the receipt no receipt_sig".into()))?;
+        let gateway = match self.gateway.as_deref() {
+            None | Some("auto") => None,
+            Some(gw) => Some(parse_gateway(gw)?),
+        };
+        verify_receipt_full(&self.receipt, signature, gateway, policy_hash, consent_hash, opts)
+    }
+}
@@ -0,0 +1,107 @@
+//! LRU cache of canonical forms and receipt hashes (`cache` feature), for services that
+//! re-verify the same receipts (retries, audits).
+//!
+//! Entries are keyed by the sha256 of the receipt's JSON serialization together with every
+//! option that changes the canonical bytes or the hash (canonicalization, the canonicalizer by
+//! its `Debug` form, hash scheme, excluded fields, sign path, size limits), so a hit returns
+//! exactly what recomputing would.
+//! Signature recovery and the policy, time-window and replay checks still run on every call.
+
+use crate::{VerifyError, VerifyOptions};
+use lru::LruCache;
+use sha2::{Digest, Sha256};
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Shared, internally locked cache; put it in `VerifyOptions::cache` behind an `Arc`.
+#[derive(Debug)]
+pub struct VerifierCache {
+    entries: Mutex<LruCache<[u8; 32], (String, String)>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl VerifierCache {
+    /// A cache holding up to `capacity` receipts (at least one).
+    pub fn new(capacity: usize) -> Self {
+        VerifierCache {
+            entries: Mutex::new(LruCache::new(NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN))),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Lookups answered from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Lookups that had to canonicalize and hash.
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Receipts currently cached.
+    pub fn len(&self) -> usize {
+        self.entries.lock().map(|e| e.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// `(canonical, receipt_hash)` for `receipt_val` under `opts`, computed by `compute` on a miss.
+    pub(crate) fn get_or_compute(
+        &self,
+        receipt_val: &serde_json::Value,
+        opts: &VerifyOptions,
+        compute: impl FnOnce() -> Result<(String, String), VerifyError>,
+    ) -> Result<(String, String), VerifyError> {
+        let key = cache_key(receipt_val, opts)?;
+        if let Some(hit) = self.entries.lock().ok().and_then(|mut e| e.get(&key).cloned()) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(hit);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let computed = compute()?;
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.put(key, computed.clone());
+        }
+        Ok(computed)
+    }
+}
+
+fn cache_key(receipt_val: &serde_json::Value, opts: &VerifyOptions) -> Result<[u8; 32], VerifyError> {
+    let mut hasher = Sha256::new();
+    let settings = format!(
+        "{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}",
+        opts.canon,
+        opts.canonicalizer,
+        opts.signed_raw,
+        opts.hash,
+        opts.excluded(),
+        opts.sign_path,
+        opts.sort_arrays_at,
+        opts.max_depth,
+        opts.max_canonical_bytes
+    );
+    hasher.update((settings.len() as u64).to_be_bytes());
+    hasher.update(settings.as_bytes());
+    serde_json::to_writer(HashWriter(&mut hasher), receipt_val).map_err(|e| VerifyError::Serde(e.to_string()))?;
+    Ok(hasher.finalize().into())
+}
+
+/// Streams serialized JSON into the hasher instead of buffering it.
+struct HashWriter<'a>(&'a mut Sha256);
+
+impl std::io::Write for HashWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+//! Public inputs as the felt tuple the on-chain Stwo verifier contract takes.
+//!
+//! Layout (the contract's argument order; change both together):
+//! 1. `receipt_hash` low 128 bits, then high 128 bits;
+//! 2. `policy_hash` low, high;
+//! 3. `consent_hash` low, high;
+//! 4. `gateway_address` as a single felt (20-byte address, or a 32-byte Stark public key).
+//!
+//! Hashes follow the Cairo `u256 { low, high }` serialization: `low` is the last 16 bytes of the
+//! big-endian hash. Limbs are written as `0x` + 32 hex digits, the gateway as `0x` + its bytes.
+
+use crate::{hex_bytes, PublicInputs, VerifyError};
+
+/// Number of felts in `public_inputs_calldata` output.
+pub const CALLDATA_LEN: usize = 7;
+
+fn u256_limbs(hex_str: &str, what: &str) -> Result<[String; 2], VerifyError> {
+    let bytes: [u8; 32] = hex_bytes(hex_str, true)
+        .map_err(|e| VerifyError::Hex(format!("{}: {}", what, e)))?
+        .try_into()
+        .map_err(|b: Vec<u8>| VerifyError::Hex(format!("{}: expected 32 bytes, got {}", what, b.len())))?;
+    Ok([format!("0x{}", hex::encode(&bytes[16..])), format!("0x{}", hex::encode(&bytes[..16]))])
+}
+
+/// `pi` in the contract's calldata order, as described in the module docs.
+pub fn public_inputs_calldata(pi: &PublicInputs) -> Result<Vec<String>, VerifyError> {
+    let gateway = hex_bytes(&pi.gateway_address, true).map_err(|e| VerifyError::Hex(format!("gateway_address: {}", e)))?;
+    if gateway.len() != 20 && gateway.len() != 32 {
+        return Err(VerifyError::Hex(format!("gateway_address must be 20 or 32 bytes, got {}", gateway.len())));
+    }
+    let mut felts = Vec::with_capacity(CALLDATA_LEN);
+    felts.extend(u256_limbs(&pi.receipt_hash, "receipt_hash")?);
+    felts.extend(u256_limbs(&pi.policy_hash, "policy_hash")?);
+    felts.extend(u256_limbs(&pi.consent_hash, "consent_hash")?);
+    felts.push(format!("0x{}", hex::encode(gateway)));
+    Ok(felts)
+}
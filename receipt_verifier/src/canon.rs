@@ -0,0 +1,104 @@
+//! Pluggable canonicalization for tenants whose signers normalize receipts before signing.
+//!
+//! A `Canonicalizer` may rewrite the receipt tree, then serializes it with a `CanonMode`.
+//! `CanonMode` itself is the identity rewrite (today's behavior); the transforms below wrap
+//! another canonicalizer and apply their rewrite after it, so they chain:
+//! `TrimStrings(DropNulls(CanonMode::Jcs))` drops nulls, then trims, then serializes as JCS.
+//!
+//! Signer and verifier must use the same canonicalizer: the receipt hash and the personal_sign
+//! digest are taken over its output, so any difference makes every signature fail.
+
+use crate::CanonMode;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::fmt;
+
+pub trait Canonicalizer: fmt::Debug + Send + Sync {
+    /// The tree that gets serialized; the default is `val` unchanged.
+    fn transform<'a>(&self, val: &'a Value) -> Cow<'a, Value> {
+        Cow::Borrowed(val)
+    }
+
+    /// Serialization applied to the transformed tree.
+    fn mode(&self) -> CanonMode;
+}
+
+impl Canonicalizer for CanonMode {
+    fn mode(&self) -> CanonMode {
+        *self
+    }
+}
+
+/// Applies `rewrite` to every value of `inner`'s output tree, innermost first.
+fn rewrite_tree<'a>(inner: &dyn Canonicalizer, val: &'a Value, rewrite: &dyn Fn(&mut Value)) -> Cow<'a, Value> {
+    fn walk(v: &mut Value, rewrite: &dyn Fn(&mut Value)) {
+        match v {
+            Value::Object(map) => map.values_mut().for_each(|c| walk(c, rewrite)),
+            Value::Array(items) => items.iter_mut().for_each(|c| walk(c, rewrite)),
+            _ => {}
+        }
+        rewrite(v);
+    }
+    let mut owned = inner.transform(val).into_owned();
+    walk(&mut owned, rewrite);
+    Cow::Owned(owned)
+}
+
+/// Removes object members whose value is `null` (array elements are kept).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DropNulls<C = CanonMode>(pub C);
+
+impl<C: Canonicalizer> Canonicalizer for DropNulls<C> {
+    fn transform<'a>(&self, val: &'a Value) -> Cow<'a, Value> {
+        rewrite_tree(&self.0, val, &|v| {
+            if let Value::Object(map) = v {
+                map.retain(|_, c| !c.is_null());
+            }
+        })
+    }
+
+    fn mode(&self) -> CanonMode {
+        self.0.mode()
+    }
+}
+
+/// Lowercases string values that are `0x` + 40 hex digits (Ethereum addresses, EIP-55 or not).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LowercaseAddresses<C = CanonMode>(pub C);
+
+impl<C: Canonicalizer> Canonicalizer for LowercaseAddresses<C> {
+    fn transform<'a>(&self, val: &'a Value) -> Cow<'a, Value> {
+        rewrite_tree(&self.0, val, &|v| {
+            if let Value::String(s) = v {
+                let is_address = s.len() == 42 && s.starts_with("0x") && s[2..].bytes().all(|b| b.is_ascii_hexdigit());
+                if is_address {
+                    s.make_ascii_lowercase();
+                }
+            }
+        })
+    }
+
+    fn mode(&self) -> CanonMode {
+        self.0.mode()
+    }
+}
+
+/// Trims leading and trailing whitespace from string values (object keys are left alone).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TrimStrings<C = CanonMode>(pub C);
+
+impl<C: Canonicalizer> Canonicalizer for TrimStrings<C> {
+    fn transform<'a>(&self, val: &'a Value) -> Cow<'a, Value> {
+        rewrite_tree(&self.0, val, &|v| {
+            if let Value::String(s) = v {
+                if s.trim().len() != s.len() {
+                    *s = s.trim().to_string();
+                }
+            }
+        })
+    }
+
+    fn mode(&self) -> CanonMode {
+        self.0.mode()
+    }
+}
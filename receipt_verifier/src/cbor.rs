@@ -0,0 +1,83 @@
+//! CBOR (incl. dag-cbor) wire format for receipts, public inputs, witnesses and proofs
+//! (`cbor` feature).
+//!
+//! Canonicalization runs on the decoded value tree, never on the wire bytes, so a receipt
+//! decoded from CBOR hashes and verifies exactly like the same receipt decoded from JSON.
+//! CBOR values without a JSON equivalent (byte strings, non-string map keys) are rejected;
+//! tags such as dag-cbor's CID tag 42 are dropped and their inner value kept.
+
+use crate::VerifyError;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// Decode any CBOR document from a reader.
+pub fn from_cbor<T: DeserializeOwned>(reader: impl Read) -> Result<T, VerifyError> {
+    ciborium::de::from_reader(BufReader::new(reader)).map_err(|e| VerifyError::Serde(format!("cbor: {}", e)))
+}
+
+/// Encode a value (public inputs, witness, proof, ...) as CBOR. It goes through a JSON value
+/// tree first, so map keys come out sorted and numbers as CBOR integers (bignums beyond 64
+/// bits) or floats.
+pub fn to_cbor<T: Serialize>(value: &T) -> Result<Vec<u8>, VerifyError> {
+    let tree = serde_json::to_value(value).map_err(|e| VerifyError::Serde(format!("cbor: {}", e)))?;
+    let mut out = Vec::new();
+    ciborium::ser::into_writer(&NativeNumbers(&tree), &mut out).map_err(|e| VerifyError::Serde(format!("cbor: {}", e)))?;
+    Ok(out)
+}
+
+/// A value tree serialized with plain serde numbers. serde_json's `arbitrary_precision`
+/// numbers serialize as a private one-entry map outside serde_json, not as numbers.
+struct NativeNumbers<'a>(&'a serde_json::Value);
+
+impl Serialize for NativeNumbers<'_> {
+    fn serialize<S: serde::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::{Error, SerializeMap, SerializeSeq};
+        match self.0 {
+            serde_json::Value::Number(n) => {
+                let literal = n.to_string();
+                if let Some(u) = n.as_u64() {
+                    ser.serialize_u64(u)
+                } else if let Some(i) = n.as_i64() {
+                    ser.serialize_i64(i)
+                } else if let Ok(u) = literal.parse::<u128>() {
+                    ser.serialize_u128(u)
+                } else if let Ok(i) = literal.parse::<i128>() {
+                    ser.serialize_i128(i)
+                } else if crate::is_integer_literal(n) {
+                    Err(S::Error::custom(format!("integer {} does not fit in 128 bits", literal)))
+                } else {
+                    ser.serialize_f64(n.as_f64().ok_or_else(|| S::Error::custom(format!("number {} is not finite", literal)))?)
+                }
+            }
+            serde_json::Value::Array(arr) => {
+                let mut seq = ser.serialize_seq(Some(arr.len()))?;
+                for v in arr {
+                    seq.serialize_element(&NativeNumbers(v))?;
+                }
+                seq.end()
+            }
+            serde_json::Value::Object(map) => {
+                let mut m = ser.serialize_map(Some(map.len()))?;
+                for (k, v) in map {
+                    m.serialize_entry(k, &NativeNumbers(v))?;
+                }
+                m.end()
+            }
+            other => other.serialize(ser),
+        }
+    }
+}
+
+/// CBOR counterpart of `read_receipt`: decode a receipt into the JSON value tree the verifier uses.
+pub fn read_receipt_cbor(reader: impl Read) -> Result<serde_json::Value, VerifyError> {
+    from_cbor(reader)
+}
+
+/// `read_receipt_cbor` over a file.
+pub fn read_receipt_cbor_file(path: impl AsRef<Path>) -> Result<serde_json::Value, VerifyError> {
+    let path = path.as_ref();
+    let file = std::fs::File::open(path).map_err(|e| VerifyError::Serde(format!("{}: {}", path.display(), e)))?;
+    read_receipt_cbor(file)
+}
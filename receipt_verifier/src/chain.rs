@@ -0,0 +1,164 @@
+//! Running commitment over an append-only receipt log, so one head hash can be anchored for
+//! many receipts instead of re-hashing the whole log.
+//!
+//! The chain starts at `CHAIN_GENESIS` (32 zero bytes) and each appended receipt folds in as
+//! `head = keccak256(head || receipt_hash)`, where `receipt_hash` is the receipt hash bytes
+//! under the chain's `VerifyOptions` (sha256 of the sorted canonical form by default). The head
+//! commits to the order of the receipts as well as their content. Signatures are not checked;
+//! verify each receipt on its own first.
+
+use crate::{canonical_and_hash, hex_array, hex_bytes, keccak256, HashHex, VerifyError, VerifyOptions};
+
+/// Head of an empty chain.
+pub const CHAIN_GENESIS: [u8; 32] = [0u8; 32];
+
+/// Incremental keccak hash chain over receipt hashes.
+#[derive(Debug, Clone)]
+pub struct ReceiptChain {
+    head: [u8; 32],
+    len: u64,
+    opts: VerifyOptions,
+}
+
+impl Default for ReceiptChain {
+    fn default() -> Self {
+        ReceiptChain::new()
+    }
+}
+
+impl ReceiptChain {
+    /// Empty chain hashing receipts with the default options.
+    pub fn new() -> Self {
+        ReceiptChain::with_options(VerifyOptions::default())
+    }
+
+    /// Empty chain hashing receipts as `opts` does (`canon`, `hash`, exclusions, `sign_path`).
+    pub fn with_options(opts: VerifyOptions) -> Self {
+        ReceiptChain { head: CHAIN_GENESIS, len: 0, opts }
+    }
+
+    /// Continue a persisted chain from its `head` (32-byte hex) after `len` receipts.
+    pub fn resume(head: &str, len: u64, opts: VerifyOptions) -> Result<Self, VerifyError> {
+        Ok(ReceiptChain { head: hex_array(head, "chain head")?, len, opts })
+    }
+
+    /// Fold `receipt_val`'s receipt hash into the chain and return the new head.
+    pub fn append(&mut self, receipt_val: &serde_json::Value) -> Result<HashHex, VerifyError> {
+        let (_, rcpt_hash) = canonical_and_hash(receipt_val, &self.opts)?;
+        self.append_hash(&rcpt_hash)
+    }
+
+    /// `append` for an already computed receipt hash (hex).
+    pub fn append_hash(&mut self, receipt_hash: &str) -> Result<HashHex, VerifyError> {
+        let rcpt_hash = hex_bytes(receipt_hash, true).map_err(|e| VerifyError::Hex(format!("receipt hash: {}", e)))?;
+        let mut preimage = Vec::with_capacity(32 + rcpt_hash.len());
+        preimage.extend_from_slice(&self.head);
+        preimage.extend_from_slice(&rcpt_hash);
+        self.head = keccak256(&preimage);
+        self.len += 1;
+        Ok(self.head())
+    }
+
+    /// Current head; `CHAIN_GENESIS` before anything is appended.
+    pub fn head(&self) -> HashHex {
+        HashHex::from_bytes(&self.head)
+    }
+
+    /// Number of receipts appended (including those before `resume`).
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Recompute the chain over `receipts`, in order, and require its head to be `claimed_head`
+/// (`ChainHeadMismatch` otherwise). A reordered, missing, extra or altered receipt changes it.
+pub fn verify_chain(receipts: &[serde_json::Value], claimed_head: &str) -> Result<HashHex, VerifyError> {
+    verify_chain_with(receipts, claimed_head, &VerifyOptions::default())
+}
+
+/// `verify_chain` hashing receipts under `opts`.
+pub fn verify_chain_with(receipts: &[serde_json::Value], claimed_head: &str, opts: &VerifyOptions) -> Result<HashHex, VerifyError> {
+    let claimed = HashHex::parse(claimed_head)?;
+    let mut chain = ReceiptChain::with_options(opts.clone());
+    for receipt in receipts {
+        chain.append(receipt)?;
+    }
+    let head = chain.head();
+    if head != claimed {
+        return Err(VerifyError::ChainHeadMismatch { expected: claimed.to_string(), actual: head.to_string() });
+    }
+    Ok(head)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECEIPTS: [&str; 4] = [
+        include_str!("../../scripts/receipts/rcpt_1765770035_97f27be7.json"),
+        include_str!("../../scripts/receipts/rcpt_1765770249_cded8205.json"),
+        include_str!("../../scripts/receipts/rcpt_1765774313_ecabbf2d.json"),
+        include_str!("../../scripts/receipts/rcpt_1765778272_0cbf2a85.json"),
+    ];
+
+    fn receipts() -> Vec<serde_json::Value> {
+        RECEIPTS.iter().map(|r| serde_json::from_str(r).unwrap()).collect()
+    }
+
+    fn head_of(receipts: &[serde_json::Value]) -> HashHex {
+        let mut chain = ReceiptChain::new();
+        for r in receipts {
+            chain.append(r).unwrap();
+        }
+        chain.head()
+    }
+
+    fn assert_mismatch(receipts: &[serde_json::Value], head: &str) {
+        let err = verify_chain(receipts, head).unwrap_err();
+        assert_eq!(err.code(), "CHAIN_HEAD_MISMATCH", "{err}");
+    }
+
+    #[test]
+    fn head_commits_to_order_and_content() {
+        let all = receipts();
+        let head = head_of(&all);
+        assert_eq!(verify_chain(&all, &head).unwrap(), head);
+        assert_eq!(ReceiptChain::new().head(), HashHex::from_bytes(&CHAIN_GENESIS));
+        assert_eq!(verify_chain(&[], &HashHex::from_bytes(&CHAIN_GENESIS)).unwrap(), HashHex::from_bytes(&CHAIN_GENESIS));
+
+        let mut swapped = all.clone();
+        swapped.swap(1, 2);
+        assert_mismatch(&swapped, &head);
+        assert_mismatch(&all[..3], &head);
+        assert_mismatch(&[all.clone(), vec![all[0].clone()]].concat(), &head);
+        let mut edited = all.clone();
+        edited[2]["policy_hash"] = format!("0x{}", "00".repeat(32)).into();
+        assert_mismatch(&edited, &head);
+
+        // Fields outside the signed form (`anchor`, `receipt_sig` by default) are not committed to.
+        let mut anchored = all.clone();
+        anchored[0]["anchor"] = serde_json::json!({ "timestamp": 1 });
+        verify_chain(&anchored, &head).unwrap();
+    }
+
+    #[test]
+    fn resume_continues_a_persisted_chain() {
+        let all = receipts();
+        let mut chain = ReceiptChain::new();
+        chain.append(&all[0]).unwrap();
+        let (_, second) = canonical_and_hash(&all[1], &VerifyOptions::default()).unwrap();
+        let mid = chain.append_hash(&second).unwrap();
+        assert_eq!(chain.len(), 2);
+
+        let mut resumed = ReceiptChain::resume(&mid, 2, VerifyOptions::default()).unwrap();
+        for r in &all[2..] {
+            resumed.append(r).unwrap();
+        }
+        assert_eq!((resumed.head(), resumed.len()), (head_of(&all), 4));
+        assert!(ReceiptChain::resume("0x1234", 2, VerifyOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,79 @@
+//! Compressed receipt and prover input. gzip decoding needs the `gzip` feature; zstd streams
+//! are recognized by their magic number so they fail with a clear error instead of a JSON one.
+
+use crate::VerifyError;
+use std::io::Read;
+
+/// gzip member header (RFC 1952 ID1, ID2).
+pub const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// zstd frame magic number, little-endian.
+pub const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+/// Cap on decompressed input, so a small gzip bomb cannot exhaust memory.
+pub const MAX_DECOMPRESSED_BYTES: u64 = 64 << 20;
+
+/// Compression of receipt files and request bodies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Compression {
+    /// gzip if the input starts with `GZIP_MAGIC`, otherwise uncompressed.
+    #[default]
+    Auto,
+    None,
+    Gzip,
+}
+
+impl std::str::FromStr for Compression {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "auto" => Ok(Compression::Auto),
+            "none" => Ok(Compression::None),
+            "gzip" => Ok(Compression::Gzip),
+            other => Err(format!("unknown compression {other} (expected auto|none|gzip)")),
+        }
+    }
+}
+
+/// `bytes` decompressed per `compression`; uncompressed input is returned as is.
+pub fn decompress(bytes: Vec<u8>, compression: Compression) -> Result<Vec<u8>, VerifyError> {
+    let gzip = match compression {
+        Compression::None => false,
+        Compression::Gzip => true,
+        Compression::Auto => {
+            if bytes.starts_with(&ZSTD_MAGIC) {
+                return Err(VerifyError::Config("zstd input is not supported; decompress it first".into()));
+            }
+            bytes.starts_with(&GZIP_MAGIC)
+        }
+    };
+    if gzip {
+        gunzip(&bytes)
+    } else {
+        Ok(bytes)
+    }
+}
+
+/// Read all of `reader` and `decompress` it.
+pub fn read_decompressed(mut reader: impl Read, compression: Compression) -> Result<Vec<u8>, VerifyError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).map_err(|e| VerifyError::Serde(e.to_string()))?;
+    decompress(bytes, compression)
+}
+
+#[cfg(feature = "gzip")]
+fn gunzip(bytes: &[u8]) -> Result<Vec<u8>, VerifyError> {
+    let mut out = Vec::new();
+    // Multi-member streams (concatenated `gzip` output) decode as one.
+    flate2::read::MultiGzDecoder::new(bytes)
+        .take(MAX_DECOMPRESSED_BYTES + 1)
+        .read_to_end(&mut out)
+        .map_err(|e| VerifyError::Serde(format!("gzip: {}", e)))?;
+    if out.len() as u64 > MAX_DECOMPRESSED_BYTES {
+        return Err(VerifyError::Serde(format!("gzip: decompressed input exceeds {} bytes", MAX_DECOMPRESSED_BYTES)));
+    }
+    Ok(out)
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gunzip(_bytes: &[u8]) -> Result<Vec<u8>, VerifyError> {
+    Err(VerifyError::Config("gzip input needs the `gzip` feature".into()))
+}
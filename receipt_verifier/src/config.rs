@@ -0,0 +1,60 @@
+//! Named verification profiles loaded from a TOML file (`--config` / `--profile`).
+//!
+//! ```toml
+//! [profiles.tenant_a]
+//! gateway = "0x3a66a80ac10721e69b6a9cd56b07282d9a094813"   # or "auto" with gateway_allowlist
+//! policy_hash = "0xabab..."
+//! consent_hash = "0xcdcd..."
+//! prover = "receipt_sig"   # or an external prover command line
+//! max_age = 86400          # seconds
+//! ```
+//!
+//! Precedence: CLI flag, then the selected profile, then the built-in default.
+
+use crate::VerifyError;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Top-level config file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VerifyConfig {
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// One tenant's expectations; every field is optional so flags can fill the rest.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// Address, DID, or `auto`, as accepted by `--gateway`.
+    pub gateway: Option<String>,
+    pub policy_hash: Option<String>,
+    pub consent_hash: Option<String>,
+    /// Allowlist file of gateways accepted with `gateway = "auto"` (`--gateway-allowlist`).
+    pub gateway_allowlist: Option<std::path::PathBuf>,
+    /// `receipt_sig` for the built-in hook, otherwise the external prover command.
+    pub prover: Option<String>,
+    /// Maximum receipt age in seconds.
+    pub max_age: Option<u64>,
+}
+
+impl VerifyConfig {
+    pub fn parse(text: &str) -> Result<Self, VerifyError> {
+        toml::from_str(text).map_err(|e| VerifyError::Config(e.to_string()))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, VerifyError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| VerifyError::Config(format!("{}: {}", path.display(), e)))?;
+        Self::parse(&text).map_err(|e| VerifyError::Config(format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn profile(&self, name: &str) -> Result<&Profile, VerifyError> {
+        self.profiles.get(name).ok_or_else(|| {
+            let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            VerifyError::Config(format!("unknown profile {} (have: {})", name, known.join(", ")))
+        })
+    }
+}
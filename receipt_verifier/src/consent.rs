@@ -0,0 +1,152 @@
+//! Membership proofs for consent snapshots organised as keccak Merkle trees, whose root is the
+//! receipt's `consent_snapshot_hash`.
+//!
+//! Each proof step names the sibling hash and which side of the running hash it sits on:
+//! `left` gives `keccak256(sibling || current)`, `right` gives `keccak256(current || sibling)`.
+//! Receipts carry the steps for the consent grant they used as
+//! `"consent_proof": [{ "hash": "0x..", "position": "left" | "right" }, ..]`, leaf first.
+//!
+//! Scope checks go further than the hash: a receipt lists the scopes it exercised as
+//! `"consent_scopes": ["tools:search", ..]`, and each must be granted, unexpired, by a
+//! `ConsentDoc` (`{"scopes": [{"scope": "tools:*", "expires_at": 1767225600}, ..]}`).
+
+use crate::{hex_array, keccak256, VerifyError};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Side of the running hash a sibling sits on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Position {
+    Left,
+    Right,
+}
+
+/// One step of a membership proof; `hash` is 32 bytes of hex.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofNode {
+    pub hash: String,
+    pub position: Position,
+}
+
+fn hash32(hex_str: &str, what: &str) -> Result<[u8; 32], VerifyError> {
+    hex_array(hex_str, what)
+}
+
+/// Check that `leaf_hash` is in the tree with root `root` (both 32-byte hex), following `proof`
+/// from the leaf up. Fails with `ConsentMismatch` (expected root, recomputed root) if they differ.
+pub fn verify_consent_membership(leaf_hash: &str, proof: &[ProofNode], root: &str) -> Result<(), VerifyError> {
+    let root = hash32(root, "consent root")?;
+    let mut current = hash32(leaf_hash, "consent leaf")?;
+    for node in proof {
+        let sibling = hash32(&node.hash, "consent proof node")?;
+        let mut pair = [0u8; 64];
+        let (first, second) = match node.position {
+            Position::Left => (&sibling, &current),
+            Position::Right => (&current, &sibling),
+        };
+        pair[..32].copy_from_slice(first);
+        pair[32..].copy_from_slice(second);
+        current = keccak256(&pair);
+    }
+    if current != root {
+        return Err(VerifyError::ConsentMismatch {
+            expected: format!("0x{}", hex::encode(root)),
+            actual: format!("0x{}", hex::encode(current)),
+        });
+    }
+    Ok(())
+}
+
+/// `verify_consent_membership` using the receipt's own `consent_proof` and
+/// `consent_snapshot_hash`.
+pub fn verify_receipt_consent(receipt_val: &serde_json::Value, leaf_hash: &str) -> Result<(), VerifyError> {
+    verify_receipt_consent_at(receipt_val, leaf_hash, "/consent_snapshot_hash")
+}
+
+/// `verify_receipt_consent` with the snapshot root read from JSON pointer `root_path`
+/// (e.g. `/meta/consent_snapshot_hash`).
+pub fn verify_receipt_consent_at(receipt_val: &serde_json::Value, leaf_hash: &str, root_path: &str) -> Result<(), VerifyError> {
+    let field = |name: &str, reason: &str| VerifyError::ReceiptField { field: name.into(), reason: reason.into() };
+    let root = receipt_val
+        .pointer(root_path)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| field(root_path.trim_start_matches('/'), "missing"))?;
+    let proof = receipt_val.get("consent_proof").ok_or_else(|| field("consent_proof", "missing"))?;
+    let proof: Vec<ProofNode> =
+        serde_json::from_value(proof.clone()).map_err(|e| field("consent_proof", &e.to_string()))?;
+    verify_consent_membership(leaf_hash, &proof, root)
+}
+
+/// One granted scope. `scope` is matched exactly, except that a trailing `*` grants every scope
+/// starting with what precedes it (`tools:*` covers `tools:search`). `expires_at` is unix
+/// seconds; `None` never expires.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScopeGrant {
+    pub scope: String,
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+impl ScopeGrant {
+    fn covers(&self, scope: &str) -> bool {
+        match self.scope.strip_suffix('*') {
+            Some(prefix) => scope.starts_with(prefix),
+            None => self.scope == scope,
+        }
+    }
+}
+
+/// The scopes a consent grants (`--consent-doc`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConsentDoc {
+    pub scopes: Vec<ScopeGrant>,
+}
+
+impl ConsentDoc {
+    pub fn from_json(json: &str) -> Result<Self, VerifyError> {
+        serde_json::from_str(json).map_err(|e| VerifyError::Serde(format!("consent doc: {}", e)))
+    }
+
+    /// `from_json` over a file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, VerifyError> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|e| VerifyError::Serde(format!("{}: {}", path.display(), e)))?;
+        ConsentDoc::from_json(&json)
+    }
+}
+
+/// `verify_consent_scopes_at` at the current time.
+pub fn verify_consent_scopes(receipt_scopes: &[String], granted: &ConsentDoc) -> Result<(), VerifyError> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    verify_consent_scopes_at(receipt_scopes, granted, now)
+}
+
+/// Check that every scope in `receipt_scopes` is covered by a grant in `granted` that has not
+/// expired at `now`. Fails with `ConsentScope` naming the first scope that is not granted, or
+/// whose grants all expired (with the latest expiry).
+pub fn verify_consent_scopes_at(receipt_scopes: &[String], granted: &ConsentDoc, now: u64) -> Result<(), VerifyError> {
+    for scope in receipt_scopes {
+        let grants: Vec<&ScopeGrant> = granted.scopes.iter().filter(|g| g.covers(scope)).collect();
+        if grants.is_empty() {
+            return Err(VerifyError::ConsentScope { scope: scope.clone(), reason: "not granted".into() });
+        }
+        if grants.iter().all(|g| g.expires_at.is_some_and(|exp| exp < now)) {
+            let latest = grants.iter().filter_map(|g| g.expires_at).max().unwrap_or_default();
+            return Err(VerifyError::ConsentScope {
+                scope: scope.clone(),
+                reason: format!("grant expired at {} (now {})", latest, now),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// The receipt's `consent_scopes`, which must be an array of strings.
+pub fn receipt_consent_scopes(receipt_val: &serde_json::Value) -> Result<Vec<String>, VerifyError> {
+    let field = |reason: &str| VerifyError::ReceiptField { field: "consent_scopes".into(), reason: reason.into() };
+    let scopes = receipt_val.get("consent_scopes").ok_or_else(|| field("missing"))?;
+    serde_json::from_value(scopes.clone()).map_err(|e| field(&e.to_string()))
+}
@@ -0,0 +1,28 @@
+//! Constant-time equality for values a caller could probe byte by byte through timing.
+//!
+//! Compared in constant time (`ct_eq_bytes` / `ct_position`):
+//! - the recovered signer against the expected gateway or allowlist, the EIP-1271 gateway
+//!   and the co-signer allowlist;
+//! - a provided signature against the receipt's embedded `receipt_sig`;
+//! - redaction commitments in `verify_redaction`.
+//!
+//! Plain `==`: policy, consent and receipt hashes (`HashHex::matches`), proof and bundle
+//! fields, replay keys and the EIP-1271 magic value. They are public inputs whose comparison
+//! timing reveals nothing the output does not. Anything MAC-like added later belongs in the
+//! first list. Lengths are not hidden: slices of different lengths are unequal at once.
+
+use subtle::ConstantTimeEq;
+
+/// `a == b`, taking the same time for every pair of equal-length inputs.
+pub fn ct_eq_bytes(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
+
+/// Index of the first entry of `list` equal to `item`; every entry is compared, so the
+/// time does not depend on where (or whether) it matches.
+pub(crate) fn ct_position(list: &[[u8; 20]], item: &[u8; 20]) -> Option<usize> {
+    list.iter().enumerate().fold(None, |found, (i, entry)| match found {
+        None if ct_eq_bytes(entry, item) => Some(i),
+        found => found,
+    })
+}
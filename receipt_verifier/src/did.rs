@@ -0,0 +1,75 @@
+//! Gateway identities given as DIDs: `did:pkh:eip155:<chain>:0x<addr>` and
+//! `did:ethr[:<network>]:<0x addr | 0x compressed pubkey>`.
+
+use crate::{hex_bytes, VerifyError};
+use k256::ecdsa::VerifyingKey;
+
+/// A DID resolved to its Ethereum address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GatewayDid {
+    pub address: [u8; 20],
+    /// Chain the DID is scoped to; `did:ethr` without a network prefix means mainnet.
+    pub chain_id: u64,
+}
+
+/// Extract the 20-byte gateway address from a `did:pkh` or `did:ethr` DID.
+pub fn resolve_gateway_did(did: &str) -> Result<[u8; 20], VerifyError> {
+    parse_gateway_did(did).map(|d| d.address)
+}
+
+/// Parse a `did:pkh` or `did:ethr` DID into its address and chain id.
+pub fn parse_gateway_did(did: &str) -> Result<GatewayDid, VerifyError> {
+    let unsupported = || VerifyError::UnsupportedDid(did.to_string());
+    let parts: Vec<&str> = did.split(':').collect();
+    match parts.as_slice() {
+        ["did", "pkh", "eip155", chain, addr] => Ok(GatewayDid {
+            address: decode_address(addr, did)?,
+            chain_id: chain.parse().map_err(|_| bad_did(did, "chain id must be a decimal integer"))?,
+        }),
+        ["did", "pkh", ..] => Err(unsupported()),
+        ["did", "ethr", id] => Ok(GatewayDid { address: decode_ethr_id(id, did)?, chain_id: 1 }),
+        ["did", "ethr", network, id] => Ok(GatewayDid {
+            address: decode_ethr_id(id, did)?,
+            chain_id: ethr_chain_id(network).ok_or_else(|| bad_did(did, "unknown network"))?,
+        }),
+        _ => Err(unsupported()),
+    }
+}
+
+fn bad_did(did: &str, reason: &str) -> VerifyError {
+    VerifyError::GatewayParse(format!("{}: {}", did, reason))
+}
+
+/// `did:ethr` networks are a hex chain id (`0x5`) or one of the registry's well-known names.
+fn ethr_chain_id(network: &str) -> Option<u64> {
+    if let Some(hex) = network.strip_prefix("0x") {
+        return u64::from_str_radix(hex, 16).ok();
+    }
+    Some(match network {
+        "mainnet" => 1,
+        "goerli" => 5,
+        "sepolia" => 11155111,
+        "polygon" => 137,
+        "optimism" => 10,
+        "arbitrum" => 42161,
+        _ => return None,
+    })
+}
+
+fn decode_address(addr: &str, did: &str) -> Result<[u8; 20], VerifyError> {
+    let bytes = hex_bytes(addr, false).map_err(|e| bad_did(did, &e))?;
+    bytes.try_into().map_err(|_| bad_did(did, "address must be 20 bytes"))
+}
+
+/// `did:ethr` identifiers are either an address or a compressed secp256k1 public key.
+fn decode_ethr_id(id: &str, did: &str) -> Result<[u8; 20], VerifyError> {
+    let bytes = hex_bytes(id, false).map_err(|e| bad_did(did, &e))?;
+    match bytes.len() {
+        20 => decode_address(id, did),
+        33 => {
+            let vk = VerifyingKey::from_sec1_bytes(&bytes).map_err(|e| bad_did(did, &e.to_string()))?;
+            Ok(crate::address_of_key(&vk))
+        }
+        _ => Err(bad_did(did, "identifier must be an address or compressed public key")),
+    }
+}
@@ -0,0 +1,157 @@
+//! Ed25519 signatures for `did:key` gateways (`ed25519` feature).
+//!
+//! The gateway is a `did:key:z6Mk..` DID (multibase base58btc of the `ed25519-pub` multicodec
+//! `0xed 0x01` followed by the 32-byte key) or the key itself as 32 bytes of hex. The gateway
+//! signs the canonical receipt bytes directly, with no prehash and no EIP-191 prefix, under
+//! the same exclusions and canonicalization as secp256k1 receipts. Signatures are 64 bytes,
+//! hex or base64, checked with `verify_strict` (no small-order keys, canonical `s`).
+
+use crate::{
+    canonical_and_hash, check_claims, check_embedded_sig, check_expected_receipt_hash, decode_signature, hex_bytes,
+    parse_receipt, receipt_id_of, telemetry, HashHex, HashScheme, PublicInputs, ReceiptV1, SigEncoding, VerifiedReceipt,
+    VerifyError, VerifyOptions, Witness,
+};
+use ed25519_dalek::{Signature, VerifyingKey};
+
+/// Multicodec prefix of an Ed25519 public key (`ed25519-pub`, varint `0xed`).
+const ED25519_MULTICODEC: [u8; 2] = [0xed, 0x01];
+
+/// Signature algorithm of a receipt (`--sig-alg`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SigAlg {
+    /// Ed25519 for `did:key` Ed25519 gateways, secp256k1 otherwise.
+    #[default]
+    Auto,
+    Secp256k1,
+    Ed25519,
+}
+
+impl SigAlg {
+    /// The algorithm for `gateway`; `Auto` picks Ed25519 for `did:key:z6Mk..` gateways.
+    pub fn resolve(self, gateway: &str) -> SigAlg {
+        match self {
+            SigAlg::Auto if gateway.starts_with("did:key:z6Mk") => SigAlg::Ed25519,
+            SigAlg::Auto => SigAlg::Secp256k1,
+            alg => alg,
+        }
+    }
+}
+
+impl std::str::FromStr for SigAlg {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "auto" => Ok(SigAlg::Auto),
+            "secp256k1" => Ok(SigAlg::Secp256k1),
+            "ed25519" => Ok(SigAlg::Ed25519),
+            other => Err(format!("unknown signature algorithm {other} (expected auto|secp256k1|ed25519)")),
+        }
+    }
+}
+
+/// The Ed25519 public key of a `did:key` DID.
+pub fn parse_did_key(did: &str) -> Result<[u8; 32], VerifyError> {
+    let bad = |reason: &str| VerifyError::GatewayParse(format!("{}: {}", did, reason));
+    let multibase = did.strip_prefix("did:key:").ok_or_else(|| VerifyError::UnsupportedDid(did.to_string()))?;
+    let encoded = multibase.strip_prefix('z').ok_or_else(|| bad("only base58btc (`z`) multibase keys are supported"))?;
+    let bytes = bs58::decode(encoded).into_vec().map_err(|e| bad(&e.to_string()))?;
+    let key = bytes
+        .strip_prefix(&ED25519_MULTICODEC[..])
+        .ok_or_else(|| VerifyError::UnsupportedDid(format!("{} is not an Ed25519 key", did)))?;
+    key.try_into().map_err(|_| bad(&format!("Ed25519 key must be 32 bytes, got {}", key.len())))
+}
+
+/// `did:key` DID of Ed25519 public key `key`.
+pub fn did_key(key: &[u8; 32]) -> String {
+    let mut bytes = ED25519_MULTICODEC.to_vec();
+    bytes.extend_from_slice(key);
+    format!("did:key:z{}", bs58::encode(bytes).into_string())
+}
+
+/// The gateway key named by `gateway`: a `did:key` DID or 32 bytes of hex.
+pub fn parse_ed25519_gateway(gateway: &str) -> Result<[u8; 32], VerifyError> {
+    if gateway.starts_with("did:") {
+        return parse_did_key(gateway);
+    }
+    let bytes = hex_bytes(gateway, false).map_err(VerifyError::GatewayParse)?;
+    let len = bytes.len();
+    bytes.try_into().map_err(|_| VerifyError::GatewayParse(format!("Ed25519 public key must be 32 bytes, got {}", len)))
+}
+
+/// Check Ed25519 signature `sig` (64 bytes) by `pubkey` over `canonical_bytes`.
+pub fn verify_ed25519(canonical_bytes: &[u8], sig: &[u8], pubkey: &[u8; 32]) -> Result<(), VerifyError> {
+    let sig: &[u8; 64] =
+        sig.try_into().map_err(|_| VerifyError::Sig(format!("Ed25519 signature must be 64 bytes, got {}", sig.len())))?;
+    let key = VerifyingKey::from_bytes(pubkey).map_err(|e| VerifyError::GatewayParse(format!("Ed25519 public key: {}", e)))?;
+    key.verify_strict(canonical_bytes, &Signature::from_bytes(sig))
+        .map_err(|_| VerifyError::Sig("Ed25519 signature does not verify against the public key".into()))
+}
+
+/// A receipt whose Ed25519 signature and claims checked out.
+#[derive(Debug, Clone)]
+pub struct Ed25519VerifiedReceipt {
+    pub receipt: ReceiptV1,
+    pub canonical: String,
+    /// Receipt hash (hex, no `0x`) under the options' `HashScheme`.
+    pub receipt_hash: String,
+    /// The gateway's Ed25519 public key.
+    pub public_key: [u8; 32],
+    /// Scheme `receipt_hash` was computed with.
+    pub hash_scheme: HashScheme,
+}
+
+impl Ed25519VerifiedReceipt {
+    /// `receipt_hash` in the `0x` form used in public inputs and outputs.
+    pub fn receipt_hash_hex(&self) -> HashHex {
+        HashHex::normalize(&self.receipt_hash)
+    }
+
+    /// Public inputs and witness; `gateway_address` is the full 32-byte public key.
+    pub fn public_and_witness(&self, signature: &str) -> (PublicInputs, Witness) {
+        let (mut pub_inputs, witness) = self.as_verified().public_and_witness(signature);
+        pub_inputs.gateway_address = format!("0x{}", hex::encode(self.public_key));
+        pub_inputs.gateway_address_checksum = None;
+        (pub_inputs, witness)
+    }
+
+    fn as_verified(&self) -> VerifiedReceipt {
+        VerifiedReceipt {
+            receipt: self.receipt.clone(),
+            canonical: self.canonical.clone(),
+            receipt_hash: self.receipt_hash.clone(),
+            signer: low_address(&self.public_key),
+            chain_id: None,
+            hash_scheme: self.hash_scheme.clone(),
+            digest_mode: None,
+        }
+    }
+}
+
+/// Low 20 bytes of the key, used where a 20-byte signer is needed (replay keys, the
+/// allowlist and the registry), as for Stark gateways.
+fn low_address(key: &[u8; 32]) -> [u8; 20] {
+    key[12..].try_into().expect("20 bytes")
+}
+
+/// `verify_receipt_full` for an Ed25519-signed receipt: `gateway` is a `did:key` DID or the
+/// hex public key; `opts.scheme`, `digest`, `allow_high_s` and `expected_chain_id` do not apply.
+pub fn verify_receipt_ed25519(
+    receipt_val: &serde_json::Value,
+    signature: &str,
+    gateway: &str,
+    expected_policy_hash: &str,
+    expected_consent_hash: &str,
+    opts: &VerifyOptions,
+) -> Result<Ed25519VerifiedReceipt, VerifyError> {
+    telemetry::observe_verify(|| {
+        let _span = tracing::info_span!("verify_ed25519", receipt_id = receipt_id_of(receipt_val), gateway).entered();
+        let receipt = parse_receipt(receipt_val, opts)?;
+        check_embedded_sig(receipt_val, signature, opts)?;
+        let (canonical, receipt_hash) = canonical_and_hash(receipt_val, opts)?;
+        check_expected_receipt_hash(&receipt_hash, opts)?;
+        let public_key = parse_ed25519_gateway(gateway)?;
+        verify_ed25519(canonical.as_bytes(), &decode_signature(signature, SigEncoding::Auto)?, &public_key)?;
+        check_claims(&receipt, &receipt_hash, low_address(&public_key), expected_policy_hash, expected_consent_hash, opts)?;
+        Ok(Ed25519VerifiedReceipt { receipt, canonical, receipt_hash, public_key, hash_scheme: opts.hash.clone() })
+    })
+}
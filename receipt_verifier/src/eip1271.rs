@@ -0,0 +1,157 @@
+//! EIP-1271 signature checks for smart-contract gateways (Safe, Argent, ...).
+
+use crate::VerifyError;
+
+/// `bytes4(keccak256("isValidSignature(bytes32,bytes)"))`, also the success return value.
+pub const EIP1271_MAGIC: [u8; 4] = [0x16, 0x26, 0xba, 0x7e];
+
+/// Minimal JSON-RPC surface needed for on-chain checks, so callers can plug in their own client.
+pub trait EthCall {
+    /// `eth_call` at the latest block; returns the raw return data.
+    /// A reverted call should come back as `Ok` with empty data, not as a transport error.
+    fn call(&self, to: [u8; 20], data: &[u8]) -> Result<Vec<u8>, VerifyError>;
+    /// `eth_getCode` at the latest block; empty means `addr` is an EOA.
+    fn code(&self, addr: [u8; 20]) -> Result<Vec<u8>, VerifyError>;
+    /// Any other JSON-RPC method, returning its `result`; the anchor and registry checks need
+    /// it. Clients that only do `eth_call` / `eth_getCode` can leave the default, which fails.
+    fn request(&self, method: &str, _params: serde_json::Value) -> Result<serde_json::Value, VerifyError> {
+        Err(VerifyError::Rpc(format!("{}: not supported by this client", method)))
+    }
+}
+
+/// ABI-encode `isValidSignature(bytes32 hash, bytes signature)`.
+pub fn is_valid_signature_calldata(hash: [u8; 32], signature: &[u8]) -> Vec<u8> {
+    let padded = signature.len().div_ceil(32) * 32;
+    let mut data = Vec::with_capacity(4 + 32 * 3 + padded);
+    data.extend_from_slice(&EIP1271_MAGIC);
+    data.extend_from_slice(&hash);
+    data.extend_from_slice(&abi_word(0x40));
+    data.extend_from_slice(&abi_word(signature.len() as u64));
+    data.extend_from_slice(signature);
+    data.resize(4 + 32 * 3 + padded, 0);
+    data
+}
+
+fn abi_word(v: u64) -> [u8; 32] {
+    let mut w = [0u8; 32];
+    w[24..].copy_from_slice(&v.to_be_bytes());
+    w
+}
+
+/// Call `isValidSignature` on `contract` and require the magic value back.
+pub fn is_valid_signature(
+    eth: &dyn EthCall,
+    contract: [u8; 20],
+    hash: [u8; 32],
+    signature: &[u8],
+) -> Result<(), VerifyError> {
+    let ret = eth.call(contract, &is_valid_signature_calldata(hash, signature))?;
+    // bytes4 return values are left-aligned in a 32-byte word.
+    if ret.len() >= 4 && ret[..4] == EIP1271_MAGIC {
+        Ok(())
+    } else {
+        Err(VerifyError::ContractSigInvalid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_gateway, signing_digest, verify_receipt_1271_full, VerifiedReceipt, VerifyOptions};
+    use std::cell::RefCell;
+
+    const RECEIPT: &str = include_str!("../../scripts/receipts/rcpt_1765778272_0cbf2a85.json");
+    const CONTRACT: [u8; 20] = [0xaa; 20];
+
+    /// Answers `eth_getCode` with `code` and `eth_call` with `ret`, recording every call.
+    struct MockEth {
+        code: Vec<u8>,
+        ret: Vec<u8>,
+        calls: RefCell<Vec<([u8; 20], Vec<u8>)>>,
+    }
+
+    impl MockEth {
+        fn new(code: &[u8], ret: &[u8]) -> Self {
+            Self { code: code.to_vec(), ret: ret.to_vec(), calls: RefCell::new(Vec::new()) }
+        }
+    }
+
+    impl EthCall for MockEth {
+        fn call(&self, to: [u8; 20], data: &[u8]) -> Result<Vec<u8>, VerifyError> {
+            self.calls.borrow_mut().push((to, data.to_vec()));
+            Ok(self.ret.clone())
+        }
+
+        fn code(&self, _addr: [u8; 20]) -> Result<Vec<u8>, VerifyError> {
+            Ok(self.code.clone())
+        }
+    }
+
+    fn verify(gateway: [u8; 20], eth: &dyn EthCall) -> Result<VerifiedReceipt, VerifyError> {
+        let receipt: serde_json::Value = serde_json::from_str(RECEIPT).unwrap();
+        let sig = receipt["receipt_sig"].as_str().unwrap().to_string();
+        let (policy, consent) = (format!("0x{}", "ab".repeat(32)), format!("0x{}", "cd".repeat(32)));
+        verify_receipt_1271_full(&receipt, &sig, gateway, &policy, &consent, &VerifyOptions::default(), eth)
+    }
+
+    fn magic_word() -> Vec<u8> {
+        let mut word = EIP1271_MAGIC.to_vec();
+        word.resize(32, 0);
+        word
+    }
+
+    #[test]
+    fn calldata_pads_the_signature() {
+        let data = is_valid_signature_calldata([0x11; 32], &[0x22; 65]);
+        assert_eq!(data.len(), 4 + 32 * 3 + 96);
+        assert_eq!(data[..4], EIP1271_MAGIC);
+        assert_eq!(data[4..36], [0x11; 32]);
+        assert_eq!(data[67], 0x40);
+        assert_eq!(data[99], 65);
+        assert_eq!(data[100..165], [0x22; 65]);
+        assert!(data[165..].iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn contract_gateway_is_asked_for_the_signing_digest() {
+        let eth = MockEth::new(&[0x60, 0x80], &magic_word());
+        let verified = verify(CONTRACT, &eth).unwrap();
+        assert_eq!(verified.signer, CONTRACT);
+        assert_eq!(verified.chain_id, None);
+
+        let receipt: serde_json::Value = serde_json::from_str(RECEIPT).unwrap();
+        let (_, _, digest) = signing_digest(&receipt, &VerifyOptions::default()).unwrap();
+        let sig = hex::decode(receipt["receipt_sig"].as_str().unwrap()).unwrap();
+        assert_eq!(*eth.calls.borrow(), vec![(CONTRACT, is_valid_signature_calldata(digest, &sig))]);
+    }
+
+    #[test]
+    fn contract_rejection_is_contract_sig_invalid() {
+        for ret in [&[][..], &[0xff; 32][..], &EIP1271_MAGIC[..3]] {
+            let eth = MockEth::new(&[0x60], ret);
+            assert!(matches!(verify(CONTRACT, &eth), Err(VerifyError::ContractSigInvalid)));
+        }
+    }
+
+    #[test]
+    fn eoa_gateway_is_not_called() {
+        let eth = MockEth::new(&[], &magic_word());
+        assert!(matches!(verify(CONTRACT, &eth), Err(VerifyError::AddressMismatch { .. })));
+        assert!(eth.calls.borrow().is_empty());
+    }
+
+    #[test]
+    fn recovered_gateway_skips_the_rpc() {
+        struct Unreachable;
+        impl EthCall for Unreachable {
+            fn call(&self, _: [u8; 20], _: &[u8]) -> Result<Vec<u8>, VerifyError> {
+                panic!("eth_call for an EOA signature")
+            }
+            fn code(&self, _: [u8; 20]) -> Result<Vec<u8>, VerifyError> {
+                panic!("eth_getCode for an EOA signature")
+            }
+        }
+        let gateway = parse_gateway("0x3A66A80Ac10721e69b6A9Cd56B07282D9a094813").unwrap();
+        assert_eq!(verify(gateway, &Unreachable).unwrap().signer, gateway);
+    }
+}
@@ -0,0 +1,340 @@
+//! EIP-712 typed-data hashing for receipts signed with `eth_signTypedData_v4`.
+//!
+//! The message is the receipt itself: each field declared on the primary type is
+//! looked up by name among the receipt's top-level keys. Undeclared keys are not
+//! part of the signed struct and are ignored.
+
+use crate::{hex_bytes, keccak256, normalize_hex_even, VerifyError};
+use hex::FromHex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// One `{ name, type }` member of an EIP-712 struct type.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Eip712Field {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+/// EIP-712 domain. Only the fields that are set become members of `EIP712Domain`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Eip712Domain {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub chain_id: Option<u64>,
+    pub verifying_contract: Option<String>,
+    pub salt: Option<String>,
+}
+
+/// Declared struct types for the receipt, keyed by type name, plus the primary type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReceiptType {
+    #[serde(rename = "primaryType")]
+    pub primary_type: String,
+    pub types: BTreeMap<String, Vec<Eip712Field>>,
+}
+
+/// On-disk layout matching the `eth_signTypedData_v4` payload minus `message`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Eip712Schema {
+    pub domain: Eip712Domain,
+    #[serde(rename = "primaryType")]
+    pub primary_type: String,
+    pub types: BTreeMap<String, Vec<Eip712Field>>,
+}
+
+impl Eip712Schema {
+    pub fn into_parts(self) -> (Eip712Domain, ReceiptType) {
+        let mut types = self.types;
+        // The domain type is always derived from the populated domain fields.
+        types.remove("EIP712Domain");
+        (self.domain, ReceiptType { primary_type: self.primary_type, types })
+    }
+}
+
+fn err(msg: impl Into<String>) -> VerifyError {
+    VerifyError::Serde(format!("eip712: {}", msg.into()))
+}
+
+impl Eip712Domain {
+    fn as_struct(&self) -> (Vec<Eip712Field>, serde_json::Value) {
+        let mut fields = Vec::new();
+        let mut obj = serde_json::Map::new();
+        let mut push = |name: &str, ty: &str, v: serde_json::Value| {
+            fields.push(Eip712Field { name: name.into(), ty: ty.into() });
+            obj.insert(name.into(), v);
+        };
+        if let Some(n) = &self.name {
+            push("name", "string", n.clone().into());
+        }
+        if let Some(v) = &self.version {
+            push("version", "string", v.clone().into());
+        }
+        if let Some(c) = self.chain_id {
+            push("chainId", "uint256", c.into());
+        }
+        if let Some(a) = &self.verifying_contract {
+            push("verifyingContract", "address", a.clone().into());
+        }
+        if let Some(s) = &self.salt {
+            push("salt", "bytes32", s.clone().into());
+        }
+        (fields, serde_json::Value::Object(obj))
+    }
+
+    /// hashStruct(EIP712Domain) over the populated domain fields.
+    pub fn separator(&self) -> Result<[u8; 32], VerifyError> {
+        let (fields, value) = self.as_struct();
+        let mut types = BTreeMap::new();
+        types.insert("EIP712Domain".to_string(), fields);
+        hash_struct(&types, "EIP712Domain", &value)
+    }
+}
+
+impl ReceiptType {
+    /// encodeType for the primary type (primary first, then referenced types sorted by name).
+    pub fn encode_type(&self) -> Result<String, VerifyError> {
+        encode_type(&self.types, &self.primary_type)
+    }
+
+    /// hashStruct(primaryType, receipt).
+    pub fn hash_struct(&self, receipt_val: &serde_json::Value) -> Result<[u8; 32], VerifyError> {
+        hash_struct(&self.types, &self.primary_type, receipt_val)
+    }
+
+    /// Whether the primary type has a field with any of these names.
+    pub(crate) fn covers_any(&self, names: &[&str]) -> bool {
+        self.types
+            .get(&self.primary_type)
+            .is_some_and(|fields| fields.iter().any(|f| names.contains(&f.name.as_str())))
+    }
+}
+
+/// EIP-712 signing digest: keccak256(0x19 0x01 || domainSeparator || hashStruct(message)).
+pub fn eip712_digest(
+    domain: &Eip712Domain,
+    receipt_type: &ReceiptType,
+    receipt_val: &serde_json::Value,
+) -> Result<[u8; 32], VerifyError> {
+    let mut buf = Vec::with_capacity(66);
+    buf.extend_from_slice(&[0x19, 0x01]);
+    buf.extend_from_slice(&domain.separator()?);
+    buf.extend_from_slice(&receipt_type.hash_struct(receipt_val)?);
+    Ok(keccak256(&buf))
+}
+
+/// Strip one trailing `[..]` array suffix, if any.
+fn array_base(ty: &str) -> Option<(&str, Option<usize>)> {
+    if !ty.ends_with(']') {
+        return None;
+    }
+    let open = ty.rfind('[')?;
+    let len = &ty[open + 1..ty.len() - 1];
+    let len = if len.is_empty() { None } else { len.parse().ok() };
+    Some((&ty[..open], len))
+}
+
+fn struct_name(ty: &str) -> &str {
+    match ty.find('[') {
+        Some(i) => &ty[..i],
+        None => ty,
+    }
+}
+
+fn collect_deps<'a>(
+    types: &'a BTreeMap<String, Vec<Eip712Field>>,
+    ty: &'a str,
+    out: &mut BTreeSet<&'a str>,
+) -> Result<(), VerifyError> {
+    if out.contains(ty) {
+        return Ok(());
+    }
+    let fields = types.get(ty).ok_or_else(|| err(format!("unknown struct type {}", ty)))?;
+    out.insert(ty);
+    for f in fields {
+        let base = struct_name(&f.ty);
+        if types.contains_key(base) {
+            collect_deps(types, base, out)?;
+        }
+    }
+    Ok(())
+}
+
+fn encode_type(types: &BTreeMap<String, Vec<Eip712Field>>, primary: &str) -> Result<String, VerifyError> {
+    let mut deps = BTreeSet::new();
+    collect_deps(types, primary, &mut deps)?;
+    deps.remove(primary);
+    let mut out = String::new();
+    for name in std::iter::once(primary).chain(deps) {
+        let members: Vec<String> = types[name].iter().map(|f| format!("{} {}", f.ty, f.name)).collect();
+        out.push_str(&format!("{}({})", name, members.join(",")));
+    }
+    Ok(out)
+}
+
+fn hash_struct(
+    types: &BTreeMap<String, Vec<Eip712Field>>,
+    ty: &str,
+    val: &serde_json::Value,
+) -> Result<[u8; 32], VerifyError> {
+    let obj = val.as_object().ok_or_else(|| err(format!("{} must be an object", ty)))?;
+    let fields = types.get(ty).ok_or_else(|| err(format!("unknown struct type {}", ty)))?;
+    let mut buf = Vec::with_capacity(32 * (fields.len() + 1));
+    buf.extend_from_slice(&keccak256(encode_type(types, ty)?.as_bytes()));
+    for f in fields {
+        let v = obj.get(&f.name).ok_or_else(|| err(format!("missing field {}.{}", ty, f.name)))?;
+        buf.extend_from_slice(&encode_value(types, &f.ty, v)?);
+    }
+    Ok(keccak256(&buf))
+}
+
+fn encode_value(
+    types: &BTreeMap<String, Vec<Eip712Field>>,
+    ty: &str,
+    val: &serde_json::Value,
+) -> Result<[u8; 32], VerifyError> {
+    if let Some((base, len)) = array_base(ty) {
+        let items = val.as_array().ok_or_else(|| err(format!("{} must be an array", ty)))?;
+        if let Some(n) = len {
+            if items.len() != n {
+                return Err(err(format!("{} expects {} items, got {}", ty, n, items.len())));
+            }
+        }
+        let mut buf = Vec::with_capacity(32 * items.len());
+        for item in items {
+            buf.extend_from_slice(&encode_value(types, base, item)?);
+        }
+        return Ok(keccak256(&buf));
+    }
+    if types.contains_key(ty) {
+        return hash_struct(types, ty, val);
+    }
+    match ty {
+        "string" => {
+            let s = val.as_str().ok_or_else(|| err("string field must be a JSON string"))?;
+            Ok(keccak256(s.as_bytes()))
+        }
+        "bytes" => Ok(keccak256(&hex_field(val, ty)?)),
+        "bool" => {
+            let b = val.as_bool().ok_or_else(|| err("bool field must be a JSON bool"))?;
+            let mut out = [0u8; 32];
+            out[31] = b as u8;
+            Ok(out)
+        }
+        "address" => {
+            let bytes = hex_field(val, ty)?;
+            if bytes.len() != 20 {
+                return Err(err(format!("address must be 20 bytes, got {}", bytes.len())));
+            }
+            let mut out = [0u8; 32];
+            out[12..].copy_from_slice(&bytes);
+            Ok(out)
+        }
+        _ if ty.starts_with("bytes") => {
+            let n: usize = ty[5..].parse().map_err(|_| err(format!("unsupported type {}", ty)))?;
+            if !(1..=32).contains(&n) {
+                return Err(err(format!("unsupported type {}", ty)));
+            }
+            let bytes = hex_field(val, ty)?;
+            if bytes.len() != n {
+                return Err(err(format!("{} expects {} bytes, got {}", ty, n, bytes.len())));
+            }
+            let mut out = [0u8; 32];
+            out[..n].copy_from_slice(&bytes);
+            Ok(out)
+        }
+        _ if ty.starts_with("uint") || ty.starts_with("int") => encode_integer(ty, val),
+        _ => Err(err(format!("unsupported type {}", ty))),
+    }
+}
+
+fn hex_field(val: &serde_json::Value, ty: &str) -> Result<Vec<u8>, VerifyError> {
+    let s = val.as_str().ok_or_else(|| err(format!("{} field must be a hex string", ty)))?;
+    Vec::from_hex(normalize_hex_even(s)).map_err(|e| VerifyError::Hex(e.to_string()))
+}
+
+/// Encode a JSON number or decimal/`0x` hex string as a 256-bit two's-complement word.
+fn encode_integer(ty: &str, val: &serde_json::Value) -> Result<[u8; 32], VerifyError> {
+    let signed = ty.starts_with("int");
+    let bits: usize = {
+        let digits = if signed { &ty[3..] } else { &ty[4..] };
+        if digits.is_empty() {
+            256
+        } else {
+            digits.parse().map_err(|_| err(format!("unsupported type {}", ty)))?
+        }
+    };
+    if bits == 0 || bits > 256 || !bits.is_multiple_of(8) {
+        return Err(err(format!("unsupported type {}", ty)));
+    }
+    let text = match val {
+        serde_json::Value::Number(n) => n.to_string(),
+        serde_json::Value::String(s) => s.clone(),
+        _ => return Err(err(format!("{} field must be a number or string", ty))),
+    };
+    let (negative, magnitude) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.as_str()),
+    };
+    if negative && !signed {
+        return Err(err(format!("{} cannot be negative", ty)));
+    }
+    let mut word = parse_u256(magnitude).ok_or_else(|| err(format!("invalid integer {} for {}", text, ty)))?;
+    let nbytes = bits / 8;
+    // Magnitude bound: uintN < 2^N, intN in [-2^(N-1), 2^(N-1)).
+    let mut fits = word[..32 - nbytes].iter().all(|b| *b == 0);
+    if signed && word[32 - nbytes] & 0x80 != 0 {
+        fits &= negative && is_min_signed(&word, nbytes);
+    }
+    if !fits {
+        return Err(err(format!("{} out of range for {}", text, ty)));
+    }
+    if negative {
+        for b in word.iter_mut() {
+            *b = !*b;
+        }
+        for b in word.iter_mut().rev() {
+            let (v, carry) = b.overflowing_add(1);
+            *b = v;
+            if !carry {
+                break;
+            }
+        }
+    }
+    Ok(word)
+}
+
+/// True when the low `nbytes` hold exactly 2^(8*nbytes - 1), the magnitude of intN's minimum.
+fn is_min_signed(word: &[u8; 32], nbytes: usize) -> bool {
+    let low = &word[32 - nbytes..];
+    low[0] == 0x80 && low[1..].iter().all(|b| *b == 0)
+}
+
+fn parse_u256(s: &str) -> Option<[u8; 32]> {
+    let mut out = [0u8; 32];
+    if let Some(h) = s.strip_prefix("0x") {
+        if h.is_empty() || h.len() > 64 {
+            return None;
+        }
+        let bytes = hex_bytes(h, true).ok()?;
+        out[32 - bytes.len()..].copy_from_slice(&bytes);
+        return Some(out);
+    }
+    if s.is_empty() || !s.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    for d in s.bytes() {
+        let mut carry = (d - b'0') as u16;
+        for b in out.iter_mut().rev() {
+            let v = (*b as u16) * 10 + carry;
+            *b = v as u8;
+            carry = v >> 8;
+        }
+        if carry != 0 {
+            return None;
+        }
+    }
+    Some(out)
+}
@@ -0,0 +1,166 @@
+//! Witness as an array of field elements, the layout the Stwo circuit ingests.
+//!
+//! Packing, which the circuit's witness layout must mirror:
+//! - `k = (bits(p) - 1) / 8` bytes per element (3 for M31), so every chunk is below `p`;
+//! - a byte string becomes `[len]` followed by its bytes in `k`-byte chunks, each read as a
+//!   big-endian integer (the last chunk may be shorter; `len` says by how much);
+//! - the witness is the canonical receipt string followed by the raw signature bytes:
+//!   `[len(canonical), canonical chunks.., len(sig), sig chunks..]`;
+//! - each element is written as `0x` + big-endian hex, `ceil(bits(p) / 8)` bytes wide.
+//!
+//! `receipt_id` and `anchor_tx_hash` are not included; they are already in the canonical receipt.
+
+use crate::{hex_bytes, VerifyError, Witness};
+
+/// Prime field the elements live in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Field {
+    pub prime: u64,
+}
+
+impl Default for Field {
+    /// Stwo's M31, `2^31 - 1`.
+    fn default() -> Self {
+        Field { prime: (1 << 31) - 1 }
+    }
+}
+
+impl Field {
+    fn bits(&self) -> u32 {
+        64 - self.prime.leading_zeros()
+    }
+
+    /// Bytes packed into one field element.
+    pub fn bytes_per_felt(&self) -> usize {
+        ((self.bits() - 1) / 8) as usize
+    }
+
+    fn felt_bytes(&self) -> usize {
+        self.bits().div_ceil(8) as usize
+    }
+
+    fn validate(&self) -> Result<(), VerifyError> {
+        if self.prime < 1 << 9 {
+            return Err(VerifyError::Serde(format!("field prime {} is below 2^9", self.prime)));
+        }
+        Ok(())
+    }
+
+    /// `[len(bytes)]` followed by the big-endian chunks of `bytes`.
+    pub fn pack(&self, bytes: &[u8]) -> Result<Vec<u64>, VerifyError> {
+        self.validate()?;
+        let len = bytes.len() as u64;
+        if len >= self.prime {
+            return Err(VerifyError::Serde(format!("input of {} bytes exceeds the field size", len)));
+        }
+        let mut felts = vec![len];
+        felts.extend(
+            bytes
+                .chunks(self.bytes_per_felt())
+                .map(|c| c.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64)),
+        );
+        Ok(felts)
+    }
+
+    /// Inverse of `pack` for the byte string at the front of `felts`; returns the bytes and the
+    /// number of elements consumed.
+    pub fn unpack(&self, felts: &[u64]) -> Result<(Vec<u8>, usize), VerifyError> {
+        self.validate()?;
+        let bad = |m: &str| VerifyError::Serde(format!("witness felts: {}", m));
+        let (&len, rest) = felts.split_first().ok_or_else(|| bad("missing length element"))?;
+        let len = usize::try_from(len).map_err(|_| bad("length out of range"))?;
+        let k = self.bytes_per_felt();
+        let count = len.div_ceil(k);
+        if rest.len() < count {
+            return Err(bad("fewer elements than the length needs"));
+        }
+        let mut bytes = Vec::with_capacity(len);
+        for (i, felt) in rest[..count].iter().enumerate() {
+            let width = k.min(len - i * k);
+            if width < 8 && *felt >> (8 * width) != 0 {
+                return Err(bad("element does not fit its chunk"));
+            }
+            bytes.extend_from_slice(&felt.to_be_bytes()[8 - width..]);
+        }
+        Ok((bytes, 1 + count))
+    }
+
+    fn felt_hex(&self, felt: u64) -> String {
+        format!("0x{}", hex::encode(&felt.to_be_bytes()[8 - self.felt_bytes()..]))
+    }
+}
+
+/// The witness as hex field elements, laid out as described in the module docs.
+pub fn witness_felts(witness: &Witness, field: Field) -> Result<Vec<String>, VerifyError> {
+    let sig = hex_bytes(&witness.signature_hex, false).map_err(|e| VerifyError::Hex(format!("signature_hex: {}", e)))?;
+    let mut felts = field.pack(witness.canonical_receipt.as_bytes())?;
+    felts.extend(field.pack(&sig)?);
+    Ok(felts.into_iter().map(|f| field.felt_hex(f)).collect())
+}
+
+/// Recover `(canonical receipt bytes, signature bytes)` from `witness_felts` output.
+pub fn witness_from_felts(felts: &[String], field: Field) -> Result<(Vec<u8>, Vec<u8>), VerifyError> {
+    let values = felts
+        .iter()
+        .map(|f| u64::from_str_radix(f.trim_start_matches("0x"), 16).map_err(|e| VerifyError::Hex(format!("{}: {}", f, e))))
+        .collect::<Result<Vec<_>, _>>()?;
+    let (canonical, used) = field.unpack(&values)?;
+    let (sig, used_sig) = field.unpack(&values[used..])?;
+    if used + used_sig != values.len() {
+        return Err(VerifyError::Serde("witness felts: trailing elements".into()));
+    }
+    Ok((canonical, sig))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn witness(canonical: &str, sig: &[u8]) -> Witness {
+        Witness {
+            canonical_receipt: canonical.into(),
+            signature_hex: format!("0x{}", hex::encode(sig)),
+            receipt_id: None,
+            anchor_tx_hash: None,
+            signature_commitment: None,
+        }
+    }
+
+    #[test]
+    fn felts_round_trip_across_chunk_boundaries() {
+        let sig: Vec<u8> = (0..65).map(|i| 0xff - i).collect();
+        // M31 (3 bytes per element), a 2-byte field, and one just below 2^64 (7 bytes).
+        for field in [Field::default(), Field { prime: 65_521 }, Field { prime: u64::MAX - 58 }] {
+            let felt_digits = 2 * field.felt_bytes();
+            for len in 0..=3 * field.bytes_per_felt() + 1 {
+                let canonical: String = "{\"a\":\"\u{e9}\"}".chars().cycle().take(len).collect();
+                let felts = witness_felts(&witness(&canonical, &sig[..len.clamp(1, 65)]), field).unwrap();
+                assert!(felts.iter().all(|f| f.len() == 2 + felt_digits && f.starts_with("0x")), "{felts:?}");
+                let values: Vec<u64> = felts.iter().map(|f| u64::from_str_radix(&f[2..], 16).unwrap()).collect();
+                assert!(values.iter().all(|v| *v < field.prime));
+                assert_eq!(values[0], canonical.len() as u64);
+
+                let (c, s) = witness_from_felts(&felts, field).unwrap();
+                assert_eq!((c.as_slice(), s.as_slice()), (canonical.as_bytes(), &sig[..len.clamp(1, 65)]));
+            }
+        }
+    }
+
+    #[test]
+    fn malformed_felts_are_rejected() {
+        let field = Field::default();
+        let felts = witness_felts(&witness("{\"k\":1}", &[1, 2, 3, 4]), field).unwrap();
+        let err = |felts: &[String]| witness_from_felts(felts, field).unwrap_err().to_string();
+
+        assert!(err(&felts[..felts.len() - 1]).contains("fewer elements than the length needs"));
+        assert!(err(&[felts.clone(), vec!["0x00000000".into()]].concat()).contains("trailing elements"));
+        assert!(err(&[]).contains("missing length element"));
+        // The last chunk of the 4-byte signature holds one byte.
+        let mut wide = felts.clone();
+        *wide.last_mut().unwrap() = "0x00000100".into();
+        assert!(err(&wide).contains("does not fit its chunk"));
+        assert!(matches!(witness_from_felts(&["0xzz".into()], field), Err(VerifyError::Hex(_))));
+        assert!(Field { prime: 257 }.pack(b"x").is_err());
+        assert!(Field { prime: 65_521 }.pack(&vec![0; 65_521]).is_err());
+    }
+}
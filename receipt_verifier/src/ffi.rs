@@ -0,0 +1,139 @@
+//! C API for embedding the verifier in non-Rust hosts (`ffi` feature); the declarations are in
+//! `include/receipt_verifier.h`.
+//!
+//! Ownership: every `const char *` argument is a NUL-terminated UTF-8 string borrowed for the
+//! duration of the call only. Every `char *` handed back (return values and `*out_err`) is
+//! owned by the caller and must be released with `rv_free`, exactly once; never with `free`.
+
+use crate::{parse_gateway, verify_receipt_full, VerifyError, VerifyOptions};
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+/// Return code for null or non-UTF-8 arguments and internal panics.
+const RV_EINVAL: c_int = -1;
+
+/// A failure: the `VerifyError` numeric code (or `RV_EINVAL`) and its message.
+struct FfiError(c_int, String);
+
+impl From<VerifyError> for FfiError {
+    fn from(e: VerifyError) -> Self {
+        FfiError(e.numeric_code(), e.to_string())
+    }
+}
+
+unsafe fn arg<'a>(p: *const c_char, name: &str) -> Result<&'a str, FfiError> {
+    if p.is_null() {
+        return Err(FfiError(RV_EINVAL, format!("{} is null", name)));
+    }
+    CStr::from_ptr(p).to_str().map_err(|_| FfiError(RV_EINVAL, format!("{} is not valid UTF-8", name)))
+}
+
+fn into_c(s: String) -> *mut c_char {
+    // Interior NULs cannot occur in JSON or our messages; strip them rather than fail.
+    CString::new(s.replace('\0', "")).map(CString::into_raw).unwrap_or(ptr::null_mut())
+}
+
+/// Store `err` in `*out_err` (if non-null) and return its code.
+unsafe fn report(out_err: *mut *mut c_char, err: Option<FfiError>) -> c_int {
+    let (code, message) = match err {
+        Some(FfiError(code, message)) => (code, Some(message)),
+        None => (0, None),
+    };
+    if !out_err.is_null() {
+        *out_err = message.map(into_c).unwrap_or(ptr::null_mut());
+    }
+    code
+}
+
+unsafe fn verify_args(
+    receipt_json: *const c_char,
+    sig_hex: *const c_char,
+    gateway: *const c_char,
+    policy_hash: *const c_char,
+    consent_hash: *const c_char,
+) -> Result<(crate::VerifiedReceipt, String), FfiError> {
+    let receipt_val: serde_json::Value = serde_json::from_str(arg(receipt_json, "receipt_json")?)
+        .map_err(|e| VerifyError::Serde(e.to_string()))?;
+    let sig = arg(sig_hex, "sig_hex")?;
+    let expected_gateway = match if gateway.is_null() { "auto" } else { arg(gateway, "gateway")? } {
+        gw if gw.eq_ignore_ascii_case("auto") => None,
+        gw => Some(parse_gateway(gw)?),
+    };
+    let verified = verify_receipt_full(
+        &receipt_val,
+        sig,
+        expected_gateway,
+        arg(policy_hash, "policy_hash")?,
+        arg(consent_hash, "consent_hash")?,
+        &VerifyOptions::default(),
+    )?;
+    Ok((verified, sig.to_string()))
+}
+
+fn guarded<T>(f: impl FnOnce() -> Result<T, FfiError>) -> Result<T, FfiError> {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| Err(FfiError(RV_EINVAL, "internal panic".into())))
+}
+
+/// Verify a receipt. `gateway` may be null or `"auto"` (any signer), a `0x` address, or a DID.
+/// Returns 0 on success, the `VerifyError` numeric code on verification failure, or -1 for
+/// invalid arguments. On failure `*out_err` receives an owned message; on success it is set to
+/// null. `out_err` itself may be null.
+///
+/// # Safety
+/// Non-null pointer arguments must be valid NUL-terminated strings; `out_err`, if non-null,
+/// must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn rv_verify(
+    receipt_json: *const c_char,
+    sig_hex: *const c_char,
+    gateway: *const c_char,
+    policy_hash: *const c_char,
+    consent_hash: *const c_char,
+    out_err: *mut *mut c_char,
+) -> c_int {
+    let res = guarded(|| verify_args(receipt_json, sig_hex, gateway, policy_hash, consent_hash));
+    report(out_err, res.err())
+}
+
+/// Verify like `rv_verify` and return owned JSON `{ "public_inputs": .., "witness": .. }`, or
+/// null on failure with `*out_err` set as for `rv_verify`.
+///
+/// # Safety
+/// Same as `rv_verify`.
+#[no_mangle]
+pub unsafe extern "C" fn rv_build_public_witness(
+    receipt_json: *const c_char,
+    sig_hex: *const c_char,
+    gateway: *const c_char,
+    policy_hash: *const c_char,
+    consent_hash: *const c_char,
+    out_err: *mut *mut c_char,
+) -> *mut c_char {
+    let res = guarded(|| {
+        let (verified, sig) = verify_args(receipt_json, sig_hex, gateway, policy_hash, consent_hash)?;
+        let (public_inputs, witness) = verified.public_and_witness(&sig);
+        Ok(serde_json::json!({ "public_inputs": public_inputs, "witness": witness }).to_string())
+    });
+    match res {
+        Ok(json) => {
+            report(out_err, None);
+            into_c(json)
+        }
+        Err(e) => {
+            report(out_err, Some(e));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Release a string returned by this library. Null is ignored.
+///
+/// # Safety
+/// `s` must be null or a pointer returned by this library that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn rv_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
@@ -0,0 +1,124 @@
+//! `0x`-prefixed lowercase hex, the one spelling of hashes in public inputs and outputs, so
+//! consumers can compare them as strings.
+
+use crate::VerifyError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A hash as `0x` + lowercase hex digits. Parsing accepts a missing prefix and any case.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct HashHex(String);
+
+impl HashHex {
+    /// Normalize `s` (`0x` optional, any case); fails unless the rest is non-empty hex digits.
+    pub fn parse(s: &str) -> Result<Self, VerifyError> {
+        let digits = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).unwrap_or(s);
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(VerifyError::Hex(format!("{:?} is not a hex hash", s)));
+        }
+        Ok(HashHex(format!("0x{}", digits.to_ascii_lowercase())))
+    }
+
+    /// `0x` + the hex of `bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        HashHex(format!("0x{}", hex::encode(bytes)))
+    }
+
+    /// `parse` for values already checked to be hex (receipt fields validated by
+    /// `Receipt::parse`, hasher output); anything else only gets the prefix and case fixed.
+    pub(crate) fn normalize(s: &str) -> Self {
+        HashHex::parse(s).unwrap_or_else(|_| {
+            let digits = s.strip_prefix("0x").unwrap_or(s);
+            HashHex(format!("0x{}", digits.to_ascii_lowercase()))
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The hex digits without `0x`.
+    pub fn digits(&self) -> &str {
+        &self.0[2..]
+    }
+
+    /// Whether `other`, in any accepted spelling, is this hash.
+    pub fn matches(&self, other: &str) -> bool {
+        HashHex::parse(other).is_ok_and(|o| o == *self)
+    }
+}
+
+impl std::ops::Deref for HashHex {
+    type Target = str;
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for HashHex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::str::FromStr for HashHex {
+    type Err = VerifyError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        HashHex::parse(s)
+    }
+}
+
+impl TryFrom<String> for HashHex {
+    type Error = VerifyError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        HashHex::parse(&s)
+    }
+}
+
+impl From<HashHex> for String {
+    fn from(h: HashHex) -> String {
+        h.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_canonical(h: &HashHex) {
+        let digits = h.strip_prefix("0x").unwrap_or_else(|| panic!("{h} has no 0x prefix"));
+        assert!(digits.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')), "{h}");
+        assert_eq!(h.digits(), digits);
+    }
+
+    #[test]
+    fn every_constructor_yields_the_prefixed_lowercase_form() {
+        for s in ["abCD01", "0xabCD01", "0XABCD01", "0", "0x0"] {
+            let h = HashHex::parse(s).unwrap();
+            assert_canonical(&h);
+            assert_eq!(s.parse::<HashHex>().unwrap(), h);
+            assert!(h.matches(s) && h.matches(&h.to_uppercase()));
+        }
+        assert_eq!(HashHex::parse("0XABcd").unwrap().as_str(), "0xabcd");
+        assert_eq!(HashHex::from_bytes(&[0xab, 0x01]).as_str(), "0xab01");
+        assert_canonical(&HashHex::from_bytes(&[]));
+        for s in ["0xAB", "AB", "0xzz", "zz"] {
+            assert!(HashHex::normalize(s).starts_with("0x"), "{s}");
+        }
+
+        let h: HashHex = serde_json::from_str("\"0XDEADbeef\"").unwrap();
+        assert_canonical(&h);
+        assert_eq!(serde_json::to_string(&h).unwrap(), "\"0xdeadbeef\"");
+        assert_eq!(String::from(h), "0xdeadbeef");
+    }
+
+    #[test]
+    fn non_hex_is_rejected() {
+        for s in ["", "0x", "0X", "0xg0", "x12", "0x 12", "0x0x12"] {
+            assert!(matches!(HashHex::parse(s), Err(VerifyError::Hex(_))), "{s:?}");
+            assert!(serde_json::from_value::<HashHex>(s.into()).is_err());
+        }
+        assert!(!HashHex::from_bytes(&[1]).matches("0x"));
+    }
+}
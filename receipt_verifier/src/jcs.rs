@@ -0,0 +1,136 @@
+//! RFC 8785 JSON Canonicalization Scheme (JCS).
+//!
+//! Differences from the legacy `canonical_json` + serde serialization:
+//! - numbers are IEEE-754 doubles printed with ECMAScript `Number.prototype.toString`
+//!   (`1.0` -> `1`, `1e21` -> `1e+21`, integers above 2^53 are rounded like JS would);
+//! - object keys are ordered by their UTF-16 code units, as `JSON.stringify` implementations do;
+//! - strings escape only `"`, `\` and control characters (short forms where JSON has them).
+
+use crate::VerifyError;
+
+/// Serialize `val` as RFC 8785 canonical JSON.
+pub fn canonical_json_jcs(val: &serde_json::Value) -> Result<String, VerifyError> {
+    canonical_json_jcs_skip(val, &[])
+}
+
+/// `canonical_json_jcs` leaving out the top-level keys in `skip`.
+pub(crate) fn canonical_json_jcs_skip(val: &serde_json::Value, skip: &[&str]) -> Result<String, VerifyError> {
+    let mut out = String::new();
+    match val {
+        serde_json::Value::Object(map) => write_object(map, skip, &mut out)?,
+        other => write_value(other, &mut out)?,
+    }
+    Ok(out)
+}
+
+fn write_value(val: &serde_json::Value, out: &mut String) -> Result<(), VerifyError> {
+    match val {
+        serde_json::Value::Null => out.push_str("null"),
+        serde_json::Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        serde_json::Value::Number(n) => {
+            let f = n
+                .as_f64()
+                .ok_or_else(|| VerifyError::Serde(format!("jcs: number {} is not representable", n)))?;
+            out.push_str(&format_es_number(f)?);
+        }
+        serde_json::Value::String(s) => write_string(s, out),
+        serde_json::Value::Array(arr) => {
+            out.push('[');
+            for (i, v) in arr.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(v, out)?;
+            }
+            out.push(']');
+        }
+        serde_json::Value::Object(map) => write_object(map, &[], out)?,
+    }
+    Ok(())
+}
+
+fn write_object(
+    map: &serde_json::Map<String, serde_json::Value>,
+    skip: &[&str],
+    out: &mut String,
+) -> Result<(), VerifyError> {
+    let mut entries: Vec<(Vec<u16>, &String, &serde_json::Value)> = map
+        .iter()
+        .filter(|(k, _)| !skip.contains(&k.as_str()))
+        .map(|(k, v)| (k.encode_utf16().collect(), k, v))
+        .collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    out.push('{');
+    for (i, (_, k, v)) in entries.into_iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write_string(k, out);
+        out.push(':');
+        write_value(v, out)?;
+    }
+    out.push('}');
+    Ok(())
+}
+
+fn write_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{08}' => out.push_str("\\b"),
+            '\u{0c}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// ECMAScript `Number::toString(10)` for a finite double.
+pub fn format_es_number(f: f64) -> Result<String, VerifyError> {
+    if !f.is_finite() {
+        return Err(VerifyError::Serde(format!("jcs: non-finite number {}", f)));
+    }
+    if f == 0.0 {
+        // Covers -0 as well.
+        return Ok("0".into());
+    }
+    // `{:e}` yields the shortest round-tripping digits, e.g. "-1.2345e-7".
+    let sci = format!("{:e}", f.abs());
+    let (mantissa, exp) = sci.split_once('e').expect("{:e} always has an exponent");
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let k = digits.len() as i32;
+    // Decimal point position relative to the digit string (ECMA-262 `n`).
+    let n = exp.parse::<i32>().expect("{:e} exponent is an integer") + 1;
+    let mut out = String::new();
+    if f < 0.0 {
+        out.push('-');
+    }
+    if k <= n && n <= 21 {
+        out.push_str(&digits);
+        out.extend(std::iter::repeat_n('0', (n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        out.push_str(&digits[..n as usize]);
+        out.push('.');
+        out.push_str(&digits[n as usize..]);
+    } else if -6 < n && n <= 0 {
+        out.push_str("0.");
+        out.extend(std::iter::repeat_n('0', (-n) as usize));
+        out.push_str(&digits);
+    } else {
+        out.push_str(&digits[..1]);
+        if k > 1 {
+            out.push('.');
+            out.push_str(&digits[1..]);
+        }
+        out.push('e');
+        out.push(if n > 0 { '+' } else { '-' });
+        out.push_str(&(n - 1).abs().to_string());
+    }
+    Ok(out)
+}
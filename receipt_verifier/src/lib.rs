@@ -1,72 +1,471 @@
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use k256::ecdsa::signature::hazmat::PrehashVerifier;
 use tiny_keccak::{Hasher, Keccak};
 use k256::ecdsa::{Signature as KSig, RecoveryId, VerifyingKey};
-use hex::FromHex;
 use thiserror::Error;
 use std::collections::BTreeMap;
-use std::process::{Command, Stdio};
-use std::io::Write;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub mod allowlist;
+pub mod batch;
+pub mod bundle;
+pub mod calldata;
+#[cfg(feature = "cache")]
+pub mod cache;
+pub mod canon;
+pub mod chain;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod compress;
+pub mod config;
+pub mod consent;
+pub mod ct;
+pub mod did;
+#[cfg(feature = "ed25519")]
+pub mod ed25519;
+pub mod eip1271;
+pub mod eip712;
+pub mod felts;
+pub mod hashhex;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod jcs;
+pub mod multihash;
+#[cfg(feature = "mpt")]
+pub mod mpt;
+pub mod multisig;
+pub mod poseidon;
+pub mod prover;
+pub mod receipt;
+pub mod redact;
+pub mod report;
+#[cfg(feature = "rpc")]
+pub mod registry;
+pub mod replay;
+#[cfg(feature = "schema")]
+pub mod schema;
+#[cfg(feature = "signing")]
+pub mod signing;
+#[cfg(feature = "starknet")]
+pub mod stark;
+pub mod telemetry;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use allowlist::{load_allowlist, parse_allowlist};
+pub use batch::{parse_batch, prove_batch, verify_batch, verify_batch_full, verify_batch_multi, BatchItem};
+pub use bundle::{InputBundle, ProofBundle};
+pub use calldata::{public_inputs_calldata, CALLDATA_LEN};
+#[cfg(feature = "cache")]
+pub use cache::VerifierCache;
+pub use canon::{Canonicalizer, DropNulls, LowercaseAddresses, TrimStrings};
+pub use chain::{verify_chain, verify_chain_with, ReceiptChain, CHAIN_GENESIS};
+#[cfg(feature = "cbor")]
+pub use cbor::{from_cbor, read_receipt_cbor, read_receipt_cbor_file, to_cbor};
+pub use compress::{decompress, read_decompressed, Compression};
+pub use config::{Profile, VerifyConfig};
+pub use consent::{
+    receipt_consent_scopes, verify_consent_membership, verify_consent_scopes, verify_consent_scopes_at, verify_receipt_consent,
+    verify_receipt_consent_at, ConsentDoc, Position, ProofNode, ScopeGrant,
+};
+pub use ct::ct_eq_bytes;
+pub use did::{parse_gateway_did, resolve_gateway_did, GatewayDid};
+#[cfg(feature = "ed25519")]
+pub use ed25519::{did_key, parse_did_key, verify_ed25519, verify_receipt_ed25519, Ed25519VerifiedReceipt, SigAlg};
+pub use eip1271::{is_valid_signature, EthCall, EIP1271_MAGIC};
+pub use eip712::{eip712_digest, Eip712Domain, Eip712Field, Eip712Schema, ReceiptType};
+pub use felts::{witness_felts, witness_from_felts, Field};
+pub use hashhex::HashHex;
+pub use jcs::canonical_json_jcs;
+#[cfg(feature = "mpt")]
+pub use mpt::{
+    verify_anchor_receipt_proof, verify_anchor_receipt_proof_with, verify_mpt_proof, verify_transaction_proof, AnchorEvent, ProvenLog,
+    ProvenReceipt, ReceiptProof,
+};
+pub use multihash::{to_cid, to_multihash, HashFormat};
+pub use multisig::{verify_multisig, verify_multisig_full, verify_multisig_with, CoSigner};
+pub use poseidon::{poseidon_hash_bytes, PoseidonParams};
+pub use prover::{
+    canned_prover_input, check_prover, mock_prove, proof_id, verify_proof, verify_proof_with, FallbackProver, ProveOpts, Prover, ProverRegistry, SigProver, FALLBACK_SUFFIX,
+};
+#[cfg(feature = "subprocess")]
+pub use prover::{external_prove, external_prove_with_opts, SubprocessProver};
+#[cfg(feature = "async")]
+pub use prover::external_prove_async;
+#[cfg(feature = "http")]
+pub use prover::HttpProver;
+pub use receipt::{parse_receipt_strict, read_receipt, read_receipt_file, read_receipt_strict, Anchor, L2Tx, RawReceipt, Receipt, ReceiptV1};
+pub use redact::{
+    redact_witness, redact_witness_mode, redaction_commitment, restore_signature, signature_commitment, verify_redaction,
+    withhold_signature, RedactedWitness, Redaction, SignatureOpening, REDACTED_KEY,
+};
+pub use report::{verify_receipt_all, verify_receipt_report, CheckOutcome, CheckStatus, VerificationReport};
+#[cfg(feature = "rpc")]
+pub use rpc::{verify_anchor, verify_anchor_with, AnchorExpect, AnchorStatus, RpcClient};
+#[cfg(feature = "schema")]
+pub use schema::{validate_schema, ReceiptSchema, RECEIPT_V1_SCHEMA};
+#[cfg(feature = "signing")]
+pub use signing::{sign_receipt, signer_address};
+#[cfg(feature = "starknet")]
+pub use stark::{parse_felt, stark_message_hash, verify_receipt_stark, verify_stark_sig, StarkHash, StarkVerifiedReceipt};
+#[cfg(feature = "rpc")]
+pub use registry::{GatewayRegistry, RpcGatewayRegistry, DEFAULT_REGISTRY_FUNCTION};
+pub use replay::{FileSeenStore, MemorySeenStore, ReplayGuard, SeenKey, SeenStore};
 
 #[derive(Debug, Error)]
 pub enum VerifyError {
     #[error("invalid hex: {0}")] Hex(String),
     #[error("serde error: {0}")] Serde(String),
     #[error("signature error: {0}")] Sig(String),
-    #[error("address mismatch")] AddressMismatch,
-    #[error("policy/consent mismatch")] PolicyConsentMismatch,
+    #[error("address mismatch: recovered {recovered}")] AddressMismatch { recovered: String },
+    #[error("policy hash mismatch: expected {expected}, got {actual}")] PolicyMismatch { expected: String, actual: String },
+    #[error("consent hash mismatch: expected {expected}, got {actual}")] ConsentMismatch { expected: String, actual: String },
     #[error("gateway parse error: {0}")] GatewayParse(String),
+    #[error("unsupported DID: {0}")] UnsupportedDid(String),
     #[error("prover error: {0}")] Prover(String),
+    #[error("prover timed out after {0:?}")] ProverTimeout(std::time::Duration),
+    #[error("rpc error: {0}")] Rpc(String),
+    #[error("anchor check failed: {0}")] Anchor(String),
+    #[error("contract signature invalid (EIP-1271)")] ContractSigInvalid,
+    #[error("malleable signature: s is in the upper half of the curve order")] MalleableSignature,
+    #[error("replay detected: {0}")] ReplayDetected(String),
+    #[error("receipt expired: {0}")] Expired(String),
+    #[error("receipt not yet valid: {0}")] NotYetValid(String),
+    #[error("receipt hash mismatch: expected {expected}, got {actual}")] ReceiptHashMismatch { expected: String, actual: String },
+    #[error("receipt field `{field}`: {reason}")] ReceiptField { field: String, reason: String },
+    #[error("config error: {0}")] Config(String),
+    #[error("receipt does not match schema: {}", .0.join("; "))] SchemaInvalid(Vec<String>),
+    #[error("chain id mismatch: expected {expected}, signature has {}", .actual.map_or("none".to_string(), |c| c.to_string()))] ChainIdMismatch { expected: u64, actual: Option<u64> },
+    #[error("invalid proof bundle: {0}")] Bundle(String),
+    #[error("canonicalization version mismatch: proof was made with v{actual}, this verifier implements v{expected}")] VersionMismatch { expected: u32, actual: u32 },
+    #[error("unauthorized gateway: {0}")] UnauthorizedGateway(String),
+    #[error("co-signature threshold not met: {have} of {need} required authorized signers")] ThresholdNotMet { have: usize, need: usize },
+    #[error("receipt chain head mismatch: expected {expected}, got {actual}")] ChainHeadMismatch { expected: String, actual: String },
+    #[error("receipt declares gateway {declared} but was signed by {recovered}")] SelfGatewayMismatch { declared: String, recovered: String },
+    #[error("consent scope `{scope}`: {reason}")] ConsentScope { scope: String, reason: String },
+    #[error("I/O error: {0}")] Io(String),
 }
 
-#[derive(Debug, Deserialize, Clone)]
-pub struct Receipt {
-    #[serde(flatten)]
-    pub rest: serde_json::Value,
+impl VerifyError {
+    /// Stable string code for programmatic handling. Codes never change across patch releases,
+    /// even when the `Display` message does; new variants only add codes.
+    pub fn code(&self) -> &'static str {
+        match self {
+            VerifyError::Hex(_) => "INVALID_HEX",
+            VerifyError::Serde(_) => "SERDE",
+            VerifyError::Sig(_) => "INVALID_SIGNATURE",
+            VerifyError::AddressMismatch { .. } => "ADDRESS_MISMATCH",
+            VerifyError::PolicyMismatch { .. } => "POLICY_MISMATCH",
+            VerifyError::ConsentMismatch { .. } => "CONSENT_MISMATCH",
+            VerifyError::GatewayParse(_) => "GATEWAY_PARSE",
+            VerifyError::UnsupportedDid(_) => "UNSUPPORTED_DID",
+            VerifyError::Prover(_) => "PROVER",
+            VerifyError::ProverTimeout(_) => "PROVER_TIMEOUT",
+            VerifyError::Rpc(_) => "RPC",
+            VerifyError::Anchor(_) => "ANCHOR",
+            VerifyError::ContractSigInvalid => "CONTRACT_SIG_INVALID",
+            VerifyError::MalleableSignature => "MALLEABLE_SIGNATURE",
+            VerifyError::ReplayDetected(_) => "REPLAY_DETECTED",
+            VerifyError::Expired(_) => "EXPIRED",
+            VerifyError::NotYetValid(_) => "NOT_YET_VALID",
+            VerifyError::ReceiptHashMismatch { .. } => "RECEIPT_HASH_MISMATCH",
+            VerifyError::ReceiptField { .. } => "RECEIPT_FIELD",
+            VerifyError::Config(_) => "CONFIG",
+            VerifyError::SchemaInvalid(_) => "SCHEMA_INVALID",
+            VerifyError::ChainIdMismatch { .. } => "CHAIN_ID_MISMATCH",
+            VerifyError::Bundle(_) => "BUNDLE_INVALID",
+            VerifyError::VersionMismatch { .. } => "VERSION_MISMATCH",
+            VerifyError::UnauthorizedGateway(_) => "UNAUTHORIZED_GATEWAY",
+            VerifyError::ThresholdNotMet { .. } => "THRESHOLD_NOT_MET",
+            VerifyError::ChainHeadMismatch { .. } => "CHAIN_HEAD_MISMATCH",
+            VerifyError::SelfGatewayMismatch { .. } => "SELF_GATEWAY_MISMATCH",
+            VerifyError::ConsentScope { .. } => "CONSENT_SCOPE",
+            VerifyError::Io(_) => "IO",
+        }
+    }
+
+    /// Stable numeric code, paired one-to-one with `code()`; same stability guarantee.
+    pub fn numeric_code(&self) -> i32 {
+        match self {
+            VerifyError::Hex(_) => 1,
+            VerifyError::Serde(_) => 2,
+            VerifyError::Sig(_) => 3,
+            VerifyError::AddressMismatch { .. } => 4,
+            // 5 was the combined POLICY_CONSENT_MISMATCH; retired, not reused.
+            VerifyError::PolicyMismatch { .. } => 24,
+            VerifyError::ConsentMismatch { .. } => 25,
+            VerifyError::GatewayParse(_) => 6,
+            VerifyError::Prover(_) => 7,
+            VerifyError::ProverTimeout(_) => 8,
+            VerifyError::Rpc(_) => 9,
+            VerifyError::ContractSigInvalid => 10,
+            VerifyError::MalleableSignature => 11,
+            VerifyError::ReplayDetected(_) => 12,
+            VerifyError::ReceiptHashMismatch { .. } => 13,
+            VerifyError::ReceiptField { .. } => 14,
+            VerifyError::Expired(_) => 15,
+            VerifyError::NotYetValid(_) => 16,
+            VerifyError::Anchor(_) => 17,
+            VerifyError::UnsupportedDid(_) => 18,
+            VerifyError::Config(_) => 19,
+            VerifyError::SchemaInvalid(_) => 20,
+            VerifyError::ChainIdMismatch { .. } => 21,
+            VerifyError::Bundle(_) => 22,
+            VerifyError::VersionMismatch { .. } => 23,
+            VerifyError::UnauthorizedGateway(_) => 26,
+            VerifyError::ThresholdNotMet { .. } => 27,
+            VerifyError::ChainHeadMismatch { .. } => 28,
+            VerifyError::SelfGatewayMismatch { .. } => 29,
+            VerifyError::ConsentScope { .. } => 30,
+            VerifyError::Io(_) => 31,
+        }
+    }
+
+    /// Process exit status the CLI uses for this error; grouped by category, see the README
+    /// exit code table. 1 stays reserved for unexpected errors; 5 is I/O, for `Io` and file
+    /// errors outside the library.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            VerifyError::Sig(_)
+            | VerifyError::AddressMismatch { .. }
+            | VerifyError::ContractSigInvalid
+            | VerifyError::MalleableSignature
+            | VerifyError::ChainIdMismatch { .. }
+            | VerifyError::UnauthorizedGateway(_)
+            | VerifyError::ThresholdNotMet { .. }
+            | VerifyError::SelfGatewayMismatch { .. } => 2,
+            VerifyError::PolicyMismatch { .. } | VerifyError::ConsentMismatch { .. } | VerifyError::ConsentScope { .. } => 3,
+            VerifyError::Expired(_) | VerifyError::NotYetValid(_) => 4,
+            VerifyError::Io(_) => 5,
+            VerifyError::Hex(_)
+            | VerifyError::Serde(_)
+            | VerifyError::ReceiptField { .. }
+            | VerifyError::SchemaInvalid(_)
+            | VerifyError::GatewayParse(_)
+            | VerifyError::UnsupportedDid(_)
+            | VerifyError::Config(_) => 6,
+            VerifyError::ReplayDetected(_) => 7,
+            VerifyError::Prover(_)
+            | VerifyError::ProverTimeout(_)
+            | VerifyError::ReceiptHashMismatch { .. }
+            | VerifyError::ChainHeadMismatch { .. }
+            | VerifyError::Bundle(_)
+            | VerifyError::VersionMismatch { .. } => 8,
+            VerifyError::Rpc(_) | VerifyError::Anchor(_) => 9,
+        }
+    }
 }
 
+/// Version of this crate.
+pub const VERIFIER_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Version of the canonicalization rules; bumped whenever the canonical bytes (and therefore
+/// receipt hashes) produced for some receipt and `CanonMode` change, so proofs made under
+/// older rules fail with `VersionMismatch` instead of a hash mismatch.
+pub const CANONICALIZATION_VERSION: u32 = 1;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PublicInputs {
-    pub receipt_hash: String,
-    pub policy_hash: String,
-    pub consent_hash: String,
+    pub receipt_hash: HashHex,
+    pub policy_hash: HashHex,
+    pub consent_hash: HashHex,
     pub gateway_address: String,
+    /// EIP-55 form of `gateway_address`.
+    #[serde(default)]
+    pub gateway_address_checksum: Option<String>,
     pub note: Option<String>,
+    /// `CANONICALIZATION_VERSION` of the verifier that produced these; absent on older proofs.
+    #[serde(default)]
+    pub canonicalization_version: Option<u32>,
+    /// `VERIFIER_VERSION` of the verifier that produced these; informational.
+    #[serde(default)]
+    pub verifier_version: Option<String>,
+    /// When the receipt was anchored, if its `anchor` says; `None` for unanchored receipts.
+    #[serde(default)]
+    pub anchor: Option<AnchorInfo>,
+    /// How `receipt_hash` was computed; absent on older proofs, which used sha256.
+    #[serde(default)]
+    pub hash_scheme: Option<HashScheme>,
+    /// What the personal_sign signature covered; absent on older proofs (keccak) and for
+    /// EIP-712 and Stark receipts.
+    #[serde(default)]
+    pub digest_mode: Option<DigestMode>,
+    /// SHA-256 and Keccak-256 of the same canonical form, whichever `hash_scheme` made
+    /// `receipt_hash`, for on-chain components that store the other digest; absent on older proofs.
+    #[serde(default)]
+    pub receipt_hash_sha256: Option<HashHex>,
+    #[serde(default)]
+    pub receipt_hash_keccak: Option<HashHex>,
+}
+
+impl PublicInputs {
+    /// The receipt hash under `scheme`: `receipt_hash` if that is the scheme it was made with,
+    /// else the matching `receipt_hash_sha256` / `receipt_hash_keccak`. `None` if the public
+    /// inputs do not carry it.
+    pub fn receipt_hash_as(&self, scheme: &HashScheme) -> Option<&HashHex> {
+        if self.hash_scheme.as_ref().unwrap_or(&HashScheme::Sha256) == scheme {
+            return Some(&self.receipt_hash);
+        }
+        match scheme {
+            HashScheme::Sha256 => self.receipt_hash_sha256.as_ref(),
+            HashScheme::Keccak => self.receipt_hash_keccak.as_ref(),
+            HashScheme::Poseidon(_) => None,
+        }
+    }
+}
+
+/// Anchoring epoch from the receipt's `anchor` section (`l2_tx.block_number`, `timestamp`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnchorInfo {
+    pub anchor_block_number: Option<u64>,
+    pub anchor_timestamp: Option<u64>,
 }
 
+/// Private inputs for the prover: the full signed receipt and its signature. With the
+/// `zeroize` feature the strings are wiped when the witness is dropped.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "zeroize", derive(zeroize::Zeroize, zeroize::ZeroizeOnDrop))]
 pub struct Witness {
+    /// Canonical JSON of the signed fields (without `receipt_sig` and `anchor`), the bytes
+    /// `receipt_hash` and the signature cover. Before `PROOF_VERSION` 2 this was documented as
+    /// the whole receipt's canonical form.
     pub canonical_receipt: String,
+    /// Empty, and left out of the JSON, when the signature is withheld (`withhold_signature`).
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub signature_hex: String,
     pub receipt_id: Option<String>,
     pub anchor_tx_hash: Option<String>,
+    /// `sha256(salt || signature)` standing in for a withheld signature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature_commitment: Option<String>,
 }
 
+/// Serialization version of `Proof`, written into every new proof. Fields added later must be
+/// `#[serde(default)]` (or `Option`) so older proofs still parse; a change that would misread
+/// them instead bumps this and teaches `Proof::from_value` to migrate the previous version.
+///
+/// v2: `witness_summary.canonical_len` (like the witness's `canonical_receipt`) is that of the
+/// signed canonical form, without `receipt_sig` and `anchor`; v0/v1 proofs may carry the whole
+/// receipt's.
+pub const PROOF_VERSION: u32 = 2;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Proof {
+    /// `PROOF_VERSION` the proof was written under; 0 for proofs from before it existed.
+    #[serde(default)]
+    pub version: u32,
     pub proof_id: String,
     pub proof: String,
     pub public_inputs: PublicInputs,
+    #[serde(default)]
     pub witness_summary: WitnessSummary,
     pub prover: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Proof {
+    /// Parse a proof written under any version up to `PROOF_VERSION`, migrated to the current
+    /// struct. Newer proofs are rejected rather than read with fields missing.
+    pub fn from_json(json: &str) -> Result<Proof, VerifyError> {
+        Proof::from_value(serde_json::from_str(json).map_err(|e| VerifyError::Serde(e.to_string()))?)
+    }
+
+    /// `from_json` over bytes.
+    pub fn from_slice(bytes: &[u8]) -> Result<Proof, VerifyError> {
+        Proof::from_value(serde_json::from_slice(bytes).map_err(|e| VerifyError::Serde(e.to_string()))?)
+    }
+
+    /// `from_json` over an already parsed value.
+    pub fn from_value(value: serde_json::Value) -> Result<Proof, VerifyError> {
+        let version = match value.get("version") {
+            None => 0,
+            Some(v) => v.as_u64().ok_or_else(|| VerifyError::Serde("proof version is not an integer".into()))?,
+        };
+        if version > u64::from(PROOF_VERSION) {
+            return Err(VerifyError::Serde(format!(
+                "proof version {} is newer than this verifier's {}",
+                version, PROOF_VERSION
+            )));
+        }
+        // v0 -> v1 added `version` itself; every other field v0 lacks is defaulted by serde.
+        // v1 -> v2 changed what `canonical_len` measures, which cannot be redone without the
+        // receipt; it is informational, so it is kept as written.
+        let mut proof: Proof = serde_json::from_value(value).map_err(|e| VerifyError::Serde(format!("proof: {}", e)))?;
+        proof.version = PROOF_VERSION;
+        Ok(proof)
+    }
+
+    /// The backend that produced the proof: `prover` without a `FALLBACK_SUFFIX`.
+    pub fn backend(&self) -> &str {
+        self.prover.strip_suffix(FALLBACK_SUFFIX).unwrap_or(&self.prover)
+    }
+
+    /// Whether a `FallbackProver` produced this proof after its primary prover failed, e.g. a
+    /// `receipt_sig` stub standing in for a real proof.
+    pub fn is_fallback(&self) -> bool {
+        self.prover.ends_with(FALLBACK_SUFFIX)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct WitnessSummary {
     pub receipt_id: Option<String>,
     pub anchor_tx_hash: Option<String>,
+    #[serde(default)]
     pub canonical_len: usize,
 }
 
+/// `s` without `0x`, left-padded to an even number of digits. Does not validate; `parse_hex`
+/// is the checked decoder. Kept for JSON-RPC and EIP-712 data, where a bare `0x` is valid.
 pub fn normalize_hex_even(s: &str) -> String {
     let clean = s.trim_start_matches("0x");
-    if clean.len() % 2 == 0 {
+    if clean.len().is_multiple_of(2) {
         clean.to_string()
     } else {
         format!("0{}", clean)
     }
 }
 
+/// Decode `0x`-optional hex (`0X` too). An odd number of digits is left-padded with a zero,
+/// reading the input as a number (felts, u256 words, gateways given as felts); byte strings
+/// use `parse_hex_strict`. An empty string, a bare `0x` or a non-hex character fails with `Hex`.
+pub fn parse_hex(s: &str) -> Result<Vec<u8>, VerifyError> {
+    hex_bytes(s, true).map_err(VerifyError::Hex)
+}
+
+/// `parse_hex` for byte strings (signatures, keys, hashes, salts): an odd number of digits
+/// is rejected instead of padded.
+pub fn parse_hex_strict(s: &str) -> Result<Vec<u8>, VerifyError> {
+    hex_bytes(s, false).map_err(VerifyError::Hex)
+}
+
+/// `parse_hex` (`pad_odd`) or `parse_hex_strict`, returning the bare reason for callers that
+/// report it in their own error variant.
+pub(crate) fn hex_bytes(s: &str, pad_odd: bool) -> Result<Vec<u8>, String> {
+    let digits = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).unwrap_or(s);
+    if digits.is_empty() {
+        return Err(if s.is_empty() { "empty string".into() } else { "no digits after 0x".into() });
+    }
+    if let Some((i, c)) = digits.char_indices().find(|(_, c)| !c.is_ascii_hexdigit()) {
+        return Err(format!("invalid character {:?} at position {}", c, s.len() - digits.len() + i));
+    }
+    if digits.len().is_multiple_of(2) {
+        return Ok(hex::decode(digits).expect("validated hex digits"));
+    }
+    if !pad_odd {
+        return Err(format!("odd number of hex digits ({})", digits.len()));
+    }
+    Ok(hex::decode(format!("0{}", digits)).expect("validated hex digits"))
+}
+
+/// Exactly `N` bytes of strict hex; errors name `what`.
+pub(crate) fn hex_array<const N: usize>(s: &str, what: &str) -> Result<[u8; N], VerifyError> {
+    let bytes = hex_bytes(s, false).map_err(|e| VerifyError::Hex(format!("{}: {}", what, e)))?;
+    let len = bytes.len();
+    bytes.try_into().map_err(|_| VerifyError::Hex(format!("{}: expected {} bytes, got {}", what, N, len)))
+}
+
 /// Canonicalize JSON by sorting keys recursively.
 pub fn canonical_json(val: &serde_json::Value) -> serde_json::Value {
     match val {
@@ -80,19 +479,420 @@ pub fn canonical_json(val: &serde_json::Value) -> serde_json::Value {
         serde_json::Value::Array(arr) => {
             serde_json::Value::Array(arr.iter().map(canonical_json).collect())
         }
+        serde_json::Value::Number(n) => canonical_number(n),
         _ => val.clone(),
     }
 }
 
-/// Compute sha256 over canonical JSON string (no whitespace, sorted keys).
-pub fn receipt_hash_sha256(val: &serde_json::Value) -> String {
-    let canon = canonical_json(val);
-    let s = serde_json::to_string(&canon).unwrap();
+/// Whether `n` is an integer literal (`-?[0-9]+`). Parsing keeps those digit for digit at any
+/// size (serde_json's `arbitrary_precision`), so they canonicalize exactly.
+pub(crate) fn is_integer_literal(n: &serde_json::Number) -> bool {
+    if n.is_u64() || n.is_i64() {
+        return true;
+    }
+    let s = n.to_string();
+    let digits = s.strip_prefix('-').unwrap_or(&s);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Integers as written; other numbers as the shortest round-tripping double (`1e2` is
+/// `100.0`), the form they had before numbers were parsed with arbitrary precision.
+fn canonical_number(n: &serde_json::Number) -> serde_json::Value {
+    match n.as_f64().and_then(serde_json::Number::from_f64) {
+        Some(f) if !is_integer_literal(n) => serde_json::Value::Number(f),
+        _ => serde_json::Value::Number(n.clone()),
+    }
+}
+
+/// Top-level keys not covered by the signature (added after signing), unless
+/// `VerifyOptions::excluded_fields` says otherwise.
+pub const DEFAULT_EXCLUDED_FIELDS: &[&str] = &["receipt_sig", "anchor"];
+
+/// Serializes exactly like `canonical_json(val)`, but borrows instead of building a sorted
+/// copy of the tree. Top-level keys listed in `skip` are omitted.
+struct SortedJson<'a> {
+    val: &'a serde_json::Value,
+    skip: &'a [&'a str],
+}
+
+impl Serialize for SortedJson<'_> {
+    fn serialize<S: serde::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::{SerializeMap, SerializeSeq};
+        match self.val {
+            serde_json::Value::Object(map) => {
+                let mut entries: Vec<_> = map.iter().filter(|(k, _)| !self.skip.contains(&k.as_str())).collect();
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+                let mut m = ser.serialize_map(Some(entries.len()))?;
+                for (k, v) in entries {
+                    m.serialize_entry(k, &SortedJson { val: v, skip: &[] })?;
+                }
+                m.end()
+            }
+            serde_json::Value::Array(arr) => {
+                let mut seq = ser.serialize_seq(Some(arr.len()))?;
+                for v in arr {
+                    seq.serialize_element(&SortedJson { val: v, skip: &[] })?;
+                }
+                seq.end()
+            }
+            serde_json::Value::Number(n) => canonical_number(n).serialize(ser),
+            other => other.serialize(ser),
+        }
+    }
+}
+
+/// Largest integer every JSON implementation represents exactly (`Number.MAX_SAFE_INTEGER`).
+pub const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// Append `/key` to a JSON pointer, escaping `~` and `/` (RFC 6901).
+fn push_pointer_token(path: &mut String, key: &str) {
+    path.push('/');
+    path.push_str(&key.replace('~', "~0").replace('/', "~1"));
+}
+
+/// Default for `VerifyOptions::max_depth`: nesting levels of objects and arrays.
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+/// Default for `VerifyOptions::max_canonical_bytes`: 1 MiB.
+pub const DEFAULT_MAX_CANONICAL_BYTES: usize = 1 << 20;
+
+/// Size guards applied while canonicalizing.
+#[derive(Debug, Clone, Copy)]
+struct Limits {
+    max_depth: usize,
+    max_bytes: usize,
+}
+
+impl Limits {
+    const DEFAULT: Limits = Limits { max_depth: DEFAULT_MAX_DEPTH, max_bytes: DEFAULT_MAX_CANONICAL_BYTES };
+}
+
+fn too_large(detail: String) -> VerifyError {
+    VerifyError::Serde(format!("receipt too large/deep: {}", detail))
+}
+
+/// Reject numbers that serialize differently across implementations: non-integers with
+/// magnitude above `MAX_SAFE_INTEGER`, non-finite values such as `1e400`, and negative zero
+/// (the literal `-0` already parses as the integer 0). Integers of any size are kept digit
+/// for digit (JCS rounds them to doubles, as JS does). Also rejects containers nested deeper
+/// than `max_depth` (`depth` is that of `val`'s parent), before the walk can recurse further.
+fn check_tree(val: &serde_json::Value, path: &mut String, depth: usize, max_depth: usize) -> Result<(), VerifyError> {
+    if (val.is_array() || val.is_object()) && depth >= max_depth {
+        let at = if path.is_empty() { "/" } else { path.as_str() };
+        return Err(too_large(format!("nesting deeper than {} at {}", max_depth, at)));
+    }
+    match val {
+        serde_json::Value::Number(n) => {
+            let in_range = match n.as_f64() {
+                _ if is_integer_literal(n) => n.to_string() != "-0",
+                Some(f) => f.is_finite() && f.abs() <= MAX_SAFE_INTEGER as f64 && !(f == 0.0 && f.is_sign_negative()),
+                None => false,
+            };
+            if !in_range {
+                let at = if path.is_empty() { "/" } else { path.as_str() };
+                return Err(VerifyError::Serde(format!(
+                    "number {} at {} is outside the supported range (integers, or finite |n| <= 2^53 - 1; not -0)",
+                    n, at
+                )));
+            }
+        }
+        serde_json::Value::Array(arr) => {
+            for (i, v) in arr.iter().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("/{}", i));
+                check_tree(v, path, depth + 1, max_depth)?;
+                path.truncate(len);
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for (k, v) in map {
+                let len = path.len();
+                push_pointer_token(path, k);
+                check_tree(v, path, depth + 1, max_depth)?;
+                path.truncate(len);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Reject containers nested deeper than `max_depth` anywhere in `val`, unsigned keys
+/// included, before the receipt is deserialized or hashed for the cache (both recurse through
+/// the whole tree). The walk itself stops at `max_depth`.
+fn check_nesting(val: &serde_json::Value, path: &mut String, depth: usize, max_depth: usize) -> Result<(), VerifyError> {
+    if (val.is_array() || val.is_object()) && depth >= max_depth {
+        let at = if path.is_empty() { "/" } else { path.as_str() };
+        return Err(too_large(format!("nesting deeper than {} at {}", max_depth, at)));
+    }
+    match val {
+        serde_json::Value::Array(arr) => {
+            for (i, v) in arr.iter().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("/{}", i));
+                check_nesting(v, path, depth + 1, max_depth)?;
+                path.truncate(len);
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for (k, v) in map {
+                let len = path.len();
+                push_pointer_token(path, k);
+                check_nesting(v, path, depth + 1, max_depth)?;
+                path.truncate(len);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Canonical JSON of `val` in `mode`, leaving out the top-level keys in `skip`. Numbers must be
+/// in the range `check_tree` accepts, and the tree and its canonical form within `limits`.
+fn canonical_string_skip(
+    val: &serde_json::Value,
+    mode: CanonMode,
+    skip: &[&str],
+    limits: Limits,
+) -> Result<String, VerifyError> {
+    check_signed_tree(val, skip, limits)?;
+    let canon = match mode {
+        CanonMode::Sorted => {
+            serde_json::to_string(&SortedJson { val, skip }).map_err(|e| VerifyError::Serde(e.to_string()))?
+        }
+        CanonMode::Jcs => jcs::canonical_json_jcs_skip(val, skip)?,
+    };
+    if canon.len() > limits.max_bytes {
+        return Err(too_large(format!("canonical form is {} bytes, limit {}", canon.len(), limits.max_bytes)));
+    }
+    Ok(canon)
+}
+
+/// `VerifyOptions::signed_raw` as the signed form, once it is known to parse to `val` (both
+/// without the `skip` keys).
+fn raw_signed_form(raw: &str, val: &serde_json::Value, skip: &[&str], limits: Limits) -> Result<String, VerifyError> {
+    if raw.len() > limits.max_bytes {
+        return Err(too_large(format!("raw signed form is {} bytes, limit {}", raw.len(), limits.max_bytes)));
+    }
+    check_signed_tree(val, skip, limits)?;
+    let parsed = parse_receipt_strict(raw).map_err(|e| VerifyError::ReceiptField { field: "receipt_raw".into(), reason: e.to_string() })?;
+    let without_skipped = |v: &serde_json::Value| match v {
+        serde_json::Value::Object(map) => {
+            serde_json::Value::Object(map.iter().filter(|(k, _)| !skip.contains(&k.as_str())).map(|(k, v)| (k.clone(), v.clone())).collect())
+        }
+        other => other.clone(),
+    };
+    if without_skipped(&parsed) != without_skipped(val) {
+        return Err(VerifyError::ReceiptField {
+            field: "receipt_raw".into(),
+            reason: "does not parse to the receipt's signed fields".into(),
+        });
+    }
+    Ok(raw.to_string())
+}
+
+/// `canonical_string_skip` after `canonicalizer`'s rewrite. The raw tree is checked against
+/// `limits` first, so rewrites never walk an over-deep tree.
+fn canonical_string_with(
+    val: &serde_json::Value,
+    canonicalizer: &dyn Canonicalizer,
+    skip: &[&str],
+    limits: Limits,
+) -> Result<String, VerifyError> {
+    check_signed_tree(val, skip, limits)?;
+    canonical_string_skip(&canonicalizer.transform(val), canonicalizer.mode(), skip, limits)
+}
+
+/// Number range and nesting depth of `val` outside the top-level keys in `skip`.
+fn check_signed_tree(val: &serde_json::Value, skip: &[&str], limits: Limits) -> Result<(), VerifyError> {
+    match val {
+        serde_json::Value::Object(map) => {
+            if limits.max_depth == 0 {
+                return Err(too_large("nesting deeper than 0 at /".into()));
+            }
+            let mut path = String::new();
+            for (k, v) in map.iter().filter(|(k, _)| !skip.contains(&k.as_str())) {
+                path.clear();
+                push_pointer_token(&mut path, k);
+                check_tree(v, &mut path, 1, limits.max_depth)?;
+            }
+        }
+        other => check_tree(other, &mut String::new(), 0, limits.max_depth)?,
+    }
+    Ok(())
+}
+
+/// Canonical JSON flavour used for hashing and signing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CanonMode {
+    /// Recursively sorted keys, serde_json serialization (the original scheme).
+    #[default]
+    Sorted,
+    /// RFC 8785 JCS, byte-compatible with JS/Python JCS implementations.
+    Jcs,
+}
+
+impl std::str::FromStr for CanonMode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "sorted" => Ok(CanonMode::Sorted),
+            "jcs" => Ok(CanonMode::Jcs),
+            other => Err(format!("unknown canonicalization mode {other} (expected sorted|jcs)")),
+        }
+    }
+}
+
+/// Bytes a personal_sign signer passed to EIP-191 (`personal_hash_keccak`); picking the wrong
+/// one makes every signature recover to an unrelated address.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestMode {
+    /// The canonical JSON string itself, as `personal_sign(canonical)`.
+    #[serde(rename = "raw")]
+    RawCanonical,
+    /// The 32-byte keccak256 of the canonical JSON (the original scheme).
+    #[default]
+    Keccak,
+    /// The 32-byte sha256 of the canonical JSON (raw bytes, not hex).
+    Sha256,
+}
+
+impl DigestMode {
+    /// EIP-191 digest the signature is checked against, for canonical JSON `canonical`.
+    pub fn personal_digest(self, canonical: &[u8]) -> [u8; 32] {
+        match self {
+            DigestMode::RawCanonical => personal_hash_keccak(canonical),
+            DigestMode::Keccak => personal_hash_keccak(&keccak256(canonical)),
+            DigestMode::Sha256 => personal_hash_keccak(&Sha256::digest(canonical)),
+        }
+    }
+}
+
+impl std::str::FromStr for DigestMode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "raw" => Ok(DigestMode::RawCanonical),
+            "keccak" => Ok(DigestMode::Keccak),
+            "sha256" => Ok(DigestMode::Sha256),
+            other => Err(format!("unknown digest mode {other} (expected raw|keccak|sha256)")),
+        }
+    }
+}
+
+/// Digest carried in `PublicInputs.receipt_hash`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "scheme", rename_all = "lowercase")]
+pub enum HashScheme {
+    /// SHA-256 of the canonical JSON (the original scheme).
+    #[default]
+    Sha256,
+    /// Keccak-256 of the canonical JSON, for on-chain verifiers without a sha256 precompile.
+    Keccak,
+    /// Poseidon over the canonical JSON packed into field elements (see `poseidon`).
+    Poseidon(PoseidonParams),
+}
+
+impl HashScheme {
+    fn hash_hex(&self, bytes: &[u8]) -> Result<String, VerifyError> {
+        match self {
+            HashScheme::Sha256 => Ok(sha256_hex(bytes)),
+            HashScheme::Keccak => Ok(hex::encode(keccak256(bytes))),
+            HashScheme::Poseidon(params) => poseidon_hash_bytes(bytes, params),
+        }
+    }
+}
+
+impl std::str::FromStr for HashScheme {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "sha256" => Ok(HashScheme::Sha256),
+            "keccak" => Ok(HashScheme::Keccak),
+            "poseidon" => Ok(HashScheme::Poseidon(PoseidonParams::default())),
+            other => Err(format!("unknown hash scheme {other} (expected sha256|keccak|poseidon)")),
+        }
+    }
+}
+
+/// Sorted canonical JSON of `val` without the top-level keys in `excluded` (pass
+/// `DEFAULT_EXCLUDED_FIELDS` for the receipt's default signed form).
+pub fn canonical_string(val: &serde_json::Value, excluded: &[&str]) -> Result<String, VerifyError> {
+    canonical_string_skip(val, CanonMode::Sorted, excluded, Limits::DEFAULT)
+}
+
+/// The exact canonical bytes verification under `opts` hashes and signs over (after
+/// `sign_path`, exclusions and any `canonicalizer`); for diffing against a signer's form.
+pub fn signed_canonical_string(receipt_val: &serde_json::Value, opts: &VerifyOptions) -> Result<String, VerifyError> {
+    canonical_and_hash(receipt_val, opts).map(|(canonical, _)| canonical)
+}
+
+/// Canonical JSON string for `val` in the given mode.
+pub fn canonical_string_mode(val: &serde_json::Value, mode: CanonMode) -> Result<String, VerifyError> {
+    canonical_string_skip(val, mode, &[], Limits::DEFAULT)
+}
+
+pub(crate) fn sha256_hex(bytes: &[u8]) -> String {
     let mut hasher = Sha256::new();
-    hasher.update(s.as_bytes());
+    hasher.update(bytes);
     format!("{:x}", hasher.finalize())
 }
 
+/// Sorted canonical JSON of the whole of `val`, without the size guards, for the bare
+/// receipt hash helpers.
+fn sorted_json_string(val: &serde_json::Value) -> Result<String, VerifyError> {
+    serde_json::to_string(&SortedJson { val, skip: &[] }).map_err(|e| VerifyError::Serde(e.to_string()))
+}
+
+/// Compute sha256 over canonical JSON string (no whitespace, sorted keys).
+pub fn receipt_hash_sha256(val: &serde_json::Value) -> String {
+    try_receipt_hash_sha256(val).expect("canonicalizable receipt")
+}
+
+/// `receipt_hash_sha256` returning serializer errors as `Serde` instead of panicking, as
+/// `receipt_hash_keccak` and `receipt_hash_poseidon` do.
+pub fn try_receipt_hash_sha256(val: &serde_json::Value) -> Result<String, VerifyError> {
+    Ok(sha256_hex(sorted_json_string(val)?.as_bytes()))
+}
+
+/// Keccak-256 over the canonical JSON string, as `receipt_hash_sha256`.
+pub fn receipt_hash_keccak(val: &serde_json::Value) -> Result<String, VerifyError> {
+    Ok(hex::encode(keccak256(sorted_json_string(val)?.as_bytes())))
+}
+
+/// Poseidon (default M31 parameters) over the canonical JSON string; fails for strings of
+/// 2^31 bytes or more.
+pub fn receipt_hash_poseidon(val: &serde_json::Value) -> Result<String, VerifyError> {
+    poseidon_hash_bytes(sorted_json_string(val)?.as_bytes(), &PoseidonParams::default())
+}
+
+/// Compute sha256 over the canonical JSON string produced by `mode`.
+pub fn receipt_hash_sha256_mode(val: &serde_json::Value, mode: CanonMode) -> Result<String, VerifyError> {
+    Ok(sha256_hex(canonical_string_mode(val, mode)?.as_bytes()))
+}
+
+/// Compute sha256 over the canonical JSON string produced by `canonicalizer`.
+pub fn receipt_hash_sha256_with(val: &serde_json::Value, canonicalizer: &dyn Canonicalizer) -> Result<String, VerifyError> {
+    Ok(sha256_hex(canonical_string_with(val, canonicalizer, &[], Limits::DEFAULT)?.as_bytes()))
+}
+
+/// Expected `policy_hash` for a policy document: `0x` + sha256 of its sorted canonical JSON,
+/// the same convention as `receipt_hash_sha256`. Issuers must hash their policies this way
+/// for the result to match.
+pub fn policy_hash_from_doc(policy: &serde_json::Value) -> Result<String, VerifyError> {
+    Ok(format!("0x{}", try_receipt_hash_sha256(policy)?))
+}
+
+/// `policy_hash_from_doc` with an explicit canonicalization and hash, for issuers that use JCS
+/// or Poseidon.
+pub fn policy_hash_from_doc_with(
+    policy: &serde_json::Value,
+    canon: CanonMode,
+    hash: &HashScheme,
+) -> Result<String, VerifyError> {
+    Ok(format!("0x{}", hash.hash_hex(canonical_string_mode(policy, canon)?.as_bytes())?))
+}
+
 /// Ethereum personal_sign hash (EIP-191) over bytes.
 pub fn personal_hash_keccak(bytes: &[u8]) -> [u8; 32] {
     let prefix = format!("\u{19}Ethereum Signed Message:\n{}", bytes.len());
@@ -113,32 +913,338 @@ pub fn keccak256(bytes: &[u8]) -> [u8; 32] {
     out
 }
 
-/// Recover address from a 65-byte signature (r,s,v) over given hash.
-pub fn recover_address(sig_hex: &str, msg_hash: [u8; 32]) -> Result<[u8; 20], VerifyError> {
-    let sig_bytes = Vec::from_hex(normalize_hex_even(sig_hex))
-        .map_err(|e| VerifyError::Hex(e.to_string()))?;
-    if sig_bytes.len() != 65 {
-        return Err(VerifyError::Sig("expected 65-byte signature".into()));
-    }
-    let v = sig_bytes[64];
-    let rec_byte = match v {
-        27 | 28 => v - 27,
-        _ => v % 4,
+/// EIP-55 mixed-case checksum encoding of an address (`0x`-prefixed).
+pub fn to_checksum_address(addr: [u8; 20]) -> String {
+    let lower = hex::encode(addr);
+    let hash = keccak256(lower.as_bytes());
+    let mut out = String::with_capacity(42);
+    out.push_str("0x");
+    for (i, c) in lower.chars().enumerate() {
+        let nibble = (hash[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0x0f;
+        out.push(if nibble >= 8 { c.to_ascii_uppercase() } else { c });
+    }
+    out
+}
+
+/// Expand an EIP-2098 compact signature (`r || yParityAndS`) to 65-byte `r || s || v`.
+pub fn eip2098_to_rsv(compact: &[u8; 64]) -> [u8; 65] {
+    let mut out = [0u8; 65];
+    out[..64].copy_from_slice(compact);
+    let y_parity = out[32] >> 7;
+    out[32] &= 0x7f;
+    out[64] = 27 + y_parity;
+    out
+}
+
+/// Compress a 65-byte low-S `r || s || v` signature to its EIP-2098 form.
+pub fn rsv_to_eip2098(sig: &[u8; 65]) -> Result<[u8; 64], VerifyError> {
+    let y_parity = match sig[64] {
+        0 | 27 => 0,
+        1 | 28 => 1,
+        v => return Err(VerifyError::Sig(format!("unsupported v {} for EIP-2098", v))),
     };
+    if sig[32] & 0x80 != 0 {
+        return Err(VerifyError::MalleableSignature);
+    }
+    let mut out = [0u8; 64];
+    out.copy_from_slice(&sig[..64]);
+    out[32] |= y_parity << 7;
+    Ok(out)
+}
+
+/// Recover address from a 65-byte signature (r,s,v), or a 64-byte EIP-2098 compact signature,
+/// over given hash. The signature may be hex or base64 (`SigEncoding::Auto`).
+/// High-S (malleable) signatures are rejected; see `recover_address_opts`.
+pub fn recover_address(sig_hex: &str, msg_hash: [u8; 32]) -> Result<[u8; 20], VerifyError> {
+    recover_address_opts(sig_hex, msg_hash, false)
+}
+
+/// `recover_address`, optionally accepting high-S signatures from legacy signers.
+/// Accepted high-S signatures are normalized to low-S (with the recovery parity flipped) first.
+pub fn recover_address_opts(sig_hex: &str, msg_hash: [u8; 32], allow_high_s: bool) -> Result<[u8; 20], VerifyError> {
+    recover_address_ext(sig_hex, msg_hash, allow_high_s).map(|(addr, _)| addr)
+}
+
+/// `recover_address_opts`, also returning the chain id when `v` is EIP-155 encoded
+/// (`v = chainId * 2 + 35 + parity`). For chain ids above 110, `v` may be given as several
+/// big-endian bytes after `r || s`.
+pub fn recover_address_ext(
+    sig_hex: &str,
+    msg_hash: [u8; 32],
+    allow_high_s: bool,
+) -> Result<([u8; 20], Option<u64>), VerifyError> {
+    let (rs, v) = parse_sig(sig_hex)?;
+    let (mut rec_byte, chain_id) = recovery_parity(v)?;
+    let (rsig, flipped) = low_s_signature(&rs, allow_high_s)?;
+    if flipped {
+        rec_byte ^= 1;
+    }
     let rec_id = RecoveryId::from_byte(rec_byte).ok_or_else(|| VerifyError::Sig("bad recovery id".into()))?;
-    let rsig = KSig::from_slice(&sig_bytes[..64])
-        .map_err(|e| VerifyError::Sig(e.to_string()))?
-        ;
     let vk = VerifyingKey::recover_from_prehash(&msg_hash, &rsig, rec_id)
         .map_err(|e| VerifyError::Sig(e.to_string()))?;
-    let pubkey_bytes = vk.to_encoded_point(false);
-    let mut k = Keccak::v256();
-    let mut out = [0u8; 32];
-    k.update(&pubkey_bytes.as_bytes()[1..]);
-    k.finalize(&mut out);
+    Ok((address_of_key(&vk), chain_id))
+}
+
+/// `r || s` of a DER-encoded ECDSA signature (a `0x30` SEQUENCE of two INTEGERs, as HSMs emit
+/// them), or `None` when `sig` is not DER. DER carries no recovery id; see
+/// `recover_der_candidates`.
+pub fn der_signature_rs(sig: &str) -> Result<Option<[u8; 64]>, VerifyError> {
+    let bytes = decode_signature(sig, SigEncoding::Auto)?;
+    if bytes.first() != Some(&0x30) {
+        return Ok(None);
+    }
+    Ok(KSig::from_der(&bytes).ok().map(|s| s.to_bytes().into()))
+}
+
+/// The addresses a DER signature can recover to over `msg_hash`, one per recovery id (0, then
+/// 1); a high-S `s` is normalized first when `allow_high_s` (`MalleableSignature` otherwise).
+pub fn recover_der_candidates(rs: &[u8; 64], msg_hash: [u8; 32], allow_high_s: bool) -> Result<Vec<[u8; 20]>, VerifyError> {
+    let (rsig, _) = low_s_signature(rs, allow_high_s)?;
+    let candidates: Vec<[u8; 20]> = [0u8, 1]
+        .into_iter()
+        .filter_map(|id| VerifyingKey::recover_from_prehash(&msg_hash, &rsig, RecoveryId::from_byte(id)?).ok())
+        .map(|vk| address_of_key(&vk))
+        .collect();
+    if candidates.is_empty() {
+        return Err(VerifyError::Sig("DER signature does not recover to any key".into()));
+    }
+    Ok(candidates)
+}
+
+/// `recover_address_ext`, resolving a DER signature to the candidate in `expected` (or, without
+/// one, in `opts.gateway_allowlist`). `AddressMismatch` lists both candidates when neither
+/// matches; with nothing to match against the recovery is ambiguous (`Sig`).
+pub(crate) fn recover_expecting(
+    sig: &str,
+    msg_hash: [u8; 32],
+    expected: Option<&[[u8; 20]]>,
+    opts: &VerifyOptions,
+) -> Result<([u8; 20], Option<u64>), VerifyError> {
+    let Some(rs) = der_signature_rs(sig)? else {
+        return recover_address_ext(sig, msg_hash, opts.allow_high_s);
+    };
+    let candidates = recover_der_candidates(&rs, msg_hash, opts.allow_high_s)?;
+    let listed = candidates.iter().map(|c| to_checksum_address(*c)).collect::<Vec<_>>().join(" or ");
+    let Some(pool) = expected.or(opts.gateway_allowlist.as_deref()) else {
+        return Err(VerifyError::Sig(format!(
+            "ambiguous DER signature (no recovery id): signed by {}; give the expected gateway",
+            listed
+        )));
+    };
+    candidates
+        .into_iter()
+        .find(|c| ct::ct_position(pool, c).is_some())
+        .map(|c| (c, None))
+        .ok_or(VerifyError::AddressMismatch { recovered: listed })
+}
+
+/// Recovery parity and EIP-155 chain id from `v`: raw `0`/`1`, legacy `27`/`28`, or
+/// `chainId * 2 + 35 + parity`. Anything else (`2..=26`, `29..=34`) is rejected.
+fn recovery_parity(v: u64) -> Result<(u8, Option<u64>), VerifyError> {
+    match v {
+        0 | 1 => Ok((v as u8, None)),
+        27 | 28 => Ok(((v - 27) as u8, None)),
+        35.. => Ok((((v - 35) % 2) as u8, Some((v - 35) / 2))),
+        _ => Err(VerifyError::Sig(format!("invalid recovery id v = {}", v))),
+    }
+}
+
+/// How a signature string is encoded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SigEncoding {
+    /// Hex when `0x`-prefixed or made only of hex digits (even length), base64 otherwise.
+    #[default]
+    Auto,
+    Hex,
+    /// Standard or URL-safe alphabet, padded or not.
+    Base64,
+}
+
+impl std::str::FromStr for SigEncoding {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "auto" => Ok(SigEncoding::Auto),
+            "hex" => Ok(SigEncoding::Hex),
+            "base64" => Ok(SigEncoding::Base64),
+            other => Err(format!("unknown signature encoding {other} (expected auto|hex|base64)")),
+        }
+    }
+}
+
+impl SigEncoding {
+    /// The concrete encoding `sig` is read with.
+    fn resolve(self, sig: &str) -> SigEncoding {
+        match self {
+            SigEncoding::Auto if sig.starts_with("0x") || sig.starts_with("0X") => SigEncoding::Hex,
+            SigEncoding::Auto if sig.len().is_multiple_of(2) && sig.bytes().all(|b| b.is_ascii_hexdigit()) => SigEncoding::Hex,
+            SigEncoding::Auto => SigEncoding::Base64,
+            other => other,
+        }
+    }
+}
+
+/// Decode a signature string to its raw bytes.
+pub fn decode_signature(sig: &str, encoding: SigEncoding) -> Result<Vec<u8>, VerifyError> {
+    use base64::engine::{general_purpose::GeneralPurposeConfig, DecodePaddingMode, GeneralPurpose};
+    use base64::{alphabet, Engine};
+    let sig = sig.trim();
+    match encoding.resolve(sig) {
+        SigEncoding::Base64 => {
+            let alphabet = if sig.contains(['-', '_']) { &alphabet::URL_SAFE } else { &alphabet::STANDARD };
+            let config = GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent);
+            GeneralPurpose::new(alphabet, config)
+                .decode(sig)
+                .map_err(|e| VerifyError::Sig(format!("signature is not valid base64: {}", e)))
+        }
+        _ => hex_bytes(sig, false).map_err(|e| VerifyError::Sig(format!("signature is not valid hex: {}", e))),
+    }
+}
+
+/// `sig` in the hex form `Witness::signature_hex` and the proof pipeline expect: hex input is
+/// returned as given, base64 is decoded and re-encoded as `0x` hex.
+pub fn signature_hex(sig: &str, encoding: SigEncoding) -> Result<String, VerifyError> {
+    let bytes = decode_signature(sig, encoding)?;
+    Ok(match encoding.resolve(sig.trim()) {
+        SigEncoding::Base64 => format!("0x{}", hex::encode(bytes)),
+        _ => sig.trim().to_string(),
+    })
+}
+
+/// Split a signature (hex or base64, see `SigEncoding::Auto`) into `r || s` and `v`: 65-byte
+/// `r || s || v`, 64-byte EIP-2098 compact, or `r || s` followed by a 2..=8-byte big-endian `v`.
+fn parse_sig(sig: &str) -> Result<([u8; 64], u64), VerifyError> {
+    let sig_bytes = decode_signature(sig, SigEncoding::Auto)?;
+    if let Ok(compact) = <[u8; 64]>::try_from(sig_bytes.as_slice()) {
+        let rsv = eip2098_to_rsv(&compact);
+        return Ok((rsv[..64].try_into().expect("64 bytes"), rsv[64] as u64));
+    }
+    if !(65..=72).contains(&sig_bytes.len()) {
+        return Err(VerifyError::Sig("expected 65-byte or 64-byte (EIP-2098) signature".into()));
+    }
+    let v = sig_bytes[64..].iter().fold(0u64, |acc, b| (acc << 8) | *b as u64);
+    Ok((sig_bytes[..64].try_into().expect("64 bytes"), v))
+}
+
+/// The low-S `(r, s)` of a signature, and whether `s` had to be normalized.
+fn low_s_signature(rs: &[u8; 64], allow_high_s: bool) -> Result<(KSig, bool), VerifyError> {
+    let rsig = KSig::from_slice(rs).map_err(|e| VerifyError::Sig(e.to_string()))?;
+    // normalize_s returns Some only when s > n/2.
+    match rsig.normalize_s() {
+        Some(_) if !allow_high_s => Err(VerifyError::MalleableSignature),
+        Some(low) => Ok((low, true)),
+        None => Ok((rsig, false)),
+    }
+}
+
+/// Ethereum address of a secp256k1 key: the last 20 bytes of keccak(uncompressed x || y).
+pub(crate) fn address_of_key(vk: &VerifyingKey) -> [u8; 20] {
+    let point = vk.to_encoded_point(false);
+    let hash = keccak256(&point.as_bytes()[1..]);
     let mut addr = [0u8; 20];
-    addr.copy_from_slice(&out[12..]);
-    Ok(addr)
+    addr.copy_from_slice(&hash[12..]);
+    addr
+}
+
+fn parse_pubkey(pubkey_hex: &str) -> Result<VerifyingKey, VerifyError> {
+    let bytes = hex_bytes(pubkey_hex, false).map_err(|e| VerifyError::Hex(format!("public key: {}", e)))?;
+    if !matches!(bytes.len(), 33 | 65) {
+        return Err(VerifyError::Sig(format!(
+            "public key must be 33 (compressed) or 65 (uncompressed) bytes, got {}",
+            bytes.len()
+        )));
+    }
+    VerifyingKey::from_sec1_bytes(&bytes).map_err(|e| VerifyError::Sig(format!("invalid public key: {}", e)))
+}
+
+/// Address of a SEC1 secp256k1 public key, 33-byte compressed or 65-byte uncompressed.
+pub fn address_from_pubkey(pubkey_hex: &str) -> Result<[u8; 20], VerifyError> {
+    parse_pubkey(pubkey_hex).map(|vk| address_of_key(&vk))
+}
+
+/// How the gateway produced the signature over the receipt.
+#[derive(Debug, Clone, Default)]
+pub enum SigScheme {
+    /// EIP-191 personal_sign over the canonical JSON, digested per `VerifyOptions::digest`.
+    #[default]
+    PersonalSign,
+    /// EIP-712 typed data: the receipt's top-level keys are the primary struct's fields.
+    Eip712 { domain: Eip712Domain, receipt_type: ReceiptType },
+}
+
+/// Knobs for `verify_receipt_with`; `Default` matches `verify_receipt`.
+#[derive(Debug, Clone, Default)]
+pub struct VerifyOptions {
+    pub scheme: SigScheme,
+    /// What the personal_sign signer hashed; ignored for EIP-712.
+    pub digest: DigestMode,
+    pub canon: CanonMode,
+    /// Digest returned as the receipt hash (and used in public inputs and replay keys).
+    pub hash: HashScheme,
+    /// Accept high-S signatures (legacy signers); off by default.
+    pub allow_high_s: bool,
+    /// Reject receipts already accepted before; checked last so failed receipts are not recorded.
+    pub replay: Option<ReplayGuard>,
+    /// Verification time in unix seconds; `None` uses the system clock.
+    pub now: Option<u64>,
+    /// Reject receipts issued longer ago than this.
+    pub max_age: Option<Duration>,
+    /// Tolerance applied to every time-window comparison.
+    pub clock_skew: Duration,
+    /// Top-level keys left out of the signed canonical form; `None` means
+    /// `DEFAULT_EXCLUDED_FIELDS`. Signer and verifier must agree on this set, or every
+    /// signature fails to verify.
+    pub excluded_fields: Option<Vec<String>>,
+    /// JSON pointer (RFC 6901, e.g. `/payload`) to the sub-tree the gateway signed; that
+    /// sub-tree alone is canonicalized, hashed and signed, and `excluded_fields` is ignored.
+    /// Fields outside it (including policy/consent) are then not covered by the signature.
+    pub sign_path: Option<String>,
+    /// JSON pointers (into the signed tree) to arrays that are unordered sets: their elements are
+    /// sorted by canonical JSON before the receipt is canonicalized, so any order yields the same
+    /// receipt hash and personal_sign digest. Empty (the default) preserves array order. Signer
+    /// and verifier must sort the same arrays, or every signature fails to verify; EIP-712
+    /// digests and `signed_raw` bytes are not affected.
+    pub sort_arrays_at: Vec<String>,
+    /// Require an EIP-155 encoded `v` carrying this chain id (`ChainIdMismatch` otherwise).
+    /// Not checked for EIP-1271 contract signatures.
+    pub expected_chain_id: Option<u64>,
+    /// Deepest object/array nesting accepted in the receipt; `None` means `DEFAULT_MAX_DEPTH`.
+    pub max_depth: Option<usize>,
+    /// Largest canonical form accepted, in bytes; `None` means `DEFAULT_MAX_CANONICAL_BYTES`.
+    pub max_canonical_bytes: Option<usize>,
+    /// Canonicalization with tenant rewrites; replaces `canon` when set. The gateway must
+    /// have signed with the same one.
+    pub canonicalizer: Option<std::sync::Arc<dyn Canonicalizer>>,
+    /// Exact bytes the signer signed (e.g. `JSON.stringify(receipt)`), hashed and signed over
+    /// instead of any canonical form. They must parse, without duplicate keys, to the receipt's
+    /// signed fields (`ReceiptField` on `receipt_raw` otherwise), so every check still reads
+    /// the signed values; trusting the signer's serialization is the price.
+    pub signed_raw: Option<std::sync::Arc<str>>,
+    /// Verify the given signature even when the receipt embeds a different `receipt_sig`.
+    pub ignore_embedded_sig: bool,
+    /// Require the signer to be the receipt's own `gateway` field, when it has one, in any form
+    /// `parse_gateway` takes (`SelfGatewayMismatch` otherwise).
+    pub check_self_gateway: bool,
+    /// Receipt hash (under `hash`) the receipt must have, e.g. from an anchoring event; checked
+    /// before any signature work (`ReceiptHashMismatch` otherwise).
+    pub expected_receipt_hash: Option<HashHex>,
+    /// JSON pointers to the policy and consent hashes for receipts that nest them (e.g.
+    /// `/meta/policy_hash`); `None` reads the top-level `policy_hash` / `consent_snapshot_hash`.
+    pub policy_hash_path: Option<String>,
+    pub consent_hash_path: Option<String>,
+    /// Signers accepted even when no fixed gateway is expected (`UnauthorizedGateway`
+    /// otherwise), so `--gateway auto` still rejects unknown keys.
+    pub gateway_allowlist: Option<Vec<[u8; 20]>>,
+    /// Require the signer to be an authorized gateway in this registry at the anchor block
+    /// (`UnauthorizedGateway` otherwise).
+    #[cfg(feature = "rpc")]
+    pub registry: Option<std::sync::Arc<dyn GatewayRegistry>>,
+    /// Schema the raw receipt must satisfy before anything else is checked.
+    #[cfg(feature = "schema")]
+    pub schema: Option<std::sync::Arc<ReceiptSchema>>,
+    /// Reuse canonical forms and receipt hashes of receipts seen before.
+    #[cfg(feature = "cache")]
+    pub cache: Option<std::sync::Arc<VerifierCache>>,
 }
 
 /// Verify the receipt signature and policy/consent hashes against expected values.
@@ -149,32 +1255,692 @@ pub fn verify_receipt(
     expected_policy_hash: &str,
     expected_consent_hash: &str,
 ) -> Result<(String, [u8; 20]), VerifyError> {
-    // Strip fields not covered by the signature (receipt_sig, anchor).
-    let mut base = receipt_val.clone();
-    if let Some(obj) = base.as_object_mut() {
-        obj.remove("receipt_sig");
-        obj.remove("anchor");
-    }
-    let canon = canonical_json(&base);
-    let canon_str = serde_json::to_string(&canon).map_err(|e| VerifyError::Serde(e.to_string()))?;
-    // hash for public signal
-    let rcpt_hash = receipt_hash_sha256(&canon);
-    // personal_sign digest for signature recovery: keccak(canonical_json) then EIP-191 keccak
-    let digest_bytes = keccak256(canon_str.as_bytes());
-    let digest = personal_hash_keccak(&digest_bytes);
-    let addr = recover_address(receipt_sig_hex, digest)?;
-    if let Some(exp) = expected_gateway {
-        if addr != exp {
-            return Err(VerifyError::AddressMismatch);
-        }
-    }
-    // Check policy/consent fields inside receipt if present
-    let policy_ok = receipt_val.get("policy_hash").and_then(|v| v.as_str()) == Some(expected_policy_hash);
-    let consent_ok = receipt_val.get("consent_snapshot_hash").and_then(|v| v.as_str()) == Some(expected_consent_hash);
-    if !(policy_ok && consent_ok) {
-        return Err(VerifyError::PolicyConsentMismatch);
-    }
-    Ok((rcpt_hash, addr))
+    verify_receipt_with(
+        receipt_val,
+        receipt_sig_hex,
+        expected_gateway,
+        expected_policy_hash,
+        expected_consent_hash,
+        &VerifyOptions::default(),
+    )
+}
+
+/// Same as `verify_receipt`, for receipts signed as EIP-712 typed data.
+pub fn verify_receipt_712(
+    receipt_val: &serde_json::Value,
+    receipt_sig_hex: &str,
+    domain: &Eip712Domain,
+    receipt_type: &ReceiptType,
+    expected_gateway: Option<[u8; 20]>,
+    expected_policy_hash: &str,
+    expected_consent_hash: &str,
+) -> Result<(String, [u8; 20]), VerifyError> {
+    let opts = VerifyOptions {
+        scheme: SigScheme::Eip712 { domain: domain.clone(), receipt_type: receipt_type.clone() },
+        ..Default::default()
+    };
+    verify_receipt_with(
+        receipt_val,
+        receipt_sig_hex,
+        expected_gateway,
+        expected_policy_hash,
+        expected_consent_hash,
+        &opts,
+    )
+}
+
+impl VerifyOptions {
+    /// The top-level keys excluded from the signature.
+    pub fn excluded(&self) -> Vec<&str> {
+        match &self.excluded_fields {
+            Some(fields) => fields.iter().map(String::as_str).collect(),
+            None => DEFAULT_EXCLUDED_FIELDS.to_vec(),
+        }
+    }
+
+    /// `digest` if the scheme is personal_sign.
+    pub(crate) fn personal_digest_mode(&self) -> Option<DigestMode> {
+        matches!(self.scheme, SigScheme::PersonalSign).then_some(self.digest)
+    }
+
+    fn limits(&self) -> Limits {
+        Limits {
+            max_depth: self.max_depth.unwrap_or(DEFAULT_MAX_DEPTH),
+            max_bytes: self.max_canonical_bytes.unwrap_or(DEFAULT_MAX_CANONICAL_BYTES),
+        }
+    }
+}
+
+/// The signed sub-tree and the keys excluded from it.
+fn signed_part<'a>(
+    receipt_val: &'a serde_json::Value,
+    opts: &'a VerifyOptions,
+) -> Result<(&'a serde_json::Value, Vec<&'a str>), VerifyError> {
+    // Fields not covered by the signature (receipt_sig, anchor by default) are skipped while
+    // serializing, so the receipt tree is never copied. A detached sub-tree is signed as-is.
+    match &opts.sign_path {
+        Some(ptr) => {
+            let sub = receipt_val
+                .pointer(ptr)
+                .ok_or_else(|| VerifyError::Serde(format!("sign path `{}` does not resolve in the receipt", ptr)))?;
+            Ok((sub, Vec::new()))
+        }
+        None => Ok((receipt_val, opts.excluded())),
+    }
+}
+
+/// `val` with the arrays at `pointers` sorted by their elements' canonical JSON in `mode`,
+/// innermost pointers first so outer pointers' indices still name the original elements.
+/// Pointers that do not resolve are skipped (the set is absent); anything but an array there is
+/// a `ReceiptField` error.
+fn sort_arrays<'a>(
+    val: &'a serde_json::Value,
+    pointers: &[String],
+    mode: CanonMode,
+    limits: Limits,
+) -> Result<std::borrow::Cow<'a, serde_json::Value>, VerifyError> {
+    if pointers.is_empty() {
+        return Ok(std::borrow::Cow::Borrowed(val));
+    }
+    let mut pointers: Vec<&String> = pointers.iter().collect();
+    pointers.sort_by_key(|p| std::cmp::Reverse(p.matches('/').count()));
+    let mut owned = val.clone();
+    for ptr in pointers {
+        let Some(target) = owned.pointer_mut(ptr) else { continue };
+        let serde_json::Value::Array(items) = target else {
+            return Err(VerifyError::ReceiptField { field: ptr.trim_start_matches('/').into(), reason: "sort_arrays_at: not an array".into() });
+        };
+        let mut keyed = std::mem::take(items)
+            .into_iter()
+            .map(|item| canonical_string_skip(&item, mode, &[], limits).map(|key| (key, item)))
+            .collect::<Result<Vec<_>, _>>()?;
+        keyed.sort_by(|a, b| a.0.cmp(&b.0));
+        *items = keyed.into_iter().map(|(_, item)| item).collect();
+    }
+    Ok(std::borrow::Cow::Owned(owned))
+}
+
+/// Canonical JSON of the signed part and its receipt hash, from `opts.cache` when it has them.
+pub(crate) fn canonical_and_hash(receipt_val: &serde_json::Value, opts: &VerifyOptions) -> Result<(String, String), VerifyError> {
+    let _span = tracing::debug_span!("canonicalize", receipt_id = receipt_id_of(receipt_val)).entered();
+    check_nesting(receipt_val, &mut String::new(), 0, opts.limits().max_depth)?;
+    let compute = || {
+        let (signed, excluded) = signed_part(receipt_val, opts)?;
+        let mode = opts.canonicalizer.as_ref().map_or(opts.canon, |c| c.mode());
+        let canon_str = match (&opts.signed_raw, &opts.canonicalizer) {
+            (Some(raw), _) => raw_signed_form(raw, signed, &excluded, opts.limits())?,
+            (None, Some(canonicalizer)) => {
+                let signed = sort_arrays(signed, &opts.sort_arrays_at, mode, opts.limits())?;
+                canonical_string_with(&signed, canonicalizer.as_ref(), &excluded, opts.limits())?
+            }
+            (None, None) => {
+                let signed = sort_arrays(signed, &opts.sort_arrays_at, mode, opts.limits())?;
+                canonical_string_skip(&signed, opts.canon, &excluded, opts.limits())?
+            }
+        };
+        // hash for public signal
+        let rcpt_hash = opts.hash.hash_hex(canon_str.as_bytes())?;
+        tracing::debug!(canonical_bytes = canon_str.len(), receipt_hash = %rcpt_hash, "canonicalized");
+        Ok((canon_str, rcpt_hash))
+    };
+    #[cfg(feature = "cache")]
+    if let Some(cache) = &opts.cache {
+        return cache.get_or_compute(receipt_val, opts, compute);
+    }
+    compute()
+}
+
+/// Recompute the receipt hash (under `opts.hash`) and require it to be `expected`, without
+/// touching the signature. Returns the hash.
+pub fn check_receipt_hash(receipt_val: &serde_json::Value, expected: &str, opts: &VerifyOptions) -> Result<HashHex, VerifyError> {
+    let (_, rcpt_hash) = canonical_and_hash(receipt_val, opts)?;
+    let actual = HashHex::normalize(&rcpt_hash);
+    if !actual.matches(expected) {
+        return Err(VerifyError::ReceiptHashMismatch { expected: expected.to_string(), actual: actual.to_string() });
+    }
+    Ok(actual)
+}
+
+/// `opts.expected_receipt_hash` against the recomputed `rcpt_hash`, if one is set.
+pub(crate) fn check_expected_receipt_hash(rcpt_hash: &str, opts: &VerifyOptions) -> Result<(), VerifyError> {
+    match &opts.expected_receipt_hash {
+        Some(expected) if !expected.matches(rcpt_hash) => Err(VerifyError::ReceiptHashMismatch {
+            expected: expected.to_string(),
+            actual: HashHex::normalize(rcpt_hash).to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// `receipt_id` for log fields; empty when the receipt has none.
+pub(crate) fn receipt_id_of(receipt_val: &serde_json::Value) -> &str {
+    receipt_val.get("receipt_id").and_then(|v| v.as_str()).unwrap_or_default()
+}
+
+/// Canonical signed form, receipt hash and the digest the gateway signed, per `opts.scheme`.
+pub(crate) fn signing_digest(
+    receipt_val: &serde_json::Value,
+    opts: &VerifyOptions,
+) -> Result<(String, String, [u8; 32]), VerifyError> {
+    let (canon_str, rcpt_hash) = canonical_and_hash(receipt_val, opts)?;
+    check_expected_receipt_hash(&rcpt_hash, opts)?;
+    let digest = digest_of(receipt_val, &canon_str, opts)?;
+    Ok((canon_str, rcpt_hash, digest))
+}
+
+/// The digest the gateway signed over canonical form `canon_str` of `receipt_val`.
+pub(crate) fn digest_of(receipt_val: &serde_json::Value, canon_str: &str, opts: &VerifyOptions) -> Result<[u8; 32], VerifyError> {
+    let (signed, excluded) = signed_part(receipt_val, opts)?;
+    Ok(match &opts.scheme {
+        // personal_sign digest for signature recovery: EIP-191 keccak over the DigestMode bytes
+        SigScheme::PersonalSign => opts.digest.personal_digest(canon_str.as_bytes()),
+        // hashStruct only reads the type's fields; strip a copy only if the type names an unsigned one.
+        SigScheme::Eip712 { domain, receipt_type } if receipt_type.covers_any(&excluded) => {
+            let mut base = signed.clone();
+            if let Some(obj) = base.as_object_mut() {
+                for k in &excluded {
+                    obj.remove(*k);
+                }
+            }
+            eip712_digest(domain, receipt_type, &base)?
+        }
+        SigScheme::Eip712 { domain, receipt_type } => eip712_digest(domain, receipt_type, signed)?,
+    })
+}
+
+/// `Receipt::parse`, after the options' schema (if any) accepts the raw receipt.
+pub(crate) fn parse_receipt(receipt_val: &serde_json::Value, opts: &VerifyOptions) -> Result<ReceiptV1, VerifyError> {
+    check_nesting(receipt_val, &mut String::new(), 0, opts.limits().max_depth)?;
+    #[cfg(feature = "schema")]
+    if let Some(schema) = &opts.schema {
+        schema.validate(receipt_val)?;
+    }
+    Receipt::parse_with(receipt_val, opts.policy_hash_path.as_deref(), opts.consent_hash_path.as_deref())
+}
+
+/// Check policy/consent fields inside the receipt against the expected values, policy first.
+fn check_policy_consent<H: ExpectedHash + ?Sized>(
+    receipt: &ReceiptV1,
+    expected_policy_hash: &H,
+    expected_consent_hash: &H,
+) -> Result<(), VerifyError> {
+    check_policy_hash(receipt, expected_policy_hash)?;
+    check_consent_hash(receipt, expected_consent_hash)
+}
+
+/// An expected policy or consent hash: hex text (compared as `HashHex`, so `0x` and letter case
+/// do not matter) or a 32-byte digest, compared with the receipt field's decoded bytes.
+pub(crate) trait ExpectedHash {
+    fn matches_field(&self, field: &str) -> bool;
+    /// The expectation as reported in mismatch errors.
+    fn describe(&self) -> String;
+}
+
+impl ExpectedHash for str {
+    fn matches_field(&self, field: &str) -> bool {
+        HashHex::normalize(field).matches(self)
+    }
+
+    fn describe(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl ExpectedHash for [u8; 32] {
+    fn matches_field(&self, field: &str) -> bool {
+        let digits = field.strip_prefix("0x").or_else(|| field.strip_prefix("0X")).unwrap_or(field);
+        let mut decoded = [0u8; 32];
+        hex::decode_to_slice(digits, &mut decoded).is_ok_and(|()| decoded == *self)
+    }
+
+    fn describe(&self) -> String {
+        format!("0x{}", hex::encode(self))
+    }
+}
+
+pub(crate) fn check_policy_hash<H: ExpectedHash + ?Sized>(receipt: &ReceiptV1, expected_policy_hash: &H) -> Result<(), VerifyError> {
+    if !expected_policy_hash.matches_field(&receipt.policy_hash) {
+        return Err(VerifyError::PolicyMismatch {
+            expected: expected_policy_hash.describe(),
+            actual: receipt.policy_hash.clone(),
+        });
+    }
+    Ok(())
+}
+
+pub(crate) fn check_consent_hash<H: ExpectedHash + ?Sized>(receipt: &ReceiptV1, expected_consent_hash: &H) -> Result<(), VerifyError> {
+    if !expected_consent_hash.matches_field(&receipt.consent_snapshot_hash) {
+        return Err(VerifyError::ConsentMismatch {
+            expected: expected_consent_hash.describe(),
+            actual: receipt.consent_snapshot_hash.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Check `issued_at`/`expires_at` (and `max_age`, if set) against the verification time.
+pub(crate) fn check_time_window(receipt: &ReceiptV1, opts: &VerifyOptions) -> Result<(), VerifyError> {
+    let now = opts
+        .now
+        .unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0));
+    let skew = opts.clock_skew.as_secs();
+    let issued = receipt.issued_at();
+    if let Some(iat) = issued {
+        if iat > now.saturating_add(skew) {
+            return Err(VerifyError::NotYetValid(format!("issued_at {} is after now {}", iat, now)));
+        }
+    }
+    if let Some(exp) = receipt.expires_at {
+        if now > exp.saturating_add(skew) {
+            return Err(VerifyError::Expired(format!("expires_at {} is before now {}", exp, now)));
+        }
+    }
+    if let Some(max_age) = opts.max_age {
+        let iat = issued.ok_or_else(|| VerifyError::ReceiptField {
+            field: "issued_at".into(),
+            reason: "missing (required by max_age)".into(),
+        })?;
+        let age = now.saturating_sub(iat);
+        if age > max_age.as_secs().saturating_add(skew) {
+            return Err(VerifyError::Expired(format!("age {}s exceeds max_age {}s", age, max_age.as_secs())));
+        }
+    }
+    Ok(())
+}
+
+/// A receipt that passed verification, with the canonical form its hash and signature cover,
+/// so witness building does not canonicalize again.
+#[derive(Debug, Clone)]
+pub struct VerifiedReceipt {
+    pub receipt: ReceiptV1,
+    /// Canonical JSON of the receipt without the excluded fields (`receipt_sig` and `anchor`
+    /// by default).
+    pub canonical: String,
+    /// Receipt hash (hex, no `0x`) under the options' `HashScheme`.
+    pub receipt_hash: String,
+    /// Recovered signer, or the contract gateway for EIP-1271 receipts.
+    pub signer: [u8; 20],
+    /// Chain id from an EIP-155 encoded `v`.
+    pub chain_id: Option<u64>,
+    /// Scheme `receipt_hash` was computed with.
+    pub hash_scheme: HashScheme,
+    /// personal_sign digest mode the signature was checked with; `None` for other schemes.
+    pub digest_mode: Option<DigestMode>,
+}
+
+impl VerifiedReceipt {
+    /// `receipt_hash` in the `0x` form used in public inputs and outputs.
+    pub fn receipt_hash_hex(&self) -> HashHex {
+        HashHex::normalize(&self.receipt_hash)
+    }
+
+    /// `receipt_hash` if it was made with `scheme` (sha256 or keccak), else that digest of `canonical`.
+    fn receipt_hash_under(&self, scheme: HashScheme) -> HashHex {
+        if self.hash_scheme == scheme {
+            return self.receipt_hash_hex();
+        }
+        match scheme {
+            HashScheme::Keccak => HashHex::from_bytes(&keccak256(self.canonical.as_bytes())),
+            _ => HashHex::normalize(&sha256_hex(self.canonical.as_bytes())),
+        }
+    }
+
+    /// The receipt's anchoring epoch, `None` if its `anchor` carries neither field.
+    pub fn anchor_info(&self) -> Option<AnchorInfo> {
+        match (self.receipt.anchor_block_number(), self.receipt.anchor_timestamp()) {
+            (None, None) => None,
+            (anchor_block_number, anchor_timestamp) => Some(AnchorInfo { anchor_block_number, anchor_timestamp }),
+        }
+    }
+
+    /// Public inputs and witness for this receipt; the witness carries `canonical`.
+    pub fn public_and_witness(&self, signature_hex: &str) -> (PublicInputs, Witness) {
+        let receipt = &self.receipt;
+        let pub_inputs = PublicInputs {
+            receipt_hash: self.receipt_hash_hex(),
+            policy_hash: HashHex::normalize(&receipt.policy_hash),
+            consent_hash: HashHex::normalize(&receipt.consent_snapshot_hash),
+            gateway_address: format!("0x{}", hex::encode(self.signer)),
+            gateway_address_checksum: Some(to_checksum_address(self.signer)),
+            note: Some("Use these as public signals; feed canonical_receipt + sig as witness".into()),
+            canonicalization_version: Some(CANONICALIZATION_VERSION),
+            verifier_version: Some(VERIFIER_VERSION.to_string()),
+            anchor: self.anchor_info(),
+            hash_scheme: Some(self.hash_scheme.clone()),
+            digest_mode: self.digest_mode,
+            receipt_hash_sha256: Some(self.receipt_hash_under(HashScheme::Sha256)),
+            receipt_hash_keccak: Some(self.receipt_hash_under(HashScheme::Keccak)),
+        };
+        let witness = Witness {
+            canonical_receipt: self.canonical.clone(),
+            signature_hex: signature_hex.to_string(),
+            receipt_id: receipt.receipt_id.clone(),
+            anchor_tx_hash: receipt.anchor_tx_hash().map(|s| s.to_string()),
+            signature_commitment: None,
+        };
+        (pub_inputs, witness)
+    }
+}
+
+/// Verify the receipt signature and policy/consent hashes using the given options.
+pub fn verify_receipt_with(
+    receipt_val: &serde_json::Value,
+    receipt_sig_hex: &str,
+    expected_gateway: Option<[u8; 20]>,
+    expected_policy_hash: &str,
+    expected_consent_hash: &str,
+    opts: &VerifyOptions,
+) -> Result<(String, [u8; 20]), VerifyError> {
+    verify_receipt_full(receipt_val, receipt_sig_hex, expected_gateway, expected_policy_hash, expected_consent_hash, opts)
+        .map(|v| (v.receipt_hash, v.signer))
+}
+
+/// `verify_receipt_with`, returning the parsed receipt and its canonical form as well.
+pub fn verify_receipt_full(
+    receipt_val: &serde_json::Value,
+    receipt_sig_hex: &str,
+    expected_gateway: Option<[u8; 20]>,
+    expected_policy_hash: &str,
+    expected_consent_hash: &str,
+    opts: &VerifyOptions,
+) -> Result<VerifiedReceipt, VerifyError> {
+    let gateways = expected_gateway.as_ref().map(std::slice::from_ref);
+    verify_against(receipt_val, receipt_sig_hex, gateways, expected_policy_hash, expected_consent_hash, opts)
+        .map(|(v, _)| v)
+}
+
+/// `verify_receipt` with the policy and consent hashes as 32-byte digests (e.g. from an
+/// on-chain event), compared with the receipt's decoded hash fields instead of as hex text.
+pub fn verify_receipt_bytes(
+    receipt_val: &serde_json::Value,
+    receipt_sig_hex: &str,
+    expected_gateway: Option<[u8; 20]>,
+    expected_policy: [u8; 32],
+    expected_consent: [u8; 32],
+) -> Result<(String, [u8; 20]), VerifyError> {
+    verify_receipt_bytes_full(receipt_val, receipt_sig_hex, expected_gateway, expected_policy, expected_consent, &VerifyOptions::default())
+        .map(|v| (v.receipt_hash, v.signer))
+}
+
+/// `verify_receipt_full` with byte digests; see `verify_receipt_bytes`.
+pub fn verify_receipt_bytes_full(
+    receipt_val: &serde_json::Value,
+    receipt_sig_hex: &str,
+    expected_gateway: Option<[u8; 20]>,
+    expected_policy: [u8; 32],
+    expected_consent: [u8; 32],
+    opts: &VerifyOptions,
+) -> Result<VerifiedReceipt, VerifyError> {
+    let gateways = expected_gateway.as_ref().map(std::slice::from_ref);
+    verify_against(receipt_val, receipt_sig_hex, gateways, &expected_policy, &expected_consent, opts).map(|(v, _)| v)
+}
+
+/// Verify against an allowlist of gateways (e.g. old and new key during rotation). Passes if
+/// the recovered signer is any entry, and returns the index of the one that matched.
+pub fn verify_receipt_multi(
+    receipt_val: &serde_json::Value,
+    receipt_sig_hex: &str,
+    expected_gateways: &[[u8; 20]],
+    expected_policy_hash: &str,
+    expected_consent_hash: &str,
+    opts: &VerifyOptions,
+) -> Result<(VerifiedReceipt, usize), VerifyError> {
+    let (v, idx) = verify_against(
+        receipt_val,
+        receipt_sig_hex,
+        Some(expected_gateways),
+        expected_policy_hash,
+        expected_consent_hash,
+        opts,
+    )?;
+    Ok((v, idx.expect("an allowlist match always has an index")))
+}
+
+/// Recover who signed the receipt, without gateway, policy/consent, time or replay checks.
+pub fn recover_signer(receipt_val: &serde_json::Value, receipt_sig_hex: &str) -> Result<[u8; 20], VerifyError> {
+    recover_signer_with(receipt_val, receipt_sig_hex, &VerifyOptions::default())
+}
+
+/// `recover_signer` honoring the options' signature scheme, canonicalization and high-S policy.
+pub fn recover_signer_with(
+    receipt_val: &serde_json::Value,
+    receipt_sig_hex: &str,
+    opts: &VerifyOptions,
+) -> Result<[u8; 20], VerifyError> {
+    let (_, _, digest) = signing_digest(receipt_val, opts)?;
+    recover_expecting(receipt_sig_hex, digest, None, opts).map(|(addr, _)| addr)
+}
+
+/// Every signer `receipt_sig_hex` could be from: one address, or both candidates of a DER
+/// signature, which carries no recovery id (`recover_der_candidates`).
+pub fn recover_signer_candidates(
+    receipt_val: &serde_json::Value,
+    receipt_sig_hex: &str,
+    opts: &VerifyOptions,
+) -> Result<Vec<[u8; 20]>, VerifyError> {
+    let (_, _, digest) = signing_digest(receipt_val, opts)?;
+    match der_signature_rs(receipt_sig_hex)? {
+        Some(rs) => recover_der_candidates(&rs, digest, opts.allow_high_s),
+        None => recover_address_opts(receipt_sig_hex, digest, opts.allow_high_s).map(|addr| vec![addr]),
+    }
+}
+
+/// Verify against a public key pinned out-of-band instead of recovering the signer. The
+/// signature's `v` byte is ignored; `VerifiedReceipt::signer` is the key's address.
+pub fn verify_with_pubkey(
+    receipt_val: &serde_json::Value,
+    receipt_sig_hex: &str,
+    pubkey_hex: &str,
+    expected_policy_hash: &str,
+    expected_consent_hash: &str,
+    opts: &VerifyOptions,
+) -> Result<VerifiedReceipt, VerifyError> {
+    telemetry::observe_verify(|| {
+        let vk = parse_pubkey(pubkey_hex)?;
+        let receipt = parse_receipt(receipt_val, opts)?;
+        check_embedded_sig(receipt_val, receipt_sig_hex, opts)?;
+        let (canonical, rcpt_hash, digest) = signing_digest(receipt_val, opts)?;
+        // v is not needed to verify, but a malformed one is still an error; DER has none.
+        let (rs, chain_id) = match der_signature_rs(receipt_sig_hex)? {
+            Some(rs) => (rs, None),
+            None => {
+                let (rs, v) = parse_sig(receipt_sig_hex)?;
+                (rs, recover_chain_id(v)?)
+            }
+        };
+        let (rsig, _) = low_s_signature(&rs, opts.allow_high_s)?;
+        vk.verify_prehash(&digest, &rsig)
+            .map_err(|_| VerifyError::Sig("signature does not verify against the public key".into()))?;
+        check_chain_id(chain_id, opts)?;
+        let signer = address_of_key(&vk);
+        check_claims(&receipt, &rcpt_hash, signer, expected_policy_hash, expected_consent_hash, opts)?;
+        let (hash_scheme, digest_mode) = (opts.hash.clone(), opts.personal_digest_mode());
+        Ok(VerifiedReceipt { receipt, canonical, receipt_hash: rcpt_hash, signer, chain_id, hash_scheme, digest_mode })
+    })
+}
+
+fn recover_chain_id(v: u64) -> Result<Option<u64>, VerifyError> {
+    recovery_parity(v).map(|(_, chain_id)| chain_id)
+}
+
+/// Require the signature's EIP-155 chain id to be `opts.expected_chain_id`, if one is set.
+pub(crate) fn check_chain_id(chain_id: Option<u64>, opts: &VerifyOptions) -> Result<(), VerifyError> {
+    match opts.expected_chain_id {
+        Some(expected) if chain_id != Some(expected) => Err(VerifyError::ChainIdMismatch { expected, actual: chain_id }),
+        _ => Ok(()),
+    }
+}
+
+/// Checks shared by every verification path once the signer is established.
+pub(crate) fn check_claims<H: ExpectedHash + ?Sized>(
+    receipt: &ReceiptV1,
+    rcpt_hash: &str,
+    signer: [u8; 20],
+    expected_policy_hash: &H,
+    expected_consent_hash: &H,
+    opts: &VerifyOptions,
+) -> Result<(), VerifyError> {
+    check_policy_consent(receipt, expected_policy_hash, expected_consent_hash)?;
+    check_time_window(receipt, opts)?;
+    check_allowlist(signer, opts)?;
+    check_self_gateway(receipt, signer, opts)?;
+    #[cfg(feature = "rpc")]
+    if let Some(registry) = &opts.registry {
+        registry::check_registry(registry.as_ref(), signer, receipt)?;
+    }
+    if let Some(guard) = &opts.replay {
+        guard.check(rcpt_hash, signer, receipt.nonce)?;
+    }
+    Ok(())
+}
+
+/// `UnauthorizedGateway` unless `signer` is in `opts.gateway_allowlist` (if one is set).
+pub(crate) fn check_allowlist(signer: [u8; 20], opts: &VerifyOptions) -> Result<(), VerifyError> {
+    match &opts.gateway_allowlist {
+        Some(allowlist) if ct::ct_position(allowlist, &signer).is_none() => {
+            Err(VerifyError::UnauthorizedGateway(format!("{} is not in the gateway allowlist", to_checksum_address(signer))))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// With `opts.check_self_gateway`, `SelfGatewayMismatch` unless `signer` is the gateway the
+/// receipt declares; receipts without a `gateway` field pass.
+pub(crate) fn check_self_gateway(receipt: &ReceiptV1, signer: [u8; 20], opts: &VerifyOptions) -> Result<(), VerifyError> {
+    let Some(declared) = receipt.gateway.as_deref().filter(|_| opts.check_self_gateway) else {
+        return Ok(());
+    };
+    let declared_addr = parse_gateway(declared).map_err(|e| VerifyError::ReceiptField { field: "gateway".into(), reason: e.to_string() })?;
+    if !ct_eq_bytes(&declared_addr, &signer) {
+        return Err(VerifyError::SelfGatewayMismatch { declared: declared.to_string(), recovered: to_checksum_address(signer) });
+    }
+    Ok(())
+}
+
+/// Shared verification; `None` accepts any signer.
+/// Unless `opts.ignore_embedded_sig`, a receipt carrying its own `receipt_sig` must carry the
+/// signature being verified (compared as bytes, so hex and base64 forms of it agree).
+pub(crate) fn check_embedded_sig(receipt_val: &serde_json::Value, receipt_sig: &str, opts: &VerifyOptions) -> Result<(), VerifyError> {
+    let Some(embedded) = receipt_val.get("receipt_sig").filter(|v| !v.is_null()) else {
+        return Ok(());
+    };
+    if opts.ignore_embedded_sig {
+        return Ok(());
+    }
+    let same = match (embedded.as_str().map(|e| decode_signature(e, SigEncoding::Auto)), decode_signature(receipt_sig, SigEncoding::Auto)) {
+        (Some(Ok(embedded)), Ok(given)) => ct_eq_bytes(&embedded, &given),
+        _ => embedded.as_str() == Some(receipt_sig),
+    };
+    if !same {
+        return Err(VerifyError::Sig("provided signature does not match embedded receipt_sig".into()));
+    }
+    Ok(())
+}
+
+fn verify_against<H: ExpectedHash + ?Sized>(
+    receipt_val: &serde_json::Value,
+    receipt_sig_hex: &str,
+    expected_gateways: Option<&[[u8; 20]]>,
+    expected_policy_hash: &H,
+    expected_consent_hash: &H,
+    opts: &VerifyOptions,
+) -> Result<(VerifiedReceipt, Option<usize>), VerifyError> {
+    telemetry::observe_verify(|| {
+        let span = tracing::info_span!("verify", receipt_id = receipt_id_of(receipt_val), recovered_address = tracing::field::Empty);
+        let _entered = span.enter();
+        let receipt = parse_receipt(receipt_val, opts)?;
+        check_embedded_sig(receipt_val, receipt_sig_hex, opts)?;
+        let (canonical, rcpt_hash, digest) = signing_digest(receipt_val, opts)?;
+        let (addr, chain_id) = tracing::debug_span!("recover")
+            .in_scope(|| recover_expecting(receipt_sig_hex, digest, expected_gateways, opts))
+            .inspect_err(|e| tracing::debug!(error = %e, "signature recovery failed"))?;
+        span.record("recovered_address", tracing::field::display(to_checksum_address(addr)));
+        let matched = match expected_gateways {
+            Some(gws) => Some(
+                ct::ct_position(gws, &addr)
+                    .ok_or_else(|| VerifyError::AddressMismatch { recovered: to_checksum_address(addr) })?,
+            ),
+            None => None,
+        };
+        check_chain_id(chain_id, opts)?;
+        check_claims(&receipt, &rcpt_hash, addr, expected_policy_hash, expected_consent_hash, opts)
+            .inspect_err(|e| tracing::debug!(error = %e, "claim check failed"))?;
+        tracing::debug!(receipt_hash = %rcpt_hash, "verified");
+        let (hash_scheme, digest_mode) = (opts.hash.clone(), opts.personal_digest_mode());
+        Ok((VerifiedReceipt { receipt, canonical, receipt_hash: rcpt_hash, signer: addr, chain_id, hash_scheme, digest_mode }, matched))
+    })
+}
+
+/// Verify a receipt whose gateway may be a smart-contract wallet.
+///
+/// EOA recovery is tried first; if it does not yield `gateway` and `gateway` has code,
+/// the signature is checked via EIP-1271 `isValidSignature` over the same digest.
+pub fn verify_receipt_1271(
+    receipt_val: &serde_json::Value,
+    receipt_sig_hex: &str,
+    gateway: [u8; 20],
+    expected_policy_hash: &str,
+    expected_consent_hash: &str,
+    opts: &VerifyOptions,
+    eth: &dyn EthCall,
+) -> Result<(String, [u8; 20]), VerifyError> {
+    verify_receipt_1271_full(receipt_val, receipt_sig_hex, gateway, expected_policy_hash, expected_consent_hash, opts, eth)
+        .map(|v| (v.receipt_hash, v.signer))
+}
+
+/// `verify_receipt_1271`, returning a `VerifiedReceipt`.
+pub fn verify_receipt_1271_full(
+    receipt_val: &serde_json::Value,
+    receipt_sig_hex: &str,
+    gateway: [u8; 20],
+    expected_policy_hash: &str,
+    expected_consent_hash: &str,
+    opts: &VerifyOptions,
+    eth: &dyn EthCall,
+) -> Result<VerifiedReceipt, VerifyError> {
+    telemetry::observe_verify(|| {
+        let _span = tracing::info_span!("verify_1271", receipt_id = receipt_id_of(receipt_val), gateway = %to_checksum_address(gateway)).entered();
+        let receipt = parse_receipt(receipt_val, opts)?;
+        check_embedded_sig(receipt_val, receipt_sig_hex, opts)?;
+        let (canonical, rcpt_hash, digest) = signing_digest(receipt_val, opts)?;
+        let recovered =
+            tracing::debug_span!("recover").in_scope(|| recover_expecting(receipt_sig_hex, digest, Some(std::slice::from_ref(&gateway)), opts));
+        let chain_id = match recovered {
+            Ok((addr, chain_id)) if ct_eq_bytes(&addr, &gateway) => {
+                check_chain_id(chain_id, opts)?;
+                chain_id
+            }
+            recovered => {
+                if eth.code(gateway)?.is_empty() {
+                    return Err(match recovered {
+                        Ok((addr, _)) => VerifyError::AddressMismatch { recovered: to_checksum_address(addr) },
+                        Err(e) => e,
+                    });
+                }
+                tracing::debug!("EOA recovery did not yield the gateway; trying EIP-1271");
+                let sig_bytes = decode_signature(receipt_sig_hex, SigEncoding::Auto)?;
+                is_valid_signature(eth, gateway, digest, &sig_bytes)?;
+                None
+            }
+        };
+        check_claims(&receipt, &rcpt_hash, gateway, expected_policy_hash, expected_consent_hash, opts)?;
+        let (hash_scheme, digest_mode) = (opts.hash.clone(), opts.personal_digest_mode());
+        Ok(VerifiedReceipt { receipt, canonical, receipt_hash: rcpt_hash, signer: gateway, chain_id, hash_scheme, digest_mode })
+    })
+}
+
+/// The gateway address named by `gw`: a `did:` gateway DID, or hex (`0x` optional) of a
+/// 20-byte address or a 32-byte felt (its low 20 bytes). Every CLI, server, WASM and C entry
+/// point parses gateways with this.
+pub fn parse_gateway(gw: &str) -> Result<[u8; 20], VerifyError> {
+    if gw.starts_with("did:") {
+        return resolve_gateway_did(gw);
+    }
+    let gb = hex_bytes(gw, true).map_err(VerifyError::GatewayParse)?;
+    let slice: &[u8] = if gb.len() == 32 { &gb[12..] } else { &gb };
+    slice
+        .try_into()
+        .map_err(|_| VerifyError::GatewayParse(format!("gateway must be 20 bytes (or 32 felt), got {}", gb.len())))
 }
 
 /// Compute public inputs and witness for downstream Stwo circuit.
@@ -183,102 +1949,375 @@ pub fn build_public_and_witness(
     signature_hex: &str,
     gateway_hex: Option<&str>,
 ) -> Result<(PublicInputs, Witness), VerifyError> {
-    let expected_gateway = if let Some(gw) = gateway_hex {
-        let mut gateway_bytes = [0u8; 20];
-        let clean = normalize_hex_even(gw);
-        let gb = Vec::from_hex(&clean).map_err(|e| VerifyError::GatewayParse(e.to_string()))?;
-        let slice: &[u8] = if gb.len() == 32 { &gb[12..] } else { &gb };
-        if slice.len() != 20 {
-            return Err(VerifyError::GatewayParse(format!("gateway must be 20 bytes (or 32 felt), got {}", gb.len())));
-        }
-        gateway_bytes.copy_from_slice(slice);
-        Some(gateway_bytes)
-    } else {
-        None
-    };
-    let (rcpt_hash, addr) = verify_receipt(
+    build_public_and_witness_with(receipt_val, signature_hex, gateway_hex, &VerifyOptions::default())
+}
+
+/// `build_public_and_witness` with explicit verification options (e.g. the signature scheme).
+pub fn build_public_and_witness_with(
+    receipt_val: &serde_json::Value,
+    signature_hex: &str,
+    gateway_hex: Option<&str>,
+    opts: &VerifyOptions,
+) -> Result<(PublicInputs, Witness), VerifyError> {
+    let expected_gateway = gateway_hex.map(parse_gateway).transpose()?;
+    let receipt = parse_receipt(receipt_val, opts)?;
+    let verified = verify_receipt_full(
         receipt_val,
         signature_hex,
         expected_gateway,
-        receipt_val.get("policy_hash").and_then(|v| v.as_str()).unwrap_or_default(),
-        receipt_val.get("consent_snapshot_hash").and_then(|v| v.as_str()).unwrap_or_default(),
+        &receipt.policy_hash,
+        &receipt.consent_snapshot_hash,
+        opts,
     )?;
-    let canon = canonical_json(receipt_val);
-    let canon_str = serde_json::to_string(&canon).map_err(|e| VerifyError::Serde(e.to_string()))?;
-    let pub_inputs = PublicInputs {
-        receipt_hash: format!("0x{}", rcpt_hash),
-        policy_hash: receipt_val
-            .get("policy_hash")
-            .and_then(|v| v.as_str())
-            .unwrap_or_default()
-            .to_string(),
-        consent_hash: receipt_val
-            .get("consent_snapshot_hash")
-            .and_then(|v| v.as_str())
-            .unwrap_or_default()
-            .to_string(),
-        gateway_address: format!("0x{}", hex::encode(addr)),
-        note: Some("Use these as public signals; feed canonical_receipt + sig as witness".into()),
-    };
-    let witness = Witness {
-        canonical_receipt: canon_str,
-        signature_hex: signature_hex.to_string(),
-        receipt_id: receipt_val.get("receipt_id").and_then(|v| v.as_str()).map(|s| s.to_string()),
-        anchor_tx_hash: receipt_val
-            .get("anchor")
-            .and_then(|a| a.get("l2_tx"))
-            .and_then(|l| l.get("tx_hash"))
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string()),
+    Ok(verified.public_and_witness(signature_hex))
+}
+
+/// Build public inputs and witness for a receipt that has already been verified by the caller
+/// (e.g. via `verify_receipt_1271`), given its receipt hash and signer address. The witness
+/// uses the default sorted canonicalization; prefer `VerifiedReceipt::public_and_witness`.
+pub fn assemble_public_and_witness(
+    receipt_val: &serde_json::Value,
+    signature_hex: &str,
+    rcpt_hash: &str,
+    addr: [u8; 20],
+) -> Result<(PublicInputs, Witness), VerifyError> {
+    let verified = VerifiedReceipt {
+        receipt: Receipt::parse(receipt_val)?,
+        canonical: canonical_string_skip(receipt_val, CanonMode::Sorted, DEFAULT_EXCLUDED_FIELDS, Limits::DEFAULT)?,
+        receipt_hash: rcpt_hash.to_string(),
+        signer: addr,
+        chain_id: None,
+        hash_scheme: HashScheme::default(),
+        digest_mode: Some(DigestMode::default()),
     };
-    Ok((pub_inputs, witness))
-}
-
-/// Mock prover hook: hashes public inputs + witness to emit a proof stub.
-/// Replace with Stwo/LuminAIR prover when ready.
-pub fn mock_prove(pub_inputs: &PublicInputs, witness: &Witness) -> Proof {
-    Proof {
-        // Use the actual receipt signature as the “proof” payload to avoid the prior hash stub.
-        proof_id: format!("proof_{}", pub_inputs.receipt_hash.trim_start_matches("0x")),
-        proof: witness.signature_hex.clone(),
-        public_inputs: pub_inputs.clone(),
-        witness_summary: WitnessSummary {
-            receipt_id: witness.receipt_id.clone(),
-            anchor_tx_hash: witness.anchor_tx_hash.clone(),
-            canonical_len: witness.canonical_receipt.len(),
-        },
-        prover: "receipt_sig".into(),
-    }
-}
-
-/// External prover hook: call command in LUMINAIR_PROVER_CMD with stdin JSON {public_inputs, witness}.
-/// The command must return a JSON Proof on stdout.
-pub fn external_prove(pub_inputs: &PublicInputs, witness: &Witness) -> Result<Proof, VerifyError> {
-    let cmd_str = std::env::var("LUMINAIR_PROVER_CMD")
-        .map_err(|_| VerifyError::Prover("LUMINAIR_PROVER_CMD not set".into()))?;
-    let mut parts = cmd_str.split_whitespace();
-    let bin = parts.next().ok_or_else(|| VerifyError::Prover("empty LUMINAIR_PROVER_CMD".into()))?;
-    let args: Vec<&str> = parts.collect();
-    let mut child = Command::new(bin)
-        .args(args)
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .spawn()
-        .map_err(|e| VerifyError::Prover(e.to_string()))?;
-    if let Some(stdin) = child.stdin.as_mut() {
-        let payload = serde_json::json!({ "public_inputs": pub_inputs, "witness": witness });
-        stdin
-            .write_all(serde_json::to_string(&payload).unwrap().as_bytes())
-            .map_err(|e| VerifyError::Prover(e.to_string()))?;
-    }
-    let output = child.wait_with_output().map_err(|e| VerifyError::Prover(e.to_string()))?;
-    if !output.status.success() {
-        return Err(VerifyError::Prover(format!(
-            "prover failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        )));
+    Ok(verified.public_and_witness(signature_hex))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECEIPT: &str = include_str!("../../scripts/receipts/rcpt_1765778272_0cbf2a85.json");
+    const GATEWAY: &str = "0x3A66A80Ac10721e69b6A9Cd56B07282D9a094813";
+
+    /// The fixture receipt, the signature it embeds and its policy/consent hashes.
+    fn fixture() -> (serde_json::Value, String, String, String) {
+        let receipt: serde_json::Value = serde_json::from_str(RECEIPT).unwrap();
+        let sig = receipt["receipt_sig"].as_str().unwrap().to_string();
+        (receipt, sig, format!("0x{}", "ab".repeat(32)), format!("0x{}", "cd".repeat(32)))
+    }
+
+    /// The malleable twin of a low-S `r || s || v` signature (`v` = 27/28): `s` replaced by
+    /// `n - s` and the recovery parity flipped, which recovers the same key.
+    fn high_s_twin(sig: &str) -> String {
+        use k256::elliptic_curve::PrimeField;
+        let mut bytes = hex::decode(sig.trim_start_matches("0x")).unwrap();
+        let s = k256::Scalar::from_repr(<[u8; 32]>::try_from(&bytes[32..64]).unwrap().into()).unwrap();
+        bytes[32..64].copy_from_slice(&(-s).to_bytes());
+        bytes[64] = if bytes[64] == 27 { 28 } else { 27 };
+        hex::encode(bytes)
+    }
+
+    #[test]
+    fn high_s_is_rejected_unless_allowed() {
+        let (receipt, sig, policy, consent) = fixture();
+        let (_, _, digest) = signing_digest(&receipt, &VerifyOptions::default()).unwrap();
+        let gateway = parse_gateway(GATEWAY).unwrap();
+        let twin = high_s_twin(&sig);
+        assert_ne!(twin, sig);
+        assert!(matches!(recover_address(&twin, digest), Err(VerifyError::MalleableSignature)));
+        assert_eq!(recover_address_opts(&twin, digest, true).unwrap(), gateway);
+
+        let strict = VerifyOptions { ignore_embedded_sig: true, ..Default::default() };
+        let err = verify_receipt_with(&receipt, &twin, Some(gateway), &policy, &consent, &strict).unwrap_err();
+        assert!(matches!(err, VerifyError::MalleableSignature));
+        let lenient = VerifyOptions { allow_high_s: true, ..strict };
+        assert_eq!(verify_receipt_with(&receipt, &twin, Some(gateway), &policy, &consent, &lenient).unwrap().1, gateway);
+    }
+
+    #[test]
+    fn checksum_address_matches_eip55_vectors() {
+        // From EIP-55: all caps, all lowercase, then mixed case.
+        for expected in [
+            "0x52908400098527886E0F7030069857D2E4169EE7",
+            "0x8617E340B3D01FA5F11F306F4090FD50E238070D",
+            "0xde709f2102306220921060314715629080e2fb77",
+            "0x27b1fdb04752bbc536007a920d24acb045561c26",
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            let addr: [u8; 20] = hex::decode(&expected[2..]).unwrap().try_into().unwrap();
+            assert_eq!(to_checksum_address(addr), expected);
+        }
+    }
+
+    #[test]
+    fn eip2098_round_trips_for_both_parities() {
+        let key = k256::ecdsa::SigningKey::from_slice(&[0x11; 32]).unwrap();
+        let signer = address_of_key(key.verifying_key());
+        let mut parities = [false; 2];
+        for i in 0u8..=255 {
+            let digest = keccak256(&[i]);
+            let (sig, rec_id) = key.sign_prehash_recoverable(&digest).unwrap();
+            let mut rsv = [0u8; 65];
+            rsv[..64].copy_from_slice(&sig.to_bytes());
+            rsv[64] = 27 + rec_id.to_byte();
+            let compact = rsv_to_eip2098(&rsv).unwrap();
+            assert_eq!(compact[32] >> 7, rec_id.to_byte());
+            assert_eq!(eip2098_to_rsv(&compact), rsv);
+            assert_eq!(recover_address(&hex::encode(compact), digest).unwrap(), signer);
+            assert_eq!(recover_address(&hex::encode(rsv), digest).unwrap(), signer);
+            parities[usize::from(rec_id.to_byte())] = true;
+            if parities == [true, true] {
+                return;
+            }
+        }
+        panic!("256 signatures all had the same parity");
+    }
+
+    #[test]
+    fn exclusion_sets_shape_the_signed_form() {
+        let (receipt, sig, policy, consent) = fixture();
+        let canonical = |excluded: Option<&[&str]>| {
+            let opts = VerifyOptions {
+                excluded_fields: excluded.map(|e| e.iter().map(|s| s.to_string()).collect()),
+                ..Default::default()
+            };
+            signed_canonical_string(&receipt, &opts).unwrap()
+        };
+        let top_keys = |canonical: &str| {
+            let val: serde_json::Value = serde_json::from_str(canonical).unwrap();
+            val.as_object().unwrap().keys().cloned().collect::<Vec<_>>()
+        };
+
+        let default = canonical(None);
+        assert!(!top_keys(&default).iter().any(|k| k == "receipt_sig" || k == "anchor"));
+        assert_eq!(canonical(Some(DEFAULT_EXCLUDED_FIELDS)), default);
+
+        // Nothing excluded: every top-level key is signed.
+        let all = canonical(Some(&[]));
+        assert_eq!(top_keys(&all), receipt.as_object().unwrap().keys().cloned().collect::<Vec<_>>());
+        assert_eq!(all, canonical_string_mode(&receipt, CanonMode::Sorted).unwrap());
+
+        let custom = canonical(Some(&["receipt_sig", "anchor", "raw_result"]));
+        let keys = top_keys(&custom);
+        assert!(!keys.iter().any(|k| k == "raw_result" || k == "anchor"));
+        assert_eq!(keys.len(), top_keys(&default).len() - 1);
+
+        // The fixture was signed under the default set only.
+        let gateway = Some(parse_gateway(GATEWAY).unwrap());
+        let opts = |excluded: Vec<String>| VerifyOptions { excluded_fields: Some(excluded), ..Default::default() };
+        let extra = opts(vec!["receipt_sig".into(), "anchor".into(), "raw_result".into()]);
+        assert!(matches!(
+            verify_receipt_with(&receipt, &sig, gateway, &policy, &consent, &extra),
+            Err(VerifyError::AddressMismatch { .. })
+        ));
+        let defaults = opts(DEFAULT_EXCLUDED_FIELDS.iter().map(|s| s.to_string()).collect());
+        verify_receipt_with(&receipt, &sig, gateway, &policy, &consent, &defaults).unwrap();
+    }
+
+    #[test]
+    fn unportable_numbers_are_rejected() {
+        let canonical = |json: &str, mode| canonical_string_mode(&serde_json::from_str(json).unwrap(), mode);
+        for json in [
+            r#"{"a":1e400}"#,
+            r#"{"a":-1e400}"#,
+            r#"{"a":-0.0}"#,
+            r#"{"a":-0e5}"#,
+            r#"{"a":9007199254740993.5}"#,
+            r#"{"a":[1,{"b":1e400}]}"#,
+        ] {
+            for mode in [CanonMode::Sorted, CanonMode::Jcs] {
+                let err = canonical(json, mode).unwrap_err();
+                assert!(matches!(&err, VerifyError::Serde(m) if m.contains("outside the supported range")), "{json}: {err}");
+            }
+        }
+        let err = canonical(r#"{"a":[1,{"b":1e400}]}"#, CanonMode::Sorted).unwrap_err();
+        assert!(err.to_string().contains("at /a/1/b"), "{err}");
+
+        // Big integers are not doubles: any size is accepted, and kept as written when sorted.
+        for json in [r#"{"a":123456789012345678901}"#, r#"{"a":-123456789012345678901}"#, r#"{"a":9007199254740993}"#] {
+            assert_eq!(canonical(json, CanonMode::Sorted).unwrap(), json);
+            canonical(json, CanonMode::Jcs).unwrap();
+        }
+        // The literal -0 parses as the integer 0.
+        assert_eq!(canonical(r#"{"a":-0}"#, CanonMode::Sorted).unwrap(), r#"{"a":0}"#);
+        assert_eq!(canonical(r#"{"a":0,"b":0.0,"c":-1.5}"#, CanonMode::Sorted).unwrap(), r#"{"a":0,"b":0.0,"c":-1.5}"#);
+    }
+
+    #[test]
+    fn policy_doc_hash_mismatch_is_reported() {
+        let (receipt, sig, _, consent) = fixture();
+        let doc = serde_json::json!({ "purpose": "retrieval", "retention_days": 30, "scopes": ["read"] });
+        let hash = policy_hash_from_doc(&doc).unwrap();
+        assert_eq!(hash, format!("0x{}", sha256_hex(canonical_string_mode(&doc, CanonMode::Sorted).unwrap().as_bytes())));
+        assert_eq!(policy_hash_from_doc_with(&doc, CanonMode::Sorted, &HashScheme::Sha256).unwrap(), hash);
+        assert_eq!(hash, format!("0x{}", receipt_hash_sha256(&doc)));
+        assert_eq!(try_receipt_hash_sha256(&doc).unwrap(), receipt_hash_sha256(&doc));
+        // Key order does not matter; values do.
+        let reordered: serde_json::Value =
+            serde_json::from_str(r#"{"scopes":["read"],"retention_days":30,"purpose":"retrieval"}"#).unwrap();
+        assert_eq!(policy_hash_from_doc(&reordered).unwrap(), hash);
+        let edited = serde_json::json!({ "purpose": "retrieval", "retention_days": 31, "scopes": ["read"] });
+        assert_ne!(policy_hash_from_doc(&edited).unwrap(), hash);
+
+        // The fixture commits to a different policy.
+        let gateway = Some(parse_gateway(GATEWAY).unwrap());
+        let err = verify_receipt_with(&receipt, &sig, gateway, &hash, &consent, &VerifyOptions::default()).unwrap_err();
+        match &err {
+            VerifyError::PolicyMismatch { expected, actual } => {
+                assert_eq!(expected, &hash);
+                assert_eq!(actual, &receipt["policy_hash"]);
+            }
+            other => panic!("expected PolicyMismatch, got {other}"),
+        }
+        assert_eq!((err.code(), err.exit_code()), ("POLICY_MISMATCH", 3));
+    }
+
+    #[test]
+    fn deep_nesting_is_rejected_before_recursing() {
+        let (receipt, sig, policy, consent) = fixture();
+        let nested = |levels: usize| {
+            let mut receipt = receipt.clone();
+            let mut val = serde_json::Value::Null;
+            for _ in 0..levels {
+                val = serde_json::Value::Array(vec![val]);
+            }
+            receipt["raw_result"] = val;
+            receipt
+        };
+
+        // The receipt object is the first level.
+        let opts = VerifyOptions::default();
+        signed_canonical_string(&nested(DEFAULT_MAX_DEPTH - 1), &opts).unwrap();
+        let err = signed_canonical_string(&nested(DEFAULT_MAX_DEPTH), &opts).unwrap_err();
+        let at = format!("/raw_result{}", "/0".repeat(DEFAULT_MAX_DEPTH - 1));
+        assert_eq!(err.to_string(), format!("serde error: receipt too large/deep: nesting deeper than 64 at {at}"));
+
+        let mut deep = nested(10_000);
+        let err = signed_canonical_string(&deep, &opts).unwrap_err();
+        assert!(err.to_string().ends_with(&format!("nesting deeper than 64 at {at}")), "{err}");
+        let gateway = Some(parse_gateway(GATEWAY).unwrap());
+        for mode in [CanonMode::Sorted, CanonMode::Jcs] {
+            let opts = VerifyOptions { canon: mode, max_depth: Some(16), ..Default::default() };
+            let err = verify_receipt_with(&deep, &sig, gateway, &policy, &consent, &opts).unwrap_err();
+            assert!(err.to_string().contains("nesting deeper than 16"), "{err}");
+        }
+        assert!(recover_signer(&deep, &sig).unwrap_err().to_string().contains("nesting deeper than 64"));
+        // Unsigned fields are bounded too, since the whole receipt is deserialized.
+        let mut anchored = receipt.clone();
+        anchored["anchor"]["extra"] = nested(DEFAULT_MAX_DEPTH)["raw_result"].take();
+        let err = verify_receipt_with(&anchored, &sig, gateway, &policy, &consent, &opts).unwrap_err();
+        assert!(err.to_string().contains("nesting deeper than 64 at /anchor/extra/"), "{err}");
+        // Unwind the tree by hand; dropping it would recurse 10k levels.
+        let mut val = deep["raw_result"].take();
+        while let serde_json::Value::Array(mut arr) = val {
+            val = arr.pop().unwrap_or_default();
+        }
+    }
+
+    /// `r || s || v` (`v` = 27/28) by the test key 0x11..11 over a prehashed `digest`.
+    fn sign_digest(digest: [u8; 32]) -> String {
+        let key = k256::ecdsa::SigningKey::from_slice(&[0x11; 32]).unwrap();
+        let (sig, rid) = key.sign_prehash_recoverable(&digest).unwrap();
+        format!("0x{}{:02x}", hex::encode(sig.to_bytes()), 27 + rid.to_byte())
+    }
+
+    #[test]
+    fn each_digest_mode_recovers_only_its_own_signatures() {
+        let (receipt, _, policy, consent) = fixture();
+        let signer = parse_gateway("0x19E7E376E7C213B7E7e7e46cc70A5dD086DAff2A").unwrap();
+        let modes = [DigestMode::RawCanonical, DigestMode::Keccak, DigestMode::Sha256];
+        let opts = |digest| VerifyOptions { digest, ignore_embedded_sig: true, ..Default::default() };
+        let canonical = signed_canonical_string(&receipt, &opts(DigestMode::Keccak)).unwrap();
+
+        for mode in modes {
+            let sig = sign_digest(mode.personal_digest(canonical.as_bytes()));
+            for other in modes {
+                let recovered = recover_signer_with(&receipt, &sig, &opts(other)).unwrap();
+                assert_eq!(recovered == signer, other == mode, "signed {mode:?}, recovered as {other:?}");
+            }
+            let verified = verify_receipt_full(&receipt, &sig, Some(signer), &policy, &consent, &opts(mode)).unwrap();
+            assert_eq!(verified.digest_mode, Some(mode));
+            assert_eq!(verified.canonical, canonical);
+            let name = serde_json::to_value(mode).unwrap();
+            assert_eq!(name.as_str().unwrap().parse::<DigestMode>().unwrap(), mode);
+        }
+
+        // The modes hash the canonical form differently, and the fixture's gateway signed keccak.
+        assert_eq!(DigestMode::RawCanonical.personal_digest(canonical.as_bytes()), personal_hash_keccak(canonical.as_bytes()));
+        assert_eq!(DigestMode::Keccak.personal_digest(canonical.as_bytes()), personal_hash_keccak(&keccak256(canonical.as_bytes())));
+        assert_eq!(DigestMode::default(), DigestMode::Keccak);
+        assert_eq!(recover_signer(&receipt, receipt["receipt_sig"].as_str().unwrap()).unwrap(), parse_gateway(GATEWAY).unwrap());
+        assert!("blake3".parse::<DigestMode>().is_err());
+    }
+
+    #[test]
+    fn big_integers_are_canonicalized_digit_for_digit() {
+        let (receipt, ..) = fixture();
+        for digits in ["12345678901234567890", "123456789012345678901", "1234567890123456789012345678901234567890123"] {
+            for literal in [digits.to_string(), format!("-{digits}")] {
+                let mut json = receipt.clone();
+                json["amount"] = serde_json::Value::Null;
+                json["raw_result"] = serde_json::json!({ "block_number": null });
+                let text = json.to_string().replace("null", &literal);
+
+                #[cfg_attr(not(feature = "cbor"), allow(unused_mut))]
+                let mut parsed = vec![read_receipt(text.as_bytes()).unwrap(), parse_receipt_strict(&text).unwrap()];
+                // CBOR integers (bignums included) go up to 128 bits.
+                #[cfg(feature = "cbor")]
+                match to_cbor(&parsed[0]) {
+                    Ok(bytes) => parsed.push(read_receipt_cbor(bytes.as_slice()).unwrap()),
+                    Err(e) => assert!(digits.len() > 38 && e.to_string().contains("does not fit in 128 bits"), "{e}"),
+                }
+                for val in parsed {
+                    let canonical = canonical_string(&val, DEFAULT_EXCLUDED_FIELDS).unwrap();
+                    assert!(canonical.contains(&format!("\"amount\":{literal},")), "{canonical}");
+                    assert!(canonical.contains(&format!("{{\"block_number\":{literal}}}")), "{canonical}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sort_arrays_at_makes_set_arrays_order_independent() {
+        let (receipt, _, policy, consent) = fixture();
+        let with = |scopes: serde_json::Value, grants: serde_json::Value| {
+            let mut r = receipt.clone();
+            r["scopes"] = scopes;
+            r["grants"] = grants;
+            r
+        };
+        let a = with(
+            serde_json::json!(["read", "write", { "k": 1 }]),
+            serde_json::json!([{ "id": 1, "tags": ["x", "y"] }, { "id": 2, "tags": ["w", "z"] }]),
+        );
+        let b = with(
+            serde_json::json!([{ "k": 1 }, "write", "read"]),
+            serde_json::json!([{ "id": 2, "tags": ["z", "w"] }, { "id": 1, "tags": ["y", "x"] }]),
+        );
+        let sets: Vec<String> = ["/scopes", "/grants", "/grants/0/tags", "/grants/1/tags"].map(String::from).into();
+        let signer = parse_gateway("0x19E7E376E7C213B7E7e7e46cc70A5dD086DAff2A").unwrap();
+
+        for canon in [CanonMode::Sorted, CanonMode::Jcs] {
+            let opts = VerifyOptions { canon, sort_arrays_at: sets.clone(), ignore_embedded_sig: true, ..Default::default() };
+            let (canonical_a, hash_a, digest) = signing_digest(&a, &opts).unwrap();
+            let (canonical_b, hash_b, _) = signing_digest(&b, &opts).unwrap();
+            assert_eq!((&canonical_a, &hash_a), (&canonical_b, &hash_b));
+            let sig = sign_digest(digest);
+            assert_eq!(verify_receipt_with(&b, &sig, Some(signer), &policy, &consent, &opts).unwrap().1, signer);
+
+            let plain = VerifyOptions { canon, ignore_embedded_sig: true, ..Default::default() };
+            assert_ne!(signed_canonical_string(&a, &plain).unwrap(), signed_canonical_string(&b, &plain).unwrap());
+            assert!(matches!(
+                verify_receipt_with(&b, &sig, Some(signer), &policy, &consent, &plain),
+                Err(VerifyError::AddressMismatch { .. })
+            ));
+        }
+
+        let not_array = VerifyOptions { sort_arrays_at: vec!["/policy_hash".into()], ..Default::default() };
+        assert_eq!(signed_canonical_string(&a, &not_array).unwrap_err().code(), "RECEIPT_FIELD");
     }
-    let proof: Proof = serde_json::from_slice(&output.stdout)
-        .map_err(|e| VerifyError::Prover(e.to_string()))?;
-    Ok(proof)
 }
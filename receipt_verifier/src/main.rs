@@ -1,102 +1,1585 @@
+use anyhow::Context;
 use clap::Parser;
-use hex::FromHex;
-use receipt_verifier::{verify_receipt, build_public_and_witness};
+use receipt_verifier::{parse_batch, verify_batch_full, verify_receipt_multi, verify_proof_with, verify_receipt_full, Proof, CanonMode, Compression, DigestMode, Eip712Schema, FileSeenStore, HashFormat, HashHex, HashScheme, PoseidonParams, InputBundle, RawReceipt, ReplayGuard, SigEncoding, SigScheme, Profile, VerifyConfig, VerifyOptions};
+#[cfg(feature = "rpc")]
+use receipt_verifier::{verify_receipt_1271_full, RpcClient};
+use receipt_verifier::{ProveOpts, ProverRegistry};
+use receipt_verifier::{PublicInputs, VerifyError, Witness};
+use serde::Serialize;
+use serde_json::json;
 use std::fs;
-use std::path::PathBuf;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
 
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Text,
+    Json,
+}
+
+/// Encoding of receipt, proof and output files.
+#[cfg(feature = "cbor")]
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Wire {
+    Json,
+    Cbor,
+}
+
+/// Verify gateway-signed receipts and build, prove and check their public inputs.
 #[derive(Parser, Debug)]
-struct Args {
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    /// Output format: human-readable text, or a single JSON object on stdout (diagnostics on stderr)
+    #[arg(long, global = true, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    /// Log filter for stderr logs (e.g. debug, receipt_verifier=trace); defaults to RUST_LOG, else warn
+    #[arg(long, global = true)]
+    log_level: Option<String>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Verify a receipt (or a batch): signature, gateway, policy/consent, time window and replay
+    Verify(Box<VerifyCmd>),
+    /// Verify a receipt, then write its public inputs, witness and calldata
+    BuildInputs(Box<BuildInputsCmd>),
+    /// Verify a receipt (or a batch), build its inputs and prove them
+    Prove(Box<ProveCmd>),
+    /// Verify a receipt_sig proof against a receipt and the expected policy/consent hashes
+    VerifyProof(Box<VerifyProofCmd>),
+    /// Only canonicalize a receipt and print the recovered signer; no gateway, policy/consent, expiry or replay checks
+    Recover(Box<RecoverCmd>),
+}
+
+#[derive(clap::Args, Debug)]
+#[command(group(clap::ArgGroup::new("source").required(true).multiple(true).args(["receipt", "bundle_in", "batch", "receipts_dir"])))]
+struct VerifyCmd {
+    #[command(flatten)]
+    input: InputArgs,
+    #[command(flatten)]
+    batch: BatchArgs,
+    #[command(flatten)]
+    sig: SignatureArgs,
+    #[command(flatten)]
+    signing: SigningArgs,
+    #[command(flatten)]
+    checks: CheckArgs,
+    #[command(flatten)]
+    diag: DiagArgs,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(mut_arg("receipt", |a| a.required_unless_present("bundle_in")))]
+struct BuildInputsCmd {
+    #[command(flatten)]
+    input: InputArgs,
+    #[command(flatten)]
+    sig: SignatureArgs,
+    #[command(flatten)]
+    signing: SigningArgs,
+    #[command(flatten)]
+    checks: CheckArgs,
+    #[command(flatten)]
+    outputs: OutputArgs,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(group(clap::ArgGroup::new("source").required(true).multiple(true).args(["receipt", "bundle_in", "batch", "receipts_dir", "check_prover"])))]
+// One file per run: a batch writes its proofs with --out-dir instead.
+#[command(mut_arg("out_public", |a| a.conflicts_with_all(["batch", "receipts_dir"])))]
+#[command(mut_arg("out_calldata", |a| a.conflicts_with_all(["batch", "receipts_dir"])))]
+#[command(mut_arg("out_witness", |a| a.conflicts_with_all(["batch", "receipts_dir"])))]
+#[command(mut_arg("out_witness_felts", |a| a.conflicts_with_all(["batch", "receipts_dir"])))]
+struct ProveCmd {
+    #[command(flatten)]
+    input: InputArgs,
+    #[command(flatten)]
+    batch: BatchArgs,
+    #[command(flatten)]
+    sig: SignatureArgs,
+    #[command(flatten)]
+    signing: SigningArgs,
+    #[command(flatten)]
+    checks: CheckArgs,
+    #[command(flatten)]
+    outputs: OutputArgs,
+    #[command(flatten)]
+    prover: ProverArgs,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(mut_arg("receipt", |a| a.required_unless_present("bundle_in")))]
+struct VerifyProofCmd {
+    #[command(flatten)]
+    input: InputArgs,
+    /// Proof JSON to verify against --receipt
+    #[arg(long)]
+    proof: PathBuf,
+    #[command(flatten)]
+    signing: SigningArgs,
+    #[command(flatten)]
+    expect: ExpectArgs,
+    #[command(flatten)]
+    hash: HashArgs,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(mut_arg("receipt", |a| a.required_unless_present("bundle_in")))]
+struct RecoverCmd {
+    #[command(flatten)]
+    input: InputArgs,
+    #[command(flatten)]
+    sig: SignatureArgs,
+    #[command(flatten)]
+    signing: SigningArgs,
+}
+
+/// Reading the receipt (and proof) files.
+#[derive(clap::Args, Debug)]
+struct InputArgs {
     /// Path to receipt JSON
     #[arg(long)]
-    receipt: String,
-    /// Receipt signature hex (65-byte r||s||v)
+    receipt: Option<String>,
+    /// One JSON file {receipt, signature, gateway, policy_hash, consent_hash} instead of the
+    /// separate flags; any of those flags given as well overrides its field
     #[arg(long)]
-    signature: String,
-    /// Gateway address (0x...), or \"auto\" to accept recovered signer
+    bundle_in: Option<PathBuf>,
+    /// `--bundle-in`, once read by `apply_bundle`.
+    #[arg(skip)]
+    bundle: Option<InputBundle>,
+    /// Reject --receipt / --receipts-dir JSON with duplicate object keys instead of keeping the last one
     #[arg(long)]
-    gateway: String,
+    reject_duplicate_keys: bool,
+    /// Encoding of --receipt, --receipts-dir (*.json or *.cbor) and --proof files
+    #[cfg(feature = "cbor")]
+    #[arg(long, value_enum, default_value_t = Wire::Json)]
+    input_format: Wire,
+    /// Compression of --receipt, --receipts-dir, --batch and --proof files: auto|none|gzip
+    /// (auto detects the gzip header; gzip needs the `gzip` feature)
+    #[arg(long, default_value = "auto")]
+    input_compression: Compression,
+}
+
+/// Many receipts instead of one `--receipt`.
+#[derive(clap::Args, Debug)]
+struct BatchArgs {
+    /// Batch file: JSON array or newline-delimited JSON of {receipt, signature}
+    #[arg(long, conflicts_with_all = ["receipt", "bundle_in", "receipts_dir", "threshold", "consent_leaf", "consent_doc", "no_canonicalize"])]
+    #[cfg_attr(feature = "starknet", arg(conflicts_with = "stark"))]
+    batch: Option<PathBuf>,
+    /// Directory of receipt JSON files, each verified with its embedded receipt_sig
+    #[arg(long, conflicts_with_all = ["receipt", "bundle_in", "threshold", "consent_leaf", "consent_doc", "no_canonicalize"])]
+    #[cfg_attr(feature = "starknet", arg(conflicts_with = "stark"))]
+    receipts_dir: Option<PathBuf>,
+}
+
+impl BatchArgs {
+    fn is_batch(&self) -> bool {
+        self.batch.is_some() || self.receipts_dir.is_some()
+    }
+}
+
+/// Where the receipt signature comes from.
+#[derive(clap::Args, Debug)]
+struct SignatureArgs {
+    /// Receipt signature (65-byte r||s||v, or 64-byte EIP-2098 compact), hex or base64
+    /// Defaults to the receipt's embedded receipt_sig; repeat with --threshold for co-signed receipts
+    #[arg(long)]
+    signature: Vec<String>,
+    /// File holding the raw signature bytes
+    #[arg(long, conflicts_with = "signature")]
+    signature_file: Option<PathBuf>,
+    /// How --signature is encoded (auto|hex|base64); auto picks hex for 0x-prefixed or all-hex-digit strings
+    #[arg(long, default_value = "auto")]
+    signature_encoding: SigEncoding,
+}
+
+/// What the signer signed: canonical form, digest and signature scheme.
+#[derive(clap::Args, Debug)]
+struct SigningArgs {
+    /// Canonicalization used for the receipt hash and signing digest (sorted|jcs)
+    #[arg(long, default_value = "sorted")]
+    canon: CanonMode,
+    /// Top-level field left out of the signed form (repeatable); replaces the default receipt_sig + anchor
+    #[arg(long = "exclude-field", value_name = "FIELD")]
+    exclude_fields: Vec<String>,
+    /// Sign over every top-level field (no exclusions)
+    #[arg(long, conflicts_with = "exclude_fields")]
+    exclude_none: bool,
+    /// JSON pointer (e.g. /payload) to the signed sub-object; default is the whole receipt minus excluded fields
+    #[arg(long, conflicts_with_all = ["exclude_fields", "exclude_none"])]
+    sign_path: Option<String>,
+    /// JSON pointer (into the signed tree) to an unordered array, sorted by canonical element before hashing (repeatable); the signer must sort the same arrays
+    #[arg(long = "sort-array", value_name = "POINTER", conflicts_with = "no_canonicalize")]
+    sort_arrays_at: Vec<String>,
+    /// Hash and verify the exact signed bytes instead of a canonical form: --receipt-raw, else the
+    /// receipt's `receipt_raw` string, else the --receipt file itself; they must parse to the receipt
+    #[arg(long, conflicts_with = "eip712_schema")]
+    no_canonicalize: bool,
+    /// File holding the exact bytes the signer signed, for --no-canonicalize
+    #[arg(long, requires = "no_canonicalize")]
+    receipt_raw: Option<PathBuf>,
+    /// Reject receipts that nest objects/arrays deeper than this
+    #[arg(long, default_value_t = receipt_verifier::DEFAULT_MAX_DEPTH)]
+    max_depth: usize,
+    /// Reject receipts whose canonical form exceeds this many bytes
+    #[arg(long, default_value_t = receipt_verifier::DEFAULT_MAX_CANONICAL_BYTES)]
+    max_bytes: usize,
+    /// What the personal_sign signer signed: raw canonical JSON, or its keccak or sha256 (raw|keccak|sha256)
+    #[arg(long, default_value = "keccak", conflicts_with = "eip712_schema")]
+    digest: DigestMode,
+    /// EIP-712 schema JSON ({domain, types, primaryType}); verifies a typed-data signature instead of personal_sign
+    #[arg(long)]
+    eip712_schema: Option<PathBuf>,
+    /// Accept high-S (malleable) signatures from legacy signers
+    #[arg(long)]
+    allow_high_s: bool,
+}
+
+/// Expected policy and consent hashes, and the `--config` profile that may supply them.
+#[derive(clap::Args, Debug)]
+struct ExpectArgs {
     /// Expected policy hash
     #[arg(long)]
-    policy_hash: String,
+    policy_hash: Option<String>,
+    /// Policy document JSON; the expected policy hash is derived from it (sha256 of its --canon canonical form)
+    #[arg(long, conflicts_with = "policy_hash")]
+    policy_doc: Option<PathBuf>,
     /// Expected consent hash
     #[arg(long)]
-    consent_hash: String,
+    consent_hash: Option<String>,
+    /// TOML file of named verification profiles; explicit flags override profile values
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// Profile to use from --config
+    #[arg(long, requires = "config", default_value = "default")]
+    profile: String,
+}
+
+/// Receipt hash carried in the public inputs.
+#[derive(clap::Args, Debug)]
+struct HashArgs {
+    /// Receipt hash carried in the public inputs (sha256|keccak|poseidon); stamped into them for verify-proof
+    #[arg(long, visible_alias = "receipt-hash-alg", default_value = "sha256")]
+    hash: HashScheme,
+    /// Field modulus for --hash poseidon (default: M31, 2147483647)
+    #[arg(long)]
+    poseidon_prime: Option<u64>,
+}
+
+/// Every check a receipt must pass.
+#[derive(clap::Args, Debug)]
+struct CheckArgs {
+    /// Gateway address (0x...) or did:pkh / did:ethr DID, a comma-separated list of them (any may sign), or \"auto\" to accept recovered signer
+    #[arg(long)]
+    gateway: Option<String>,
+    /// File of accepted gateways, one per line: the recovered signer must be listed (UNAUTHORIZED_GATEWAY otherwise), also with --gateway auto
+    #[arg(long)]
+    gateway_allowlist: Option<PathBuf>,
+    #[command(flatten)]
+    expect: ExpectArgs,
+    /// Co-signed receipt: require this many distinct --gateway entries among the --signature signers
+    #[arg(long, requires = "gateway", conflicts_with = "signature_file")]
+    threshold: Option<usize>,
+    /// Verify --signature even if the receipt embeds a different receipt_sig
+    #[arg(long)]
+    ignore_embedded_sig: bool,
+    /// Require the signer to be the gateway the receipt declares in its own `gateway` field, if any
+    #[arg(long)]
+    check_self_gateway: bool,
+    /// Fail with RECEIPT_HASH_MISMATCH, before any signature work, unless the recomputed receipt hash is this
+    #[arg(long)]
+    expect_receipt_hash: Option<HashHex>,
+    /// Consent grant leaf hash that must be in the snapshot, proven by the receipt's consent_proof
+    #[arg(long)]
+    consent_leaf: Option<String>,
+    /// Consent document {scopes: [{scope, expires_at?}]} that must grant every scope in the receipt's consent_scopes
+    #[arg(long)]
+    consent_doc: Option<PathBuf>,
+    /// JSON pointer to the receipt's policy hash (e.g. /meta/policy_hash); default is top-level policy_hash
+    #[arg(long)]
+    policy_hash_path: Option<String>,
+    /// JSON pointer to the receipt's consent hash (e.g. /meta/consent_snapshot_hash); default is top-level consent_snapshot_hash
+    #[arg(long)]
+    consent_hash_path: Option<String>,
+    /// Require an EIP-155 encoded signature v for this chain id
+    #[arg(long)]
+    chain_id: Option<u64>,
+    #[command(flatten)]
+    hash: HashArgs,
+    /// How receipt_hash is printed: hex, a sha2-256 multihash, or a CIDv1 of the canonical receipt (hex|multihash|cid; the latter two need --hash sha256)
+    #[arg(long, default_value = "hex")]
+    hash_format: HashFormat,
+    /// Verify a Stark-curve signature (message hash poseidon|pedersen): --gateway is the signer's public key felt and --signature is `r,s`
+    #[cfg(feature = "starknet")]
+    #[arg(long, value_name = "HASH", conflicts_with = "eip712_schema")]
+    stark: Option<receipt_verifier::StarkHash>,
+    /// Signature algorithm (auto|secp256k1|ed25519); auto picks ed25519 for a did:key:z6Mk.. --gateway, which may also be a 32-byte hex key
+    #[cfg(feature = "ed25519")]
+    #[arg(long, default_value = "auto")]
+    sig_alg: receipt_verifier::SigAlg,
+    /// Reject receipts issued more than this many seconds ago
+    #[arg(long)]
+    max_age: Option<u64>,
+    /// Clock-skew tolerance in seconds for issued_at/expires_at/max-age checks
+    #[arg(long, default_value_t = 0)]
+    clock_skew: u64,
+    /// Replay store file; receipts already recorded there are rejected, accepted ones are appended
+    #[arg(long)]
+    seen_store: Option<PathBuf>,
+    /// Reject receipts whose nonce is below this value (requires --seen-store)
+    #[arg(long, requires = "seen_store")]
+    min_nonce: Option<u64>,
+    /// Require strictly increasing nonces per signer (requires --seen-store)
+    #[arg(long, requires = "seen_store")]
+    monotonic_nonce: bool,
+    /// JSON Schema file the raw receipt must satisfy before verification, or "receipt_v1" for the bundled one
+    #[cfg(feature = "schema")]
+    #[arg(long)]
+    schema: Option<String>,
+    /// JSON-RPC endpoint; with a fixed --gateway, falls back to EIP-1271 when the gateway is a contract
+    #[cfg(feature = "rpc")]
+    #[arg(long)]
+    rpc_url: Option<String>,
+    /// Per-request timeout for --rpc-url calls, in seconds
+    #[cfg(feature = "rpc")]
+    #[arg(long, requires = "rpc_url", default_value_t = 10)]
+    rpc_timeout: u64,
+    /// Retry --rpc-url calls this many times after a 5xx response, timeout or connection error
+    #[cfg(feature = "rpc")]
+    #[arg(long, requires = "rpc_url", default_value_t = 2)]
+    rpc_retries: u32,
+    /// Check the anchor tx via --rpc-url and require at least this many confirmations
+    #[cfg(feature = "rpc")]
+    #[arg(long, requires = "rpc_url")]
+    min_confirmations: Option<u64>,
+    /// Gateway registry contract queried via --rpc-url: the signer must be authorized at the receipt's anchor block
+    #[cfg(feature = "rpc")]
+    #[arg(long, requires = "rpc_url")]
+    gateway_registry: Option<String>,
+    /// Registry view function taking the signer address and returning a bool
+    #[cfg(feature = "rpc")]
+    #[arg(long, requires = "gateway_registry", default_value = receipt_verifier::DEFAULT_REGISTRY_FUNCTION)]
+    registry_function: String,
+}
+
+/// `verify` diagnostics.
+#[derive(clap::Args, Debug)]
+struct DiagArgs {
+    /// Path to write the canonical signed form the receipt hash is taken over (exact bytes), before verification
+    #[arg(long, conflicts_with_all = ["batch", "receipts_dir"])]
+    dump_canonical: Option<PathBuf>,
+    /// Path to write a JSON report of every verification stage and check, written even when verification fails
+    #[arg(long, conflicts_with_all = ["batch", "receipts_dir", "threshold"])]
+    #[cfg_attr(feature = "starknet", arg(conflicts_with = "stark"))]
+    report: Option<PathBuf>,
+    /// On failure, report every failed check instead of only the first (same checks as --report)
+    #[arg(long, conflicts_with_all = ["batch", "receipts_dir", "threshold"])]
+    #[cfg_attr(feature = "starknet", arg(conflicts_with = "stark"))]
+    all_errors: bool,
+    /// Write the --report file without whitespace (stdout stays pretty-printed)
+    #[arg(long)]
+    compact: bool,
+}
+
+/// Public input, witness and calldata files.
+#[derive(clap::Args, Debug)]
+struct OutputArgs {
     /// Path to write public inputs JSON
     #[arg(long)]
     out_public: Option<PathBuf>,
+    /// Path to write the public inputs as the Stwo verifier contract's felt calldata (u256 hashes as low, high limbs)
+    #[arg(long)]
+    out_calldata: Option<PathBuf>,
     /// Path to write witness JSON
     #[arg(long)]
     out_witness: Option<PathBuf>,
-    /// Path to write proof JSON (currently the receipt_sig hook; swap to Stwo later)
+    /// Path to write the witness as a JSON array of hex field elements (Stwo circuit layout)
     #[arg(long)]
+    out_witness_felts: Option<PathBuf>,
+    /// Field modulus for --out-witness-felts (default: M31, 2147483647)
+    #[arg(long, requires = "out_witness_felts")]
+    felt_prime: Option<u64>,
+    /// JSON pointer of a witness sub-tree to replace with a salted commitment before it is written or proved (repeatable)
+    #[arg(long = "redact", value_name = "POINTER", requires = "out_redactions")]
+    redact: Vec<String>,
+    /// Path to write the redaction salts and commitments for --redact (keep private)
+    #[arg(long, requires = "redact")]
+    out_redactions: Option<PathBuf>,
+    /// Leave the signature out of the witness (written or proved), committing to it with a salted
+    /// sha256 instead; the signature and salt go to this file for the prover's secure channel (keep private)
+    #[arg(long, value_name = "FILE")]
+    withhold_signature: Option<PathBuf>,
+    /// Write JSON output files without whitespace (stdout stays pretty-printed)
+    #[arg(long)]
+    compact: bool,
+    /// Encoding of --out-public, --out-witness and --out-proof files
+    #[cfg(feature = "cbor")]
+    #[arg(long, value_enum, default_value_t = Wire::Json)]
+    output_format: Wire,
+}
+
+/// The prover and the proof files.
+#[derive(clap::Args, Debug)]
+struct ProverArgs {
+    /// Path to write proof JSON (currently the receipt_sig hook; swap to Stwo later)
+    #[arg(long, conflicts_with_all = ["batch", "receipts_dir"])]
     out_proof: Option<PathBuf>,
-    /// Generate proof via the configured hook (receipt_sig or external)
+    /// Path to write a self-contained proof bundle (proof, receipt, expectations) for offline audit
+    #[arg(long, conflicts_with_all = ["batch", "receipts_dir"])]
+    #[cfg_attr(feature = "starknet", arg(conflicts_with = "stark"))]
+    out_bundle: Option<PathBuf>,
+    /// Directory to write one proof file per receipt into (batch or single), named by --name-template
+    #[arg(long)]
+    out_dir: Option<PathBuf>,
+    /// File name of each proof in --out-dir; expands {receipt_id} (the receipt hash if it has none),
+    /// {receipt_hash} and {index} (position in the batch, from 0)
+    #[arg(long, requires = "out_dir", default_value = "{receipt_id}.proof.json")]
+    name_template: String,
+    /// In batch mode, run at most this many provers at once
+    #[arg(long, default_value_t = 4)]
+    prove_concurrency: usize,
+    /// Prover to run: receipt_sig, subprocess, auto (subprocess; with --allow-prover-fallback, falling back to receipt_sig), or http (with --prover-url)
+    #[arg(long, default_value = "auto")]
+    prover: String,
+    /// Let --prover auto fall back to the receipt_sig stub when the external prover fails or is not set,
+    /// stamping the proof's prover as receipt_sig:fallback; without it that failure is an error
+    #[arg(long)]
+    allow_prover_fallback: bool,
+    /// Only run the selected prover on a tiny built-in receipt and report whether it works; no receipts are read
+    #[arg(long, conflicts_with_all = ["receipt", "bundle_in", "batch", "receipts_dir"])]
+    check_prover: bool,
+    /// Prover service endpoint for --prover http
+    #[cfg(feature = "http")]
+    #[arg(long)]
+    prover_url: Option<String>,
+    /// API key for --prover-url (default: LUMINAIR_PROVER_API_KEY)
+    #[cfg(feature = "http")]
     #[arg(long)]
-    prove: bool,
+    prover_api_key: Option<String>,
+    /// Header carrying the API key; Authorization sends it as a Bearer token
+    #[cfg(feature = "http")]
+    #[arg(long, default_value = "Authorization")]
+    prover_auth_header: String,
     /// Force stub prover even if a real prover is later wired
     #[arg(long, default_value_t = false)]
     stub: bool,
+    /// External prover command (overrides LUMINAIR_PROVER_CMD)
+    #[arg(long)]
+    prover_cmd: Option<String>,
+    /// Kill the external prover after this many seconds
+    #[arg(long, default_value_t = 600)]
+    prover_timeout: u64,
+    /// Retry the external prover this many times after a timeout or failure
+    #[arg(long, default_value_t = 0)]
+    prover_retries: u32,
 }
 
-fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
-    let data = fs::read_to_string(&args.receipt)?;
-    let val: serde_json::Value = serde_json::from_str(&data)?;
-    let gateway_opt = if args.gateway.eq_ignore_ascii_case("auto") {
-        None
+/// One `verify`, `build-inputs` or `prove` run: the checks, plus whatever the subcommand
+/// writes afterwards.
+struct Pipeline<'a> {
+    format: Format,
+    input: &'a InputArgs,
+    batch: Option<&'a BatchArgs>,
+    sig: &'a SignatureArgs,
+    signing: &'a SigningArgs,
+    checks: &'a CheckArgs,
+    diag: Option<&'a DiagArgs>,
+    outputs: Option<&'a OutputArgs>,
+    prover: Option<&'a ProverArgs>,
+}
+
+fn main() {
+    let cli = Cli::try_parse().unwrap_or_else(|e| {
+        let _ = e.print();
+        // clap's own status for usage errors (2) would read as a signature failure.
+        std::process::exit(if e.use_stderr() { EXIT_USAGE } else { 0 });
+    });
+    if let Err(e) = init_tracing(cli.log_level.as_deref()) {
+        eprintln!("error: invalid --log-level: {e}");
+        std::process::exit(EXIT_USAGE);
+    }
+    let format = cli.format;
+    if let Err(e) = run(cli) {
+        let code = exit_code(&e);
+        match format {
+            Format::Json => fail_json(&format!("{e:#}"), e.downcast_ref::<VerifyError>(), code),
+            Format::Text => {
+                eprintln!("Error: {e:?}");
+                std::process::exit(code);
+            }
+        }
+    }
+}
+
+/// Log spans and events to stderr, filtered by `level`, else `RUST_LOG`, else `warn`. Span
+/// close events carry the span's duration (`time.busy`).
+fn init_tracing(level: Option<&str>) -> Result<(), tracing_subscriber::filter::ParseError> {
+    let filter = match level {
+        Some(level) => EnvFilter::try_new(level)?,
+        None => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+    };
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::stderr().is_terminal())
+        .init();
+    Ok(())
+}
+
+/// Exit status for invalid command-line usage (sysexits `EX_USAGE`).
+const EXIT_USAGE: i32 = 64;
+/// Exit status for failures reading or writing files.
+const EXIT_IO: i32 = 5;
+/// Exit status for malformed input, as `VerifyError::exit_code` gives for `Serde` and `Hex`.
+const EXIT_INPUT: i32 = 6;
+
+/// `VerifyError::exit_code` for verification errors, `EXIT_IO` for I/O, `EXIT_INPUT` for
+/// unparseable JSON and 1 for anything else.
+fn exit_code(err: &anyhow::Error) -> i32 {
+    if let Some(e) = err.downcast_ref::<VerifyError>() {
+        e.exit_code()
+    } else if err.downcast_ref::<std::io::Error>().is_some() {
+        EXIT_IO
+    } else if err.downcast_ref::<serde_json::Error>().is_some() {
+        EXIT_INPUT
     } else {
-        Some(args.gateway.clone())
+        1
+    }
+}
+
+/// Print `{ ok: false, error }` on stdout and exit with `code`.
+fn fail_json(message: &str, err: Option<&VerifyError>, code: i32) -> ! {
+    let error = match err {
+        Some(e) => json!({ "code": e.code(), "numeric_code": e.numeric_code(), "message": message }),
+        None => json!({ "code": "ERROR", "message": message }),
+    };
+    println!("{}", json!({ "ok": false, "error": error }));
+    std::process::exit(code);
+}
+
+/// Report a verification failure in the selected format and exit with its `exit_code`.
+fn fail(format: Format, context: &str, err: &VerifyError) -> ! {
+    match format {
+        Format::Json => fail_json(&err.to_string(), Some(err), err.exit_code()),
+        Format::Text => {
+            eprintln!("{context}: {err}");
+            std::process::exit(err.exit_code());
+        }
+    }
+}
+
+/// `--all-errors`: report every failure and exit with the first one's `exit_code`.
+fn fail_all(format: Format, errors: &[VerifyError]) -> ! {
+    let code = errors.first().map_or(1, VerifyError::exit_code);
+    match format {
+        Format::Json => {
+            let json_errors: Vec<_> =
+                errors.iter().map(|e| json!({ "code": e.code(), "numeric_code": e.numeric_code(), "message": e.to_string() })).collect();
+            println!("{}", json!({ "ok": false, "error": json_errors.first(), "errors": json_errors }));
+        }
+        Format::Text => {
+            eprintln!("verification failed with {} error(s):", errors.len());
+            for e in errors {
+                eprintln!("  {}: {}", e.code(), e);
+            }
+        }
+    }
+    std::process::exit(code);
+}
+
+/// `--signature`, `--signature-file` or the embedded `receipt_sig` as hex; Stark `r,s`
+/// signatures (`raw`) are passed through.
+fn signature_arg(sig: &SignatureArgs, raw: bool, val: &serde_json::Value) -> anyhow::Result<String> {
+    if let Some(path) = sig.signature_file.as_ref() {
+        return Ok(format!("0x{}", hex::encode(fs::read(path)?)));
+    }
+    if sig.signature.len() > 1 {
+        anyhow::bail!("more than one --signature needs --threshold");
+    }
+    let signature = match (sig.signature.first(), val.get("receipt_sig").and_then(|v| v.as_str())) {
+        (Some(signature), _) => signature.clone(),
+        (None, Some(embedded)) => embedded.to_string(),
+        (None, None) => anyhow::bail!("no --signature or --signature-file given and the receipt has no receipt_sig"),
     };
+    if raw {
+        return Ok(signature);
+    }
+    Ok(receipt_verifier::signature_hex(&signature, sig.signature_encoding)?)
+}
+
+/// `--stark`: the gateway is a Stark public key felt, not an address.
+fn stark_mode(checks: &CheckArgs) -> bool {
+    #[cfg(feature = "starknet")]
+    let stark = checks.stark.is_some();
+    #[cfg(not(feature = "starknet"))]
+    let stark = { let _ = checks; false };
+    stark
+}
+
+/// `--sig-alg ed25519` (or a did:key Ed25519 gateway): the gateway is an Ed25519 public key.
+fn ed25519_mode(checks: &CheckArgs) -> bool {
+    #[cfg(feature = "ed25519")]
+    let ed25519 = checks.sig_alg.resolve(checks.gateway.as_deref().unwrap_or("auto")) == receipt_verifier::SigAlg::Ed25519;
+    #[cfg(not(feature = "ed25519"))]
+    let ed25519 = { let _ = checks; false };
+    ed25519
+}
+
+/// Read an input file and undo its `--input-compression`. I/O errors keep their type so a
+/// missing or unreadable file exits with 5 rather than as a parse error.
+fn read_input(input: &InputArgs, path: &Path) -> anyhow::Result<Vec<u8>> {
+    let bytes = fs::read(path).with_context(|| path.display().to_string())?;
+    Ok(receipt_verifier::decompress(bytes, input.input_compression)?)
+}
+
+/// Read a receipt in the selected `--input-format`, keeping the (decompressed) bytes.
+fn load_receipt(input: &InputArgs, path: impl AsRef<Path>) -> anyhow::Result<RawReceipt> {
+    let bytes = read_input(input, path.as_ref())?;
+    #[cfg(feature = "cbor")]
+    if input.input_format == Wire::Cbor {
+        let value = receipt_verifier::read_receipt_cbor(&bytes[..])?;
+        return Ok(RawReceipt { bytes, value });
+    }
+    Ok(RawReceipt::from_json(bytes, input.reject_duplicate_keys)?)
+}
+
+/// Read a proof in the selected `--input-format`, migrated from older proof versions.
+fn load_proof(input: &InputArgs, path: &Path) -> anyhow::Result<Proof> {
+    let bytes = read_input(input, path)?;
+    #[cfg(feature = "cbor")]
+    if input.input_format == Wire::Cbor {
+        return Ok(Proof::from_value(receipt_verifier::from_cbor(&bytes[..])?)?);
+    }
+    Ok(Proof::from_slice(&bytes)?)
+}
 
-    let expected_gateway_bytes = if let Some(gw) = gateway_opt.as_ref() {
-        let mut gateway_bytes = [0u8; 20];
-        let clean = receipt_verifier::normalize_hex_even(gw);
-        let gb = Vec::from_hex(clean)?;
-        let slice: &[u8] = if gb.len() == 32 { &gb[12..] } else { &gb };
-        if slice.len() != 20 { anyhow::bail!("gateway must be 20 bytes (or 32 felt)") }
-        gateway_bytes.copy_from_slice(slice);
-        Some(gateway_bytes)
+/// Write an output file in the selected `--output-format`.
+fn write_output<T: Serialize>(outputs: &OutputArgs, path: &Path, value: &T) -> anyhow::Result<()> {
+    #[cfg(feature = "cbor")]
+    if outputs.output_format == Wire::Cbor {
+        return Ok(fs::write(path, receipt_verifier::to_cbor(value)?)?);
+    }
+    Ok(fs::write(path, json_bytes(outputs.compact, value)?)?)
+}
+
+/// `value` as JSON for an output file: compact with `--compact`, pretty-printed otherwise.
+fn json_bytes<T: Serialize>(compact: bool, value: &T) -> serde_json::Result<Vec<u8>> {
+    if compact {
+        serde_json::to_vec(value)
+    } else {
+        serde_json::to_vec_pretty(value)
+    }
+}
+
+/// `--exclude-field` / `--exclude-none`; `None` keeps the library default.
+fn excluded_fields(signing: &SigningArgs) -> Option<Vec<String>> {
+    if signing.exclude_none {
+        Some(Vec::new())
+    } else if !signing.exclude_fields.is_empty() {
+        Some(signing.exclude_fields.clone())
     } else {
         None
+    }
+}
+
+/// `--receipt`, else the `--bundle-in` receipt (re-serialized, so not its original bytes).
+fn load_source(input: &InputArgs) -> anyhow::Result<RawReceipt> {
+    if let (None, Some(bundle)) = (input.receipt.as_ref(), input.bundle.as_ref()) {
+        return Ok(RawReceipt { bytes: serde_json::to_vec(&bundle.receipt)?, value: bundle.receipt.clone() });
+    }
+    load_receipt(input, input.receipt.as_deref().unwrap_or_default())
+}
+
+/// The receipt value and, with `--no-canonicalize`, the exact bytes its signer signed:
+/// `--receipt-raw`, else its embedded `receipt_raw`, else the bytes it was loaded from.
+fn load_signed(signing: &SigningArgs, input: &InputArgs) -> anyhow::Result<(serde_json::Value, Option<Arc<str>>)> {
+    let receipt = load_source(input)?;
+    if !signing.no_canonicalize {
+        return Ok((receipt.value, None));
+    }
+    if let Some(raw_path) = signing.receipt_raw.as_ref() {
+        let raw = String::from_utf8(read_input(input, raw_path)?)?;
+        return Ok((receipt.value, Some(raw.into())));
+    }
+    if input.receipt.is_none() && receipt.value.get("receipt_raw").is_none() {
+        anyhow::bail!("--no-canonicalize with --bundle-in needs --receipt-raw or a receipt_raw in the bundled receipt");
+    }
+    let receipt = receipt.unwrap_embedded()?;
+    let raw = receipt.signed_raw()?;
+    Ok((receipt.value, Some(raw)))
+}
+
+/// `--hash`, with `--poseidon-prime` applied.
+fn hash_scheme(hash: &HashArgs) -> HashScheme {
+    match (&hash.hash, hash.poseidon_prime) {
+        (HashScheme::Poseidon(params), Some(prime)) => HashScheme::Poseidon(PoseidonParams { prime, ..params.clone() }),
+        (scheme, _) => scheme.clone(),
+    }
+}
+
+/// Options for reproducing what the signer signed; every subcommand needs these.
+fn signing_options(signing: &SigningArgs) -> anyhow::Result<VerifyOptions> {
+    let mut opts = VerifyOptions {
+        digest: signing.digest,
+        canon: signing.canon,
+        allow_high_s: signing.allow_high_s,
+        excluded_fields: excluded_fields(signing),
+        sign_path: signing.sign_path.clone(),
+        sort_arrays_at: signing.sort_arrays_at.clone(),
+        max_depth: Some(signing.max_depth),
+        max_canonical_bytes: Some(signing.max_bytes),
+        ..Default::default()
     };
+    if let Some(path) = signing.eip712_schema.as_ref() {
+        let schema: Eip712Schema = serde_json::from_str(&fs::read_to_string(path)?)?;
+        let (domain, receipt_type) = schema.into_parts();
+        opts.scheme = SigScheme::Eip712 { domain, receipt_type };
+    }
+    Ok(opts)
+}
+
+/// `signing_options` plus every check in `checks`.
+fn verify_options(signing: &SigningArgs, checks: &CheckArgs) -> anyhow::Result<VerifyOptions> {
+    let mut opts = VerifyOptions {
+        hash: hash_scheme(&checks.hash),
+        ignore_embedded_sig: checks.ignore_embedded_sig,
+        check_self_gateway: checks.check_self_gateway,
+        expected_receipt_hash: checks.expect_receipt_hash.clone(),
+        max_age: checks.max_age.map(Duration::from_secs),
+        clock_skew: Duration::from_secs(checks.clock_skew),
+        policy_hash_path: checks.policy_hash_path.clone(),
+        consent_hash_path: checks.consent_hash_path.clone(),
+        expected_chain_id: checks.chain_id,
+        gateway_allowlist: checks.gateway_allowlist.as_ref().map(receipt_verifier::load_allowlist).transpose()?,
+        ..signing_options(signing)?
+    };
+    if checks.hash_format != HashFormat::Hex && opts.hash != HashScheme::Sha256 {
+        anyhow::bail!("--hash-format multihash|cid needs --hash sha256");
+    }
+
+    #[cfg(feature = "schema")]
+    if let Some(schema) = checks.schema.as_deref() {
+        let schema = if schema == "receipt_v1" {
+            receipt_verifier::ReceiptSchema::receipt_v1()
+        } else {
+            receipt_verifier::ReceiptSchema::new(&serde_json::from_str(&fs::read_to_string(schema)?)?)?
+        };
+        opts.schema = Some(Arc::new(schema));
+    }
 
-    match verify_receipt(&val, &args.signature, expected_gateway_bytes, &args.policy_hash, &args.consent_hash) {
-        Ok((rcpt_hash, addr)) => {
-            println!("signature ok, policy/consent ok");
-            println!("receipt_hash (sha256 canonical): 0x{}", rcpt_hash);
-            println!("recovered address: 0x{}", hex::encode(addr));
-            let (pub_inputs, witness) = build_public_and_witness(&val, &args.signature, gateway_opt.as_deref())?;
-            println!("public inputs JSON:");
-            println!("{}", serde_json::to_string_pretty(&pub_inputs)?);
-            if let Some(out) = args.out_public {
-                fs::write(&out, serde_json::to_vec_pretty(&pub_inputs)?)?;
-                println!("saved public inputs to {}", out.display());
+    if let Some(path) = checks.seen_store.as_ref() {
+        let mut guard = ReplayGuard::new(Arc::new(FileSeenStore::open(path)?));
+        guard.min_nonce = checks.min_nonce;
+        guard.monotonic = checks.monotonic_nonce;
+        opts.replay = Some(guard);
+    }
+    Ok(opts)
+}
+
+/// One pooled client for every `--rpc-url` call of the run.
+#[cfg(feature = "rpc")]
+fn rpc_client(checks: &CheckArgs) -> Option<RpcClient> {
+    let mut client = RpcClient::new(checks.rpc_url.clone()?);
+    client.timeout = Duration::from_secs(checks.rpc_timeout);
+    client.retries = checks.rpc_retries;
+    Some(client)
+}
+
+/// Derive `--policy-hash` from `--policy-doc` and fill the hashes not given on the command
+/// line from the selected `--config` profile, which is returned for the caller's own defaults.
+fn resolve_expectations(expect: &mut ExpectArgs, canon: CanonMode) -> anyhow::Result<Option<Profile>> {
+    if let Some(path) = expect.policy_doc.as_ref() {
+        let doc = receipt_verifier::read_receipt_file(path)?;
+        let hash = receipt_verifier::policy_hash_from_doc_with(&doc, canon, &HashScheme::Sha256)?;
+        eprintln!("policy hash from {}: {}", path.display(), hash);
+        expect.policy_hash = Some(hash);
+    }
+    let Some(path) = expect.config.as_ref() else { return Ok(None) };
+    let config = VerifyConfig::load(path)?;
+    let profile = config.profile(&expect.profile)?.clone();
+    expect.policy_hash = expect.policy_hash.take().or(profile.policy_hash.clone());
+    expect.consent_hash = expect.consent_hash.take().or(profile.consent_hash.clone());
+    Ok(Some(profile))
+}
+
+/// The expected policy and consent hashes, both required.
+fn expected_hashes(expect: &ExpectArgs) -> anyhow::Result<(String, String)> {
+    let (Some(policy_hash), Some(consent_hash)) = (expect.policy_hash.clone(), expect.consent_hash.clone()) else {
+        anyhow::bail!("--policy-hash and --consent-hash are required (or set them in the --config profile)");
+    };
+    Ok((policy_hash, consent_hash))
+}
+
+/// Read `--bundle-in` and fill the signature, gateway and expected hashes not given on the
+/// command line from it; its receipt is used unless `--receipt` is given (`load_source`).
+fn apply_bundle(
+    input: &mut InputArgs,
+    sig: Option<&mut SignatureArgs>,
+    gateway: Option<&mut Option<String>>,
+    expect: Option<&mut ExpectArgs>,
+) -> anyhow::Result<()> {
+    let Some(path) = input.bundle_in.as_ref() else { return Ok(()) };
+    let bundle = InputBundle::from_json(&String::from_utf8(read_input(input, path)?)?)?;
+    if let Some(sig) = sig.filter(|s| s.signature.is_empty() && s.signature_file.is_none()) {
+        sig.signature.extend(bundle.signature.clone());
+    }
+    if let Some(gateway) = gateway.filter(|g| g.is_none()) {
+        *gateway = bundle.gateway.clone();
+    }
+    if let Some(expect) = expect {
+        if expect.policy_hash.is_none() && expect.policy_doc.is_none() {
+            expect.policy_hash = bundle.policy_hash.clone();
+        }
+        expect.consent_hash = expect.consent_hash.take().or(bundle.consent_hash.clone());
+    }
+    input.bundle = Some(bundle);
+    Ok(())
+}
+
+/// Fill checks (and prover options) not given on the command line from the selected
+/// `--config` profile.
+fn apply_profile(checks: &mut CheckArgs, canon: CanonMode, prover: Option<&mut ProverArgs>) -> anyhow::Result<()> {
+    let Some(profile) = resolve_expectations(&mut checks.expect, canon)? else { return Ok(()) };
+    checks.gateway = checks.gateway.take().or(profile.gateway);
+    checks.gateway_allowlist = checks.gateway_allowlist.take().or(profile.gateway_allowlist);
+    checks.max_age = checks.max_age.or(profile.max_age);
+    let Some(prover) = prover else { return Ok(()) };
+    match profile.prover.as_deref() {
+        Some("receipt_sig") => prover.stub = true,
+        Some(cmd) if prover.prover_cmd.is_none() => prover.prover_cmd = Some(cmd.to_string()),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn run(cli: Cli) -> anyhow::Result<()> {
+    let format = cli.format;
+    match cli.command {
+        Command::Verify(mut cmd) => {
+            apply_bundle(&mut cmd.input, Some(&mut cmd.sig), Some(&mut cmd.checks.gateway), Some(&mut cmd.checks.expect))?;
+            apply_profile(&mut cmd.checks, cmd.signing.canon, None)?;
+            run_pipeline(&Pipeline {
+                format,
+                input: &cmd.input,
+                batch: Some(&cmd.batch),
+                sig: &cmd.sig,
+                signing: &cmd.signing,
+                checks: &cmd.checks,
+                diag: Some(&cmd.diag),
+                outputs: None,
+                prover: None,
+            })
+        }
+        Command::BuildInputs(mut cmd) => {
+            apply_bundle(&mut cmd.input, Some(&mut cmd.sig), Some(&mut cmd.checks.gateway), Some(&mut cmd.checks.expect))?;
+            apply_profile(&mut cmd.checks, cmd.signing.canon, None)?;
+            run_pipeline(&Pipeline {
+                format,
+                input: &cmd.input,
+                batch: None,
+                sig: &cmd.sig,
+                signing: &cmd.signing,
+                checks: &cmd.checks,
+                diag: None,
+                outputs: Some(&cmd.outputs),
+                prover: None,
+            })
+        }
+        Command::Prove(mut cmd) => {
+            apply_bundle(&mut cmd.input, Some(&mut cmd.sig), Some(&mut cmd.checks.gateway), Some(&mut cmd.checks.expect))?;
+            apply_profile(&mut cmd.checks, cmd.signing.canon, Some(&mut cmd.prover))?;
+            if cmd.prover.check_prover {
+                return check_prover(format, &cmd.prover);
             }
-            if let Some(out) = args.out_witness {
-                fs::write(&out, serde_json::to_vec_pretty(&witness)?)?;
-                println!("saved witness to {}", out.display());
+            if cmd.prover.out_dir.is_some() {
+                // Reject a bad --name-template before anything is proved.
+                expand_name(&cmd.prover.name_template, 0, None, "")?;
             }
-            if args.prove {
-                let proof = if !args.stub {
-                    receipt_verifier::external_prove(&pub_inputs, &witness).unwrap_or_else(|e| {
-                        eprintln!("external prover failed or not set: {e}; falling back to receipt_sig");
-                        receipt_verifier::mock_prove(&pub_inputs, &witness)
-                    })
-                } else {
-                    receipt_verifier::mock_prove(&pub_inputs, &witness)
-                };
-                println!("proof:");
-                println!("{}", serde_json::to_string_pretty(&proof)?);
-                if let Some(out) = args.out_proof {
-                    fs::write(&out, serde_json::to_vec_pretty(&proof)?)?;
-                    println!("saved proof to {}", out.display());
-                }
+            run_pipeline(&Pipeline {
+                format,
+                input: &cmd.input,
+                batch: Some(&cmd.batch),
+                sig: &cmd.sig,
+                signing: &cmd.signing,
+                checks: &cmd.checks,
+                diag: None,
+                outputs: Some(&cmd.outputs),
+                prover: Some(&cmd.prover),
+            })
+        }
+        Command::VerifyProof(mut cmd) => {
+            apply_bundle(&mut cmd.input, None, None, Some(&mut cmd.expect))?;
+            run_verify_proof(format, &mut cmd)
+        }
+        Command::Recover(mut cmd) => {
+            apply_bundle(&mut cmd.input, Some(&mut cmd.sig), None, None)?;
+            run_recover(format, &cmd)
+        }
+    }
+}
+
+/// `verify`, `build-inputs` and `prove`: check one receipt (or a batch), then emit what the
+/// subcommand asks for.
+fn run_pipeline(p: &Pipeline) -> anyhow::Result<()> {
+    let checks = p.checks;
+    if checks.gateway.is_none() {
+        anyhow::bail!("--gateway is required (or set gateway in the --config profile)");
+    }
+    let (policy_hash, consent_hash) = expected_hashes(&checks.expect)?;
+    let json_mode = p.format == Format::Json;
+    // Human-readable progress: stdout in text mode, stderr in JSON mode so stdout stays one object.
+    macro_rules! say {
+        ($($t:tt)*) => {
+            if json_mode { eprintln!($($t)*) } else { println!($($t)*) }
+        };
+    }
+    let gateway_arg = checks.gateway.as_deref().unwrap_or("auto");
+    let (report_path, all_errors) = p.diag.map_or((None, false), |d| (d.report.as_ref(), d.all_errors));
+    if ed25519_mode(checks) && (checks.threshold.is_some() || report_path.is_some() || all_errors || p.signing.eip712_schema.is_some()) {
+        anyhow::bail!("--threshold, --report, --all-errors and --eip712-schema need secp256k1 signatures, not ed25519");
+    }
+    let gateways: Vec<[u8; 20]> = if gateway_arg.eq_ignore_ascii_case("auto") || stark_mode(checks) || ed25519_mode(checks) {
+        Vec::new()
+    } else {
+        gateway_arg.split(',').map(|g| receipt_verifier::parse_gateway(g.trim())).collect::<Result<_, _>>()?
+    };
+    let mut opts = verify_options(p.signing, checks)?;
+    #[cfg(feature = "rpc")]
+    let rpc = rpc_client(checks);
+    #[cfg(feature = "rpc")]
+    if let (Some(registry), Some(rpc)) = (checks.gateway_registry.as_deref(), rpc.as_ref()) {
+        let contract = receipt_verifier::parse_gateway(registry)?;
+        let registry = receipt_verifier::RpcGatewayRegistry::with_rpc(Arc::new(rpc.clone()), contract, &checks.registry_function);
+        opts.registry = Some(Arc::new(registry));
+    }
+
+    if let Some(batch) = p.batch.filter(|b| b.is_batch()) {
+        #[cfg(feature = "rpc")]
+        return run_batch(p, batch, &gateways, &policy_hash, &consent_hash, &opts, rpc.as_ref());
+        #[cfg(not(feature = "rpc"))]
+        return run_batch(p, batch, &gateways, &policy_hash, &consent_hash, &opts);
+    }
+
+    let (val, signed_raw) = load_signed(p.signing, p.input)?;
+    opts.signed_raw = signed_raw;
+    // Written before anything is checked, so it is there to diff when verification fails.
+    if let Some(out) = p.diag.and_then(|d| d.dump_canonical.as_ref()) {
+        fs::write(out, receipt_verifier::signed_canonical_string(&val, &opts)?)?;
+    }
+
+    if let Some(threshold) = checks.threshold {
+        return run_multisig(p, &val, &gateways, threshold, &policy_hash, &consent_hash, &opts);
+    }
+
+    let signature = signature_arg(p.sig, stark_mode(checks), &val)?;
+    // Before verification, so a receipt failing it is never recorded in the replay store.
+    if let Some(leaf) = checks.consent_leaf.as_deref() {
+        let root_path = checks.consent_hash_path.as_deref().unwrap_or("/consent_snapshot_hash");
+        receipt_verifier::verify_receipt_consent_at(&val, leaf, root_path)
+            .unwrap_or_else(|e| fail(p.format, "consent membership check failed", &e));
+        say!("consent leaf {} is in the snapshot", leaf);
+    }
+    if let Some(doc) = checks.consent_doc.as_ref() {
+        receipt_verifier::ConsentDoc::load(doc)
+            .and_then(|granted| receipt_verifier::verify_consent_scopes(&receipt_verifier::receipt_consent_scopes(&val)?, &granted))
+            .unwrap_or_else(|e| fail(p.format, "consent scope check failed", &e));
+        say!("consent scopes are granted by {}", doc.display());
+    }
+
+    #[cfg(feature = "starknet")]
+    if let Some(hash) = checks.stark {
+        let verified = receipt_verifier::verify_receipt_stark(&val, &signature, gateway_arg, &policy_hash, &consent_hash, hash, &opts)
+            .unwrap_or_else(|e| fail(p.format, "verification failed", &e));
+        let public_key = format!("0x{}", hex::encode(verified.public_key));
+        say!("stark signature ok, policy/consent ok");
+        let rcpt_hash = checks.hash_format.format(&verified.receipt_hash_hex())?;
+        say!("receipt_hash: {}", rcpt_hash);
+        say!("stark public key: {}", public_key);
+        let report = json!({ "ok": true, "receipt_hash": rcpt_hash, "public_key": public_key });
+        let (pub_inputs, witness) = verified.public_and_witness(&signature);
+        return emit_outputs(p, &val, report, pub_inputs, witness, None);
+    }
+
+    #[cfg(feature = "ed25519")]
+    if ed25519_mode(checks) {
+        let verified = receipt_verifier::verify_receipt_ed25519(&val, &signature, gateway_arg, &policy_hash, &consent_hash, &opts)
+            .unwrap_or_else(|e| fail(p.format, "verification failed", &e));
+        let public_key = format!("0x{}", hex::encode(verified.public_key));
+        say!("ed25519 signature ok, policy/consent ok");
+        let rcpt_hash = checks.hash_format.format(&verified.receipt_hash_hex())?;
+        say!("receipt_hash: {}", rcpt_hash);
+        say!("ed25519 public key: {}", public_key);
+        let report = json!({ "ok": true, "receipt_hash": rcpt_hash, "public_key": public_key });
+        let (pub_inputs, witness) = verified.public_and_witness(&signature);
+        return emit_outputs(p, &val, report, pub_inputs, witness, None);
+    }
+
+    // The report covers EOA signatures only, so it takes precedence over the EIP-1271 fallback.
+    let verified = if report_path.is_some() || all_errors {
+        let report = receipt_verifier::verify_receipt_report(&val, &signature, &gateways, &policy_hash, &consent_hash, &opts);
+        if let Some(out) = report_path {
+            fs::write(out, json_bytes(p.diag.is_some_and(|d| d.compact), &report)?)?;
+            say!("saved verification report to {}", out.display());
+        }
+        if all_errors && !report.ok {
+            fail_all(p.format, &report.into_errors());
+        }
+        report.into_result()
+    } else {
+        match gateways.as_slice() {
+            #[cfg(feature = "rpc")]
+            [gw] if rpc.is_some() => {
+                let eth = rpc.as_ref().expect("checked by the guard");
+                verify_receipt_1271_full(&val, &signature, *gw, &policy_hash, &consent_hash, &opts, eth)
+            }
+            [] => verify_receipt_full(&val, &signature, None, &policy_hash, &consent_hash, &opts),
+            [gw] => verify_receipt_full(&val, &signature, Some(*gw), &policy_hash, &consent_hash, &opts),
+            gws => verify_receipt_multi(&val, &signature, gws, &policy_hash, &consent_hash, &opts).map(|(v, idx)| {
+                say!("matched gateway #{} of {}", idx, gws.len());
+                v
+            }),
+        }
+    };
+
+    let verified = verified.unwrap_or_else(|e| fail(p.format, "verification failed", &e));
+    let (rcpt_hash, addr) = (checks.hash_format.format(&verified.receipt_hash_hex())?, verified.signer);
+    let mut report = json!({
+        "ok": true,
+        "receipt_hash": rcpt_hash,
+        "recovered_address": receipt_verifier::to_checksum_address(addr),
+    });
+    say!("signature ok, policy/consent ok");
+    let hash_name = match opts.hash {
+        HashScheme::Sha256 => "sha256",
+        HashScheme::Keccak => "keccak",
+        HashScheme::Poseidon(_) => "poseidon",
+    };
+    let form = if opts.signed_raw.is_some() { "raw" } else { "canonical" };
+    say!("receipt_hash ({} {}): {}", hash_name, form, rcpt_hash);
+    say!("recovered address: {}", receipt_verifier::to_checksum_address(addr));
+    if let Some(chain_id) = verified.chain_id {
+        say!("signed for chain id {}", chain_id);
+        report["chain_id"] = json!(chain_id);
+    }
+    if checks.expect_receipt_hash.is_some() {
+        say!("receipt_hash matches the expected commitment");
+        report["receipt_hash_matched"] = json!(true);
+    }
+    #[cfg(feature = "rpc")]
+    if let (Some(min), Some(rpc)) = (checks.min_confirmations, rpc.as_ref()) {
+        let anchor = val.get("anchor").ok_or_else(|| anyhow::anyhow!("receipt has no anchor"))?;
+        let expect = receipt_verifier::AnchorExpect { min_confirmations: min, ..Default::default() };
+        let status = receipt_verifier::verify_anchor_with(rpc, anchor, &expect)?;
+        say!(
+            "anchor ok: {} in block {} ({} confirmations)",
+            status.tx_hash, status.block_number, status.confirmations
+        );
+        report["anchor"] = serde_json::to_value(&status)?;
+    }
+    let (pub_inputs, witness) = verified.public_and_witness(&signature);
+    let expected_gateway = (!gateways.is_empty()).then_some(addr);
+    emit_outputs(p, &val, report, pub_inputs, witness, expected_gateway)
+}
+
+/// The witness `build-inputs` and `prove` write or prove: with the `--redact` sub-trees replaced
+/// by commitments and, with `--withhold-signature`, the signature too, together with the
+/// openings for `--out-redactions` and the `--withhold-signature` file.
+struct BlindedWitness {
+    witness: Witness,
+    redactions: Vec<receipt_verifier::Redaction>,
+    opening: Option<receipt_verifier::SignatureOpening>,
+}
+
+/// `--redact` and `--withhold-signature` applied to one witness, for single receipts and each
+/// batch item alike.
+fn blind_witness(outputs: &OutputArgs, canon: CanonMode, witness: &Witness) -> Result<BlindedWitness, VerifyError> {
+    let mut blinded = BlindedWitness { witness: witness.clone(), redactions: Vec::new(), opening: None };
+    if !outputs.redact.is_empty() {
+        let pointers: Vec<&str> = outputs.redact.iter().map(String::as_str).collect();
+        let redacted = receipt_verifier::redact_witness_mode(witness, &pointers, canon)?;
+        blinded.witness = redacted.witness.clone();
+        blinded.redactions = redacted.redactions.clone();
+    }
+    if outputs.withhold_signature.is_some() {
+        let (withheld, opening) = receipt_verifier::withhold_signature(&blinded.witness)?;
+        blinded.witness = withheld;
+        blinded.opening = Some(opening);
+    }
+    Ok(blinded)
+}
+
+/// Public inputs, witness, proof and bundle outputs shared by every single-receipt path.
+/// `verify` only prints the JSON report.
+fn emit_outputs(
+    p: &Pipeline,
+    val: &serde_json::Value,
+    mut report: serde_json::Value,
+    pub_inputs: PublicInputs,
+    witness: Witness,
+    expected_gateway: Option<[u8; 20]>,
+) -> anyhow::Result<()> {
+    let json_mode = p.format == Format::Json;
+    macro_rules! say {
+        ($($t:tt)*) => {
+            if json_mode { eprintln!($($t)*) } else { println!($($t)*) }
+        };
+    }
+    let Some(outputs) = p.outputs else {
+        if json_mode {
+            println!("{}", report);
+        }
+        return Ok(());
+    };
+    let blinded = blind_witness(outputs, p.signing.canon, &witness)?;
+    if let Some(out) = outputs.out_redactions.as_ref() {
+        fs::write(out, json_bytes(outputs.compact, &blinded.redactions)?)?;
+        say!("redacted {} witness field(s); openings saved to {}", blinded.redactions.len(), out.display());
+    }
+    if let (Some(out), Some(opening)) = (outputs.withhold_signature.as_ref(), blinded.opening.as_ref()) {
+        fs::write(out, json_bytes(outputs.compact, opening)?)?;
+        say!("withheld the witness signature; opening saved to {}", out.display());
+    }
+    let witness = blinded.witness;
+    if json_mode {
+        report["public_inputs"] = serde_json::to_value(&pub_inputs)?;
+    } else {
+        println!("public inputs JSON:");
+        println!("{}", serde_json::to_string_pretty(&pub_inputs)?);
+    }
+    if let Some(out) = outputs.out_public.as_ref() {
+        write_output(outputs, out, &pub_inputs)?;
+        say!("saved public inputs to {}", out.display());
+    }
+    if let Some(out) = outputs.out_calldata.as_ref() {
+        write_output(outputs, out, &receipt_verifier::public_inputs_calldata(&pub_inputs)?)?;
+        say!("saved verifier calldata to {}", out.display());
+    }
+    if let Some(out) = outputs.out_witness.as_ref() {
+        write_output(outputs, out, &witness)?;
+        say!("saved witness to {}", out.display());
+        report["witness"] = serde_json::to_value(&witness)?;
+    }
+    if let Some(out) = outputs.out_witness_felts.as_ref() {
+        let field = outputs.felt_prime.map(|prime| receipt_verifier::Field { prime }).unwrap_or_default();
+        let felts = receipt_verifier::witness_felts(&witness, field)?;
+        write_output(outputs, out, &felts)?;
+        say!("saved {} witness felts to {}", felts.len(), out.display());
+    }
+    if let Some(prover) = p.prover {
+        let proof = prove(prover, &pub_inputs, &witness)?;
+        if json_mode {
+            report["proof"] = serde_json::to_value(&proof)?;
+        } else {
+            println!("proof:");
+            println!("{}", serde_json::to_string_pretty(&proof)?);
+        }
+        if let Some(out) = prover.out_proof.as_ref() {
+            write_output(outputs, out, &proof)?;
+            say!("saved proof to {}", out.display());
+        }
+        if let Some(dir) = prover.out_dir.as_ref() {
+            let name = expand_name(&prover.name_template, 0, witness.receipt_id.as_deref(), pub_inputs.receipt_hash.as_str())?;
+            write_proofs(outputs, dir, &[(name.clone(), &proof)])?;
+            say!("saved proof to {}", dir.join(name).display());
+        }
+        if let Some(out) = prover.out_bundle.as_ref() {
+            let bundle = receipt_verifier::ProofBundle::new(proof, val.clone(), expected_gateway)?;
+            write_output(outputs, out, &bundle)?;
+            say!("saved proof bundle to {}", out.display());
+        }
+    }
+    if json_mode {
+        println!("{}", report);
+    }
+    Ok(())
+}
+
+/// `--threshold`: verify a co-signed receipt against the `--gateway` list.
+fn run_multisig(
+    p: &Pipeline,
+    val: &serde_json::Value,
+    gateways: &[[u8; 20]],
+    threshold: usize,
+    policy_hash: &str,
+    consent_hash: &str,
+    opts: &VerifyOptions,
+) -> anyhow::Result<()> {
+    if gateways.is_empty() {
+        anyhow::bail!("--threshold needs the authorized gateways as a --gateway list, not auto");
+    }
+    if p.sig.signature.is_empty() {
+        anyhow::bail!("--threshold needs the co-signatures as repeated --signature");
+    }
+    let sigs =
+        p.sig.signature.iter().map(|s| receipt_verifier::signature_hex(s, p.sig.signature_encoding)).collect::<Result<Vec<_>, _>>()?;
+    let sig_refs: Vec<&str> = sigs.iter().map(String::as_str).collect();
+    let (verified, cosigners) =
+        receipt_verifier::verify_multisig_full(val, &sig_refs, gateways, threshold, policy_hash, consent_hash, opts)
+            .unwrap_or_else(|e| fail(p.format, "verification failed", &e));
+    let signers: Vec<String> = cosigners.iter().map(|c| receipt_verifier::to_checksum_address(c.signer)).collect();
+    let rcpt_hash = p.checks.hash_format.format(&verified.receipt_hash_hex())?;
+    let json_mode = p.format == Format::Json;
+    macro_rules! say {
+        ($($t:tt)*) => {
+            if json_mode { eprintln!($($t)*) } else { println!($($t)*) }
+        };
+    }
+    say!("{} of {} required co-signatures ok, policy/consent ok", signers.len(), threshold);
+    say!("receipt_hash: {}", rcpt_hash);
+    say!("co-signers: {}", signers.join(", "));
+    let report = json!({
+        "ok": true,
+        "receipt_hash": rcpt_hash,
+        "recovered_address": receipt_verifier::to_checksum_address(verified.signer),
+        "signers": signers,
+        "threshold": threshold,
+    });
+    // The witness carries the first counted co-signature.
+    let (pub_inputs, witness) = verified.public_and_witness(&sigs[cosigners[0].index]);
+    emit_outputs(p, val, report, pub_inputs, witness, Some(verified.signer))
+}
+
+/// `verify-proof`: check a receipt_sig proof against `--receipt` and the expected hashes.
+fn run_verify_proof(format: Format, cmd: &mut VerifyProofCmd) -> anyhow::Result<()> {
+    resolve_expectations(&mut cmd.expect, cmd.signing.canon)?;
+    let (policy_hash, consent_hash) = expected_hashes(&cmd.expect)?;
+    let (val, signed_raw) = load_signed(&cmd.signing, &cmd.input)?;
+    let opts = VerifyOptions { hash: hash_scheme(&cmd.hash), signed_raw, ..signing_options(&cmd.signing)? };
+    let proof = load_proof(&cmd.input, &cmd.proof)?;
+    match verify_proof_with(&proof, &val, &policy_hash, &consent_hash, &opts) {
+        Ok(()) => {
+            match format {
+                Format::Text => println!("proof ok ({}): {}", proof.prover, proof.proof_id),
+                Format::Json => println!("{}", json!({ "ok": true, "prover": proof.prover, "proof_id": proof.proof_id })),
             }
+            Ok(())
+        }
+        Err(e) => fail(format, "proof verification failed", &e),
+    }
+}
+
+/// `recover`: print the signer of `--receipt` and nothing else.
+fn run_recover(format: Format, cmd: &RecoverCmd) -> anyhow::Result<()> {
+    let (val, signed_raw) = load_signed(&cmd.signing, &cmd.input)?;
+    let opts = VerifyOptions { signed_raw, ..signing_options(&cmd.signing)? };
+    let signature = signature_arg(&cmd.sig, false, &val)?;
+    let candidates = receipt_verifier::recover_signer_candidates(&val, &signature, &opts)
+        .unwrap_or_else(|e| fail(format, "recovery failed", &e));
+    let candidates: Vec<String> = candidates.into_iter().map(receipt_verifier::to_checksum_address).collect();
+    // A DER signature has no recovery id, so it may come from either of two keys.
+    match (format, candidates.as_slice()) {
+        (Format::Text, [signer]) => println!("recovered address: {signer}"),
+        (Format::Text, _) => println!("candidate addresses (DER signature, no recovery id): {}", candidates.join(", ")),
+        (Format::Json, [signer]) => println!("{}", json!({ "ok": true, "recovered_address": signer })),
+        (Format::Json, _) => println!("{}", json!({ "ok": true, "recovered_address": null, "candidates": candidates })),
+    }
+    Ok(())
+}
+
+/// Placeholders `--name-template` expands.
+const NAME_PLACEHOLDERS: [&str; 3] = ["{receipt_id}", "{receipt_hash}", "{index}"];
+
+/// `--name-template` for one receipt. The values are reduced to file-name-safe characters so a
+/// receipt id cannot name a path outside `--out-dir`.
+fn expand_name(template: &str, index: usize, receipt_id: Option<&str>, receipt_hash: &str) -> anyhow::Result<String> {
+    let mut rest = template.to_string();
+    for placeholder in NAME_PLACEHOLDERS {
+        rest = rest.replace(placeholder, "");
+    }
+    if rest.contains(['{', '}']) {
+        anyhow::bail!("--name-template {template}: unknown placeholder (expected {})", NAME_PLACEHOLDERS.join(", "));
+    }
+    let safe = |v: &str| v.chars().map(|c| if c.is_ascii_alphanumeric() || "._-".contains(c) { c } else { '_' }).collect::<String>();
+    Ok(template
+        .replace("{receipt_id}", &safe(receipt_id.unwrap_or(receipt_hash)))
+        .replace("{receipt_hash}", &safe(receipt_hash))
+        .replace("{index}", &index.to_string()))
+}
+
+/// Write `proofs` (with their `--name-template` names) into `--out-dir`. Names must be
+/// distinct, so nothing is written if two receipts would share a file.
+fn write_proofs(outputs: &OutputArgs, dir: &Path, proofs: &[(String, &Proof)]) -> anyhow::Result<()> {
+    let mut names = std::collections::HashSet::new();
+    if let Some((name, _)) = proofs.iter().find(|(name, _)| !names.insert(name.as_str())) {
+        anyhow::bail!("--name-template names more than one receipt's proof {name}; add {{index}} or {{receipt_hash}}");
+    }
+    for (name, proof) in proofs {
+        let path = dir.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
         }
+        write_output(outputs, &path, proof)?;
+    }
+    Ok(())
+}
+
+/// Registered provers for `prove`. `auto` runs the external prover and only falls back to
+/// `receipt_sig` (reported on stderr) with `--allow-prover-fallback`.
+fn prover_registry(args: &ProverArgs) -> ProverRegistry {
+    let prove_opts = ProveOpts {
+        timeout: Duration::from_secs(args.prover_timeout),
+        retries: args.prover_retries,
+        cmd: args.prover_cmd.clone(),
+        ..Default::default()
+    };
+    let mut registry = ProverRegistry::with_defaults(prove_opts);
+    if args.allow_prover_fallback {
+        registry.allow_fallback(Some(|e| {
+            eprintln!("warning: external prover failed or not set: {e}; falling back to the receipt_sig stub")
+        }));
+    }
+    #[cfg(feature = "http")]
+    if let Some(url) = args.prover_url.as_ref() {
+        registry.register(
+            "http",
+            receipt_verifier::HttpProver {
+                api_key: args.prover_api_key.clone().or_else(|| std::env::var("LUMINAIR_PROVER_API_KEY").ok()),
+                auth_header: args.prover_auth_header.clone(),
+                timeout: Duration::from_secs(args.prover_timeout),
+                ..receipt_verifier::HttpProver::new(url.clone())
+            },
+        );
+    }
+    registry
+}
+
+/// The prover `prove` uses: `--prover`, or `receipt_sig` with `--stub`.
+fn prover_name(args: &ProverArgs) -> &str {
+    if args.stub { "receipt_sig" } else { args.prover.as_str() }
+}
+
+fn prove(args: &ProverArgs, pub_inputs: &PublicInputs, witness: &Witness) -> anyhow::Result<Proof> {
+    let proof = prover_registry(args).get(prover_name(args))?.prove(pub_inputs, witness);
+    if prover_name(args) == "auto" && !args.allow_prover_fallback {
+        return proof.context("proving failed; --stub uses the receipt_sig prover, --allow-prover-fallback falls back to it");
+    }
+    Ok(proof?)
+}
+
+/// `prove --check-prover`: run the selected prover on `canned_prover_input` and report the
+/// outcome, failing with the prover's error.
+fn check_prover(format: Format, args: &ProverArgs) -> anyhow::Result<()> {
+    let name = prover_name(args);
+    let command = match name {
+        "auto" | "subprocess" => args.prover_cmd.clone().or_else(|| std::env::var("LUMINAIR_PROVER_CMD").ok()),
+        _ => None,
+    };
+    let registry = prover_registry(args);
+    let started = Instant::now();
+    let checked = registry.get(name).and_then(receipt_verifier::check_prover);
+    let elapsed = started.elapsed();
+    let proof = match checked {
+        Ok(proof) => proof,
         Err(e) => {
-            eprintln!("verification failed: {e}");
-            std::process::exit(1);
+            let context = format!("prover check failed ({name}{})", command.as_deref().map(|c| format!(", command `{c}`")).unwrap_or_default());
+            fail(format, &context, &e);
+        }
+    };
+    match format {
+        Format::Text => {
+            println!("prover {name} ok in {elapsed:.2?}: {} ({})", proof.prover, proof.proof_id);
+            if let Some(cmd) = command {
+                println!("command: {cmd}");
+            }
+        }
+        Format::Json => println!(
+            "{}",
+            json!({ "ok": true, "prover": name, "command": command, "backend": proof.prover, "proof_id": proof.proof_id, "elapsed_ms": elapsed.as_millis() as u64 })
+        ),
+    }
+    Ok(())
+}
+
+/// `--min-confirmations` for a batch: check the anchor of every verified item, failing the
+/// items whose anchor is missing or not confirmed yet, so they are not proved. Returns each
+/// item's anchor status for the report.
+#[cfg(feature = "rpc")]
+fn check_batch_anchors(
+    checks: &CheckArgs,
+    rpc: Option<&RpcClient>,
+    items: &[(serde_json::Value, String)],
+    results: &mut [Result<receipt_verifier::VerifiedReceipt, VerifyError>],
+) -> anyhow::Result<Vec<Option<serde_json::Value>>> {
+    let mut anchors = vec![None; results.len()];
+    let (Some(min), Some(rpc)) = (checks.min_confirmations, rpc) else { return Ok(anchors) };
+    let expect = receipt_verifier::AnchorExpect { min_confirmations: min, ..Default::default() };
+    for ((res, (val, _)), anchor) in results.iter_mut().zip(items).zip(&mut anchors) {
+        if res.is_err() {
+            continue;
         }
+        let status = val
+            .get("anchor")
+            .ok_or_else(|| VerifyError::Anchor("receipt has no anchor".into()))
+            .and_then(|a| receipt_verifier::verify_anchor_with(rpc, a, &expect));
+        match status {
+            Ok(status) => *anchor = Some(serde_json::to_value(&status)?),
+            Err(e) => *res = Err(e),
+        }
+    }
+    Ok(anchors)
+}
+
+/// Verify a batch file or receipts directory and print per-receipt status plus a summary.
+fn run_batch(
+    p: &Pipeline,
+    batch: &BatchArgs,
+    gateways: &[[u8; 20]],
+    policy_hash: &str,
+    consent_hash: &str,
+    opts: &VerifyOptions,
+    #[cfg(feature = "rpc")] rpc: Option<&RpcClient>,
+) -> anyhow::Result<()> {
+    let mut labels = Vec::new();
+    let mut items = Vec::new();
+    if let Some(path) = batch.batch.as_ref() {
+        let text = String::from_utf8(read_input(p.input, Path::new(path))?)?;
+        for (i, item) in parse_batch(&text)?.into_iter().enumerate() {
+            labels.push(format!("#{i}"));
+            items.push((item.receipt, item.signature));
+        }
+    }
+    if let Some(dir) = batch.receipts_dir.as_ref() {
+        #[cfg(feature = "cbor")]
+        let ext = if p.input.input_format == Wire::Cbor { "cbor" } else { "json" };
+        #[cfg(not(feature = "cbor"))]
+        let ext = "json";
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| {
+                // `*.json.gz` / `*.cbor.gz` too; --input-compression auto unpacks them.
+                let p = if p.extension().is_some_and(|x| x == "gz") { Path::new(p.file_stem().unwrap_or_default()) } else { p };
+                p.extension().is_some_and(|x| x == ext)
+            })
+            .collect();
+        paths.sort();
+        for path in paths {
+            let val = load_receipt(p.input, &path)?.value;
+            let sig = val.get("receipt_sig").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+            labels.push(path.display().to_string());
+            items.push((val, sig));
+        }
+    }
+    #[cfg_attr(not(feature = "rpc"), allow(unused_mut))]
+    let mut results = verify_batch_full(&items, gateways, policy_hash, consent_hash, opts);
+    #[cfg(feature = "rpc")]
+    let anchors = check_batch_anchors(p.checks, rpc, &items, &mut results)?;
+    #[cfg(not(feature = "rpc"))]
+    let anchors: Vec<Option<serde_json::Value>> = vec![None; results.len()];
+    let mut proofs: Vec<Option<Result<Proof, VerifyError>>> = results.iter().map(|_| None).collect();
+    if let (Some(prover_args), Some(outputs)) = (p.prover, p.outputs) {
+        // Each item is redacted and withheld as a single receipt would be; the openings of every
+        // item go to one --out-redactions and one --withhold-signature file, keyed by item.
+        let (mut indices, mut to_prove, mut redactions, mut openings) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+        for (i, (res, (_, sig))) in results.iter_mut().zip(&items).enumerate() {
+            let Ok(v) = res else { continue };
+            let (pub_inputs, witness) = v.public_and_witness(sig);
+            match blind_witness(outputs, p.signing.canon, &witness) {
+                Ok(blinded) => {
+                    if !blinded.redactions.is_empty() {
+                        redactions.push(json!({ "item": labels[i], "receipt_hash": pub_inputs.receipt_hash, "redactions": blinded.redactions }));
+                    }
+                    if let Some(opening) = blinded.opening.as_ref() {
+                        openings.push(json!({ "item": labels[i], "receipt_hash": pub_inputs.receipt_hash, "opening": opening }));
+                    }
+                    indices.push(i);
+                    to_prove.push((pub_inputs, blinded.witness));
+                }
+                Err(e) => *res = Err(e),
+            }
+        }
+        if let Some(out) = outputs.out_redactions.as_ref() {
+            fs::write(out, json_bytes(outputs.compact, &redactions)?)?;
+            eprintln!("redacted {} witness field(s) in {} receipts; openings saved to {}", outputs.redact.len(), redactions.len(), out.display());
+        }
+        if let Some(out) = outputs.withhold_signature.as_ref() {
+            fs::write(out, json_bytes(outputs.compact, &openings)?)?;
+            eprintln!("withheld the witness signature of {} receipts; openings saved to {}", openings.len(), out.display());
+        }
+        let registry = prover_registry(prover_args);
+        let prover = registry.get(prover_name(prover_args))?;
+        let started = Instant::now();
+        let proved = receipt_verifier::prove_batch(&to_prove, prover, prover_args.prove_concurrency);
+        let elapsed = started.elapsed();
+        let proof_failures = proved.iter().filter(|p| p.is_err()).count();
+        eprintln!(
+            "proved {} of {} receipts in {:.2?} ({:.2?} per receipt, concurrency {})",
+            proved.len() - proof_failures,
+            proved.len(),
+            elapsed,
+            elapsed / proved.len().max(1) as u32,
+            prover_args.prove_concurrency.max(1)
+        );
+        for (i, proof) in indices.into_iter().zip(proved) {
+            proofs[i] = Some(proof);
+        }
+        if let Some(dir) = prover_args.out_dir.as_ref() {
+            let mut named = Vec::new();
+            for (i, (res, proof)) in results.iter().zip(&proofs).enumerate() {
+                if let (Ok(v), Some(Ok(proof))) = (res, proof) {
+                    let rcpt_hash = v.receipt_hash_hex();
+                    named.push((expand_name(&prover_args.name_template, i, v.receipt.receipt_id.as_deref(), rcpt_hash.as_str())?, proof));
+                }
+            }
+            write_proofs(outputs, dir, &named)?;
+            eprintln!("saved {} proofs to {}", named.len(), dir.display());
+        }
+    }
+    let mut failed = 0;
+    // Exit status: the failures' shared `exit_code`, or 1 when they fall in different categories.
+    let mut exit = None;
+    let mut note_failure = |code: i32| {
+        failed += 1;
+        exit = Some(if exit.is_none_or(|c| c == code) { code } else { 1 });
+    };
+    let mut report = Vec::new();
+    for ((((label, (_, sig)), res), proof), anchor) in labels.iter().zip(&items).zip(&results).zip(proofs).zip(anchors) {
+        let line = match (res, proof) {
+            (Ok(v), proof @ (None | Some(Ok(_)))) => {
+                let (rcpt_hash, signer) = (p.checks.hash_format.format(&v.receipt_hash_hex())?, receipt_verifier::to_checksum_address(v.signer));
+                let mut entry = json!({ "item": label, "ok": true, "receipt_hash": rcpt_hash, "recovered_address": signer });
+                let mut line = format!("ok     {label} receipt_hash={rcpt_hash} signer={signer}");
+                if let Some(anchor) = anchor {
+                    line.push_str(&format!(" anchor_confirmations={}", anchor["confirmations"]));
+                    entry["anchor"] = anchor;
+                }
+                if let Some(Ok(proof)) = proof {
+                    line.push_str(&format!(" proof={}", proof.proof_id));
+                    entry["proof"] = serde_json::to_value(&proof)?;
+                }
+                report.push(entry);
+                line
+            }
+            (Ok(_), Some(Err(e))) => {
+                note_failure(e.exit_code());
+                let error = json!({ "code": e.code(), "numeric_code": e.numeric_code(), "message": e.to_string() });
+                report.push(json!({ "item": label, "ok": false, "error": error }));
+                format!("FAILED {label}: proving failed: {e}")
+            }
+            (Err(e), _) => {
+                note_failure(e.exit_code());
+                let message = if sig.is_empty() { "no signature (receipt_sig missing)".to_string() } else { e.to_string() };
+                let error = json!({ "code": e.code(), "numeric_code": e.numeric_code(), "message": message });
+                report.push(json!({ "item": label, "ok": false, "error": error }));
+                format!("FAILED {label}: {message}")
+            }
+        };
+        if p.format == Format::Text {
+            println!("{line}");
+        }
+    }
+    match p.format {
+        Format::Text => println!("{} ok, {} failed", results.len() - failed, failed),
+        Format::Json => println!("{}", json!({ "ok": failed == 0, "passed": results.len() - failed, "failed": failed, "results": report })),
+    }
+    if let Some(code) = exit {
+        std::process::exit(code);
     }
     Ok(())
 }
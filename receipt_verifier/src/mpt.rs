@@ -0,0 +1,337 @@
+//! Anchor inclusion proofs against a block's receipts root (`mpt` feature).
+//!
+//! `verify_anchor` trusts the node's `eth_getTransactionReceipt`. Here the anchoring
+//! transaction's receipt is instead proven to be in the Merkle-Patricia receipts trie of a
+//! block whose `receiptsRoot` comes from a trusted source (a light client, a checkpointed
+//! header), so no RPC answer is trusted:
+//! - the trie is keyed by `rlp(tx_index)`; the proof is the list of RLP trie nodes from the
+//!   root down to the leaf (the `eth_getProof` layout, inline nodes under 32 bytes embedded
+//!   in their parent);
+//! - the leaf is the consensus receipt encoding: `rlp([status, cumulative_gas, bloom, logs])`,
+//!   prefixed with the EIP-2718 type byte for typed transactions.
+//!
+//! The receipts trie does not contain the transaction hash. `verify_transaction_proof`
+//! ties `tx_hash` to `tx_index` through the same block's transactions trie.
+
+use crate::{hex_bytes, keccak256, to_checksum_address, VerifyError};
+use serde::Deserialize;
+
+/// Inclusion proof for the entry at `tx_index` of a block's receipts or transactions trie.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReceiptProof {
+    pub tx_index: u64,
+    /// RLP-encoded trie nodes, root first, as hex.
+    pub nodes: Vec<String>,
+}
+
+/// A log of a proven receipt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProvenLog {
+    pub address: [u8; 20],
+    pub topics: Vec<[u8; 32]>,
+    pub data: Vec<u8>,
+}
+
+impl ProvenLog {
+    /// Whether `hash` is one of the indexed topics after `topics[0]` or a 32-byte word of `data`.
+    pub fn carries(&self, hash: &[u8; 32]) -> bool {
+        self.topics.iter().skip(1).any(|t| t == hash) || self.data.chunks(32).any(|w| w == hash)
+    }
+}
+
+/// A transaction receipt proven to be in a block's receipts trie.
+#[derive(Debug, Clone)]
+pub struct ProvenReceipt {
+    pub tx_hash: String,
+    pub tx_index: u64,
+    /// EIP-2718 transaction type (0 for legacy transactions).
+    pub tx_type: u8,
+    pub cumulative_gas_used: u64,
+    pub logs: Vec<ProvenLog>,
+}
+
+/// The anchoring event `verify_anchor_receipt_proof_with` requires in the proven receipt.
+#[derive(Debug, Clone, Default)]
+pub struct AnchorEvent {
+    /// Contract that must have emitted the event.
+    pub contract: Option<[u8; 20]>,
+    /// `topics[0]` of the event.
+    pub event_topic: Option<[u8; 32]>,
+    /// Receipt hash the event must carry, as an indexed topic or a word of its data.
+    pub receipt_hash: Option<[u8; 32]>,
+}
+
+impl AnchorEvent {
+    fn matches(&self, log: &ProvenLog) -> bool {
+        self.contract.is_none_or(|c| log.address == c)
+            && self.event_topic.is_none_or(|t| log.topics.first() == Some(&t))
+            && self.receipt_hash.is_none_or(|h| log.carries(&h))
+    }
+}
+
+/// Check that `proof` proves a successful receipt of transaction `tx_hash` (at
+/// `proof.tx_index`) under `block_receipts_root`, and decode it.
+pub fn verify_anchor_receipt_proof(
+    tx_hash: &str,
+    receipt_proof: &ReceiptProof,
+    block_receipts_root: [u8; 32],
+) -> Result<ProvenReceipt, VerifyError> {
+    let encoded = proven_value(receipt_proof, block_receipts_root, "receipts")?;
+    let (tx_type, body) = match encoded.first() {
+        Some(&t) if t < 0x80 => (t, &encoded[1..]),
+        _ => (0, &encoded[..]),
+    };
+    let (succeeded, cumulative_gas_used, logs) =
+        decode_receipt(body).map_err(|e| anchor_err(format!("proven receipt of {}: {}", tx_hash, e)))?;
+    if !succeeded {
+        return Err(anchor_err(format!("{} reverted (proven receipt status 0)", tx_hash)));
+    }
+    Ok(ProvenReceipt { tx_hash: tx_hash.to_string(), tx_index: receipt_proof.tx_index, tx_type, cumulative_gas_used, logs })
+}
+
+/// `verify_anchor_receipt_proof` that also requires the receipt to contain `expect`'s event.
+pub fn verify_anchor_receipt_proof_with(
+    tx_hash: &str,
+    receipt_proof: &ReceiptProof,
+    block_receipts_root: [u8; 32],
+    expect: &AnchorEvent,
+) -> Result<ProvenReceipt, VerifyError> {
+    let receipt = verify_anchor_receipt_proof(tx_hash, receipt_proof, block_receipts_root)?;
+    if !receipt.logs.iter().any(|log| expect.matches(log)) {
+        let by = expect.contract.map(|c| format!(" by {}", to_checksum_address(c))).unwrap_or_default();
+        return Err(anchor_err(format!("proven receipt of {} has no anchor event{}", tx_hash, by)));
+    }
+    Ok(receipt)
+}
+
+/// Check that the transaction at `proof.tx_index` under `block_transactions_root` hashes to
+/// `tx_hash`, binding the hash to the index the receipts trie is keyed by.
+pub fn verify_transaction_proof(tx_hash: &str, proof: &ReceiptProof, block_transactions_root: [u8; 32]) -> Result<(), VerifyError> {
+    let expected: [u8; 32] = crate::hex_array(tx_hash, "transaction hash")?;
+    let encoded = proven_value(proof, block_transactions_root, "transactions")?;
+    if keccak256(&encoded) != expected {
+        return Err(anchor_err(format!("transaction {} of the proof is not {}", proof.tx_index, tx_hash)));
+    }
+    Ok(())
+}
+
+/// Value stored under `key` in the trie with root `root`, `None` if the proof shows there is
+/// none. `nodes` run from the root down; each must hash to the reference its parent holds.
+pub fn verify_mpt_proof(root: [u8; 32], key: &[u8], nodes: &[Vec<u8>]) -> Result<Option<Vec<u8>>, VerifyError> {
+    let key: Vec<u8> = key.iter().flat_map(|b| [b >> 4, b & 0x0f]).collect();
+    let mut pos = 0;
+    let mut proof = nodes.iter().enumerate();
+    let mut reference = NodeRef::Hash(root);
+    loop {
+        let node = match reference {
+            NodeRef::Hash(hash) => {
+                let (i, node) = proof.next().ok_or_else(|| mpt_err("proof ends before the key's leaf"))?;
+                if keccak256(node) != hash {
+                    return Err(mpt_err(&format!("node {} does not match the hash its parent references", i)));
+                }
+                node.as_slice()
+            }
+            NodeRef::Inline(node) => node,
+        };
+        let items = rlp_list(node).map_err(|e| mpt_err(&e))?;
+        let next = match items.as_slice() {
+            [children @ .., value] if children.len() == 16 => {
+                if pos == key.len() {
+                    return finish(proof.next(), rlp_bytes(value).map(<[u8]>::to_vec));
+                }
+                let child = &children[key[pos] as usize];
+                pos += 1;
+                child
+            }
+            [path, child] => {
+                let path_bytes = rlp_bytes(path).ok_or_else(|| mpt_err("node path is not a byte string"))?;
+                let (is_leaf, path) = hex_prefix(path_bytes).map_err(|e| mpt_err(&e))?;
+                let rest = &key[pos..];
+                if is_leaf {
+                    let value = (rest == path.as_slice()).then(|| rlp_bytes(child).map(<[u8]>::to_vec));
+                    return finish(proof.next(), value.flatten());
+                }
+                if !rest.starts_with(&path) {
+                    return finish(proof.next(), None);
+                }
+                pos += path.len();
+                child
+            }
+            _ => return Err(mpt_err(&format!("node with {} items is neither a branch nor a leaf/extension", items.len()))),
+        };
+        reference = match next {
+            Rlp::Bytes([]) => return finish(proof.next(), None),
+            Rlp::Bytes(b) => NodeRef::Hash(<[u8; 32]>::try_from(*b).map_err(|_| mpt_err("child reference is not 32 bytes"))?),
+            Rlp::List { raw, .. } => NodeRef::Inline(raw),
+        };
+    }
+}
+
+enum NodeRef<'a> {
+    Hash([u8; 32]),
+    /// A child node under 32 bytes, embedded in its parent.
+    Inline(&'a [u8]),
+}
+
+/// The walk's result, unless proof nodes are left over.
+fn finish(leftover: Option<(usize, &Vec<u8>)>, value: Option<Vec<u8>>) -> Result<Option<Vec<u8>>, VerifyError> {
+    match leftover {
+        Some((i, _)) => Err(mpt_err(&format!("proof has unused nodes from node {}", i))),
+        None => Ok(value.filter(|v| !v.is_empty())),
+    }
+}
+
+fn proven_value(proof: &ReceiptProof, root: [u8; 32], trie: &str) -> Result<Vec<u8>, VerifyError> {
+    let nodes = proof
+        .nodes
+        .iter()
+        .map(|n| hex_bytes(n, false).map_err(|e| VerifyError::Hex(format!("proof node: {}", e))))
+        .collect::<Result<Vec<_>, _>>()?;
+    verify_mpt_proof(root, &rlp_index(proof.tx_index), &nodes)?
+        .ok_or_else(|| anchor_err(format!("the proof shows no entry {} in the {} trie", proof.tx_index, trie)))
+}
+
+fn mpt_err(reason: &str) -> VerifyError {
+    anchor_err(format!("invalid trie proof: {}", reason))
+}
+
+fn anchor_err(reason: String) -> VerifyError {
+    VerifyError::Anchor(reason)
+}
+
+/// `rlp(index)`, the trie key of a block's `index`th transaction and receipt.
+fn rlp_index(index: u64) -> Vec<u8> {
+    match index {
+        0 => vec![0x80],
+        1..=0x7f => vec![index as u8],
+        _ => {
+            let bytes = index.to_be_bytes();
+            let digits = &bytes[index.leading_zeros() as usize / 8..];
+            let mut out = vec![0x80 + digits.len() as u8];
+            out.extend_from_slice(digits);
+            out
+        }
+    }
+}
+
+/// Decode hex-prefix encoding: (is leaf, nibbles).
+fn hex_prefix(path: &[u8]) -> Result<(bool, Vec<u8>), String> {
+    let first = *path.first().ok_or("empty node path")?;
+    let flag = first >> 4;
+    if flag > 3 || (flag & 1 == 0 && first & 0x0f != 0) {
+        return Err(format!("bad hex-prefix flag 0x{:02x}", first));
+    }
+    let mut nibbles = Vec::with_capacity(path.len() * 2);
+    if flag & 1 == 1 {
+        nibbles.push(first & 0x0f);
+    }
+    nibbles.extend(path[1..].iter().flat_map(|b| [b >> 4, b & 0x0f]));
+    Ok((flag >= 2, nibbles))
+}
+
+/// `(succeeded, cumulative gas, logs)` of an RLP receipt body.
+fn decode_receipt(body: &[u8]) -> Result<(bool, u64, Vec<ProvenLog>), String> {
+    let items = rlp_list(body)?;
+    let [status, gas, _bloom, logs] = items.as_slice() else {
+        return Err(format!("expected 4 fields, got {}", items.len()));
+    };
+    let succeeded = match rlp_bytes(status).ok_or("status is a list")? {
+        [] => false,
+        [1] => true,
+        s if s.len() == 32 => return Err("pre-Byzantium receipt (state root, no status)".into()),
+        s => return Err(format!("bad status 0x{}", hex::encode(s))),
+    };
+    let gas = rlp_uint(gas).ok_or("bad cumulative gas")?;
+    let Rlp::List { payload, .. } = logs else { return Err("logs is not a list".into()) };
+    let logs = rlp_items(payload)?.iter().map(decode_log).collect::<Result<_, _>>()?;
+    Ok((succeeded, gas, logs))
+}
+
+fn decode_log(log: &Rlp) -> Result<ProvenLog, String> {
+    let Rlp::List { payload, .. } = log else { return Err("log is not a list".into()) };
+    let items = rlp_items(payload)?;
+    let [address, topics, data] = items.as_slice() else {
+        return Err(format!("log has {} fields, expected 3", items.len()));
+    };
+    let address = rlp_bytes(address).and_then(|a| a.try_into().ok()).ok_or("log address is not 20 bytes")?;
+    let Rlp::List { payload, .. } = topics else { return Err("log topics is not a list".into()) };
+    let topics = rlp_items(payload)?
+        .iter()
+        .map(|t| rlp_bytes(t).and_then(|t| t.try_into().ok()).ok_or_else(|| "log topic is not 32 bytes".to_string()))
+        .collect::<Result<_, _>>()?;
+    let data = rlp_bytes(data).ok_or("log data is a list")?.to_vec();
+    Ok(ProvenLog { address, topics, data })
+}
+
+/// One RLP item: a byte string, or a list with its full encoding and its payload.
+enum Rlp<'a> {
+    Bytes(&'a [u8]),
+    List { raw: &'a [u8], payload: &'a [u8] },
+}
+
+fn rlp_bytes<'a>(item: &Rlp<'a>) -> Option<&'a [u8]> {
+    match item {
+        Rlp::Bytes(b) => Some(b),
+        Rlp::List { .. } => None,
+    }
+}
+
+/// A canonical big-endian integer of at most 8 bytes.
+fn rlp_uint(item: &Rlp) -> Option<u64> {
+    let b = rlp_bytes(item)?;
+    if b.len() > 8 || b.first() == Some(&0) {
+        return None;
+    }
+    Some(b.iter().fold(0u64, |n, d| n << 8 | *d as u64))
+}
+
+/// The items of `buf`, which must be exactly one RLP list.
+fn rlp_list(buf: &[u8]) -> Result<Vec<Rlp<'_>>, String> {
+    match rlp_item(buf)? {
+        (Rlp::List { payload, .. }, []) => rlp_items(payload),
+        (Rlp::List { .. }, rest) => Err(format!("{} trailing bytes after the RLP list", rest.len())),
+        (Rlp::Bytes(_), _) => Err("expected an RLP list, got a byte string".into()),
+    }
+}
+
+fn rlp_items(mut payload: &[u8]) -> Result<Vec<Rlp<'_>>, String> {
+    let mut items = Vec::new();
+    while !payload.is_empty() {
+        let (item, rest) = rlp_item(payload)?;
+        items.push(item);
+        payload = rest;
+    }
+    Ok(items)
+}
+
+/// Decode the RLP item at the start of `buf`, rejecting non-canonical length encodings.
+fn rlp_item(buf: &[u8]) -> Result<(Rlp<'_>, &[u8]), String> {
+    let prefix = *buf.first().ok_or("truncated RLP")?;
+    let (is_list, header, len) = match prefix {
+        0x00..=0x7f => return Ok((Rlp::Bytes(&buf[..1]), &buf[1..])),
+        0x80..=0xb7 => (false, 1, (prefix - 0x80) as usize),
+        0xb8..=0xbf => (false, 1 + (prefix - 0xb7) as usize, long_len(buf, (prefix - 0xb7) as usize)?),
+        0xc0..=0xf7 => (true, 1, (prefix - 0xc0) as usize),
+        0xf8..=0xff => (true, 1 + (prefix - 0xf7) as usize, long_len(buf, (prefix - 0xf7) as usize)?),
+    };
+    let end = header.checked_add(len).filter(|end| *end <= buf.len()).ok_or("truncated RLP")?;
+    let payload = &buf[header..end];
+    if !is_list && len == 1 && header == 1 && payload[0] < 0x80 {
+        return Err("non-canonical RLP: single byte below 0x80 with a length prefix".into());
+    }
+    let item = if is_list { Rlp::List { raw: &buf[..end], payload } } else { Rlp::Bytes(payload) };
+    Ok((item, &buf[end..]))
+}
+
+/// Length of a long-form RLP item whose length takes `n` bytes after the prefix.
+fn long_len(buf: &[u8], n: usize) -> Result<usize, String> {
+    let digits = buf.get(1..1 + n).ok_or("truncated RLP length")?;
+    if digits[0] == 0 || n > 8 {
+        return Err("non-canonical RLP length".into());
+    }
+    let len = digits.iter().fold(0u64, |l, d| l << 8 | *d as u64);
+    if len < 56 {
+        return Err("non-canonical RLP: long form for a short item".into());
+    }
+    usize::try_from(len).map_err(|_| "RLP length overflows".into())
+}
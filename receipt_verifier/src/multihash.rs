@@ -0,0 +1,87 @@
+//! Receipt hashes as IPFS/IPLD identifiers: a sha2-256 multihash (`0x12 0x20 || digest`), or a
+//! CIDv1 naming the canonical receipt bytes as a `raw` block (base32 multibase, `bafkrei..`).
+//! Only sha256 receipt hashes have a multihash code; Poseidon hashes stay hex.
+
+use crate::{hex_array, HashHex, VerifyError};
+
+/// Multihash code of sha2-256.
+pub const SHA2_256_CODE: u8 = 0x12;
+/// Multicodec of a block that is just bytes (here, the canonical receipt JSON).
+pub const RAW_CODEC: u8 = 0x55;
+
+/// How `receipt_hash` is written in CLI output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HashFormat {
+    /// `0x` + lowercase hex of the digest.
+    #[default]
+    Hex,
+    /// `0x` + hex of the sha2-256 multihash.
+    Multihash,
+    /// CIDv1, `raw` codec, base32.
+    Cid,
+}
+
+impl std::str::FromStr for HashFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "hex" => Ok(HashFormat::Hex),
+            "multihash" => Ok(HashFormat::Multihash),
+            "cid" => Ok(HashFormat::Cid),
+            other => Err(format!("unknown hash format {other} (expected hex|multihash|cid)")),
+        }
+    }
+}
+
+impl HashFormat {
+    /// `hash` (a sha256 digest unless the format is `Hex`) in this format.
+    pub fn format(self, hash: &HashHex) -> Result<String, VerifyError> {
+        if self == HashFormat::Hex {
+            return Ok(hash.to_string());
+        }
+        let digest: [u8; 32] = hex_array(hash.digits(), "multihash needs a 32-byte sha256 digest")?;
+        Ok(match self {
+            HashFormat::Multihash => to_multihash(&digest),
+            _ => to_cid(&digest),
+        })
+    }
+}
+
+fn multihash_bytes(digest: &[u8]) -> Vec<u8> {
+    // Both prefix fields are varints; a single byte covers digests under 128 bytes.
+    let mut out = vec![SHA2_256_CODE, digest.len() as u8];
+    out.extend_from_slice(digest);
+    out
+}
+
+/// The sha2-256 multihash of `digest`, as `0x` hex (`0x1220..` for 32 bytes).
+pub fn to_multihash(digest: &[u8]) -> String {
+    format!("0x{}", hex::encode(multihash_bytes(digest)))
+}
+
+/// The CIDv1 of a `raw` block whose sha256 is `digest`: multibase `b` + lowercase unpadded
+/// RFC 4648 base32 of `0x01 || 0x55 || multihash`.
+pub fn to_cid(digest: &[u8]) -> String {
+    let mut bytes = vec![0x01, RAW_CODEC];
+    bytes.extend(multihash_bytes(digest));
+    format!("b{}", base32_lower(&bytes))
+}
+
+fn base32_lower(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+    let mut out = String::with_capacity(bytes.len() * 8 / 5 + 1);
+    let (mut acc, mut bits) = (0u32, 0u32);
+    for b in bytes {
+        acc = (acc << 8) | *b as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[((acc >> bits) & 0x1f) as usize] as char);
+        }
+        acc &= (1 << bits) - 1;
+    }
+    if bits > 0 {
+        out.push(ALPHABET[((acc << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    out
+}
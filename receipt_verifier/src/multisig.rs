@@ -0,0 +1,117 @@
+//! m-of-n co-signed receipts: several gateways sign the same canonical receipt (same digest as
+//! a single-signer receipt under the options' scheme), and the receipt verifies once
+//! `threshold` distinct authorized gateways have signed it.
+//!
+//! A signature that recovers to an unauthorized address, or to a signer already counted, does
+//! not count; a malformed signature is an error. The embedded `receipt_sig` is not consulted.
+
+use crate::{
+    check_chain_id, check_claims, ct_eq_bytes, parse_receipt, receipt_id_of, recover_expecting, signing_digest, telemetry,
+    to_checksum_address, VerifiedReceipt, VerifyError, VerifyOptions,
+};
+
+/// A co-signature that counted toward the threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoSigner {
+    pub signer: [u8; 20],
+    /// Index of its signature in `sigs`.
+    pub index: usize,
+}
+
+/// The distinct authorized signers of `receipt_val` among `sigs`, in signature order;
+/// `ThresholdNotMet` if there are fewer than `threshold`. Signatures only: no policy/consent,
+/// time or replay checks.
+pub fn verify_multisig(
+    receipt_val: &serde_json::Value,
+    sigs: &[&str],
+    authorized: &[[u8; 20]],
+    threshold: usize,
+) -> Result<Vec<[u8; 20]>, VerifyError> {
+    verify_multisig_with(receipt_val, sigs, authorized, threshold, &VerifyOptions::default())
+}
+
+/// `verify_multisig` honoring the options' signature scheme, canonicalization, high-S policy,
+/// chain id and expected receipt hash.
+pub fn verify_multisig_with(
+    receipt_val: &serde_json::Value,
+    sigs: &[&str],
+    authorized: &[[u8; 20]],
+    threshold: usize,
+    opts: &VerifyOptions,
+) -> Result<Vec<[u8; 20]>, VerifyError> {
+    let (_, _, digest) = signing_digest(receipt_val, opts)?;
+    let cosigners = co_signers(sigs, digest, authorized, threshold, opts)?;
+    Ok(cosigners.into_iter().map(|(c, _)| c.signer).collect())
+}
+
+/// `verify_multisig_with` plus the policy/consent, time window, registry and replay checks of
+/// `verify_receipt_full`. Every co-signer must pass the registry; the `VerifiedReceipt` and
+/// replay keys use the first co-signer.
+pub fn verify_multisig_full(
+    receipt_val: &serde_json::Value,
+    sigs: &[&str],
+    authorized: &[[u8; 20]],
+    threshold: usize,
+    expected_policy_hash: &str,
+    expected_consent_hash: &str,
+    opts: &VerifyOptions,
+) -> Result<(VerifiedReceipt, Vec<CoSigner>), VerifyError> {
+    telemetry::observe_verify(|| {
+        let _span = tracing::info_span!("verify_multisig", receipt_id = receipt_id_of(receipt_val), threshold).entered();
+        let receipt = parse_receipt(receipt_val, opts)?;
+        let (canonical, rcpt_hash, digest) = signing_digest(receipt_val, opts)?;
+        let cosigners = co_signers(sigs, digest, authorized, threshold, opts)?;
+        #[cfg(feature = "rpc")]
+        if let Some(registry) = &opts.registry {
+            for (c, _) in &cosigners[1..] {
+                crate::registry::check_registry(registry.as_ref(), c.signer, &receipt)?;
+            }
+        }
+        let (first, chain_id) = cosigners[0];
+        check_claims(&receipt, &rcpt_hash, first.signer, expected_policy_hash, expected_consent_hash, opts)?;
+        let (hash_scheme, digest_mode) = (opts.hash.clone(), opts.personal_digest_mode());
+        let verified =
+            VerifiedReceipt { receipt, canonical, receipt_hash: rcpt_hash, signer: first.signer, chain_id, hash_scheme, digest_mode };
+        Ok((verified, cosigners.into_iter().map(|(c, _)| c).collect()))
+    })
+}
+
+/// Recover every signature over `digest` and keep the distinct authorized signers, with each
+/// one's EIP-155 chain id.
+fn co_signers(
+    sigs: &[&str],
+    digest: [u8; 32],
+    authorized: &[[u8; 20]],
+    threshold: usize,
+    opts: &VerifyOptions,
+) -> Result<Vec<(CoSigner, Option<u64>)>, VerifyError> {
+    if threshold == 0 {
+        return Err(VerifyError::Config("co-signature threshold must be at least 1".into()));
+    }
+    if threshold > authorized.len() {
+        return Err(VerifyError::Config(format!("threshold {} exceeds the {} authorized gateways", threshold, authorized.len())));
+    }
+    let mut matched: Vec<(CoSigner, Option<u64>)> = Vec::new();
+    for (index, sig) in sigs.iter().enumerate() {
+        let (signer, chain_id) = match recover_expecting(sig, digest, Some(authorized), opts) {
+            // A DER co-signature recovering to no authorized gateway is just not counted.
+            Err(VerifyError::AddressMismatch { recovered }) => {
+                tracing::debug!(index, signer = %recovered, "co-signer is not authorized");
+                continue;
+            }
+            recovered => recovered?,
+        };
+        check_chain_id(chain_id, opts)?;
+        if crate::ct::ct_position(authorized, &signer).is_none() {
+            tracing::debug!(index, signer = %to_checksum_address(signer), "co-signer is not authorized");
+        } else if matched.iter().any(|(c, _)| ct_eq_bytes(&c.signer, &signer)) {
+            tracing::debug!(index, signer = %to_checksum_address(signer), "duplicate co-signer");
+        } else {
+            matched.push((CoSigner { signer, index }, chain_id));
+        }
+    }
+    if matched.len() < threshold {
+        return Err(VerifyError::ThresholdNotMet { have: matched.len(), need: threshold });
+    }
+    Ok(matched)
+}
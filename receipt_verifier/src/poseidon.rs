@@ -0,0 +1,188 @@
+//! Poseidon sponge over a configurable prime field (default: Stwo's M31, `2^31 - 1`), for
+//! receipt hashes a circuit can recompute cheaply.
+//!
+//! Byte-to-felt packing, which prover and verifier must share:
+//! - `k = (bits(p) - 1) / 8` bytes per element (3 for M31), so every chunk is below `p`;
+//! - the element sequence is `[len(bytes)]` followed by the bytes in `k`-byte chunks, each read
+//!   as a big-endian integer (the last chunk may be shorter);
+//! - the sponge has capacity 1 (`state[0]`) and rate `width - 1`; input is padded with one `1`
+//!   element and then zeros to a multiple of the rate, and each block is added into
+//!   `state[1..]` before a permutation;
+//! - output is `output_len` elements squeezed from `state[1..]`, each written as a big-endian
+//!   integer of `ceil(bits(p) / 8)` bytes, hex-encoded.
+//!
+//! The permutation is HADES (`full_rounds / 2` full, `partial_rounds` partial, `full_rounds / 2`
+//! full) with S-box `x^alpha` for the smallest odd `alpha >= 3` coprime to `p - 1`. Round
+//! constants are `keccak256("receipt_verifier.poseidon" || p || width || full_rounds ||
+//! partial_rounds || i)` (all `u64` big-endian), first 8 bytes big-endian, mod `p`; the MDS is
+//! the Cauchy matrix `1 / (i + (width + j))`.
+
+use crate::{keccak256, Field, VerifyError};
+use serde::{Deserialize, Serialize};
+
+/// Field and permutation parameters.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoseidonParams {
+    /// Field modulus; must be prime and above `3 * width`.
+    pub prime: u64,
+    pub width: usize,
+    pub full_rounds: usize,
+    pub partial_rounds: usize,
+    /// Field elements in the digest.
+    pub output_len: usize,
+}
+
+impl Default for PoseidonParams {
+    /// M31 with width 16, 8 full and 14 partial rounds, 8-element (248-bit) output.
+    fn default() -> Self {
+        Self { prime: (1 << 31) - 1, width: 16, full_rounds: 8, partial_rounds: 14, output_len: 8 }
+    }
+}
+
+impl PoseidonParams {
+    fn bits(&self) -> u32 {
+        64 - self.prime.leading_zeros()
+    }
+
+    /// Bytes packed into one field element.
+    pub fn bytes_per_felt(&self) -> usize {
+        ((self.bits() - 1) / 8) as usize
+    }
+
+    fn felt_bytes(&self) -> usize {
+        self.bits().div_ceil(8) as usize
+    }
+
+    fn validate(&self) -> Result<(), VerifyError> {
+        let bad = |m: &str| Err(VerifyError::Serde(format!("poseidon params: {}", m)));
+        if self.prime < 1 << 9 || self.prime.is_multiple_of(2) {
+            return bad("prime must be odd and at least 2^9");
+        }
+        if self.width < 2 || (3 * self.width) as u64 >= self.prime {
+            return bad("width must be at least 2 and below prime / 3");
+        }
+        if !self.full_rounds.is_multiple_of(2) || self.output_len == 0 {
+            return bad("full_rounds must be even and output_len non-zero");
+        }
+        Ok(())
+    }
+}
+
+struct Permutation<'a> {
+    params: &'a PoseidonParams,
+    alpha: u64,
+    constants: Vec<u64>,
+    mds: Vec<Vec<u64>>,
+}
+
+fn add(a: u64, b: u64, p: u64) -> u64 {
+    ((a as u128 + b as u128) % p as u128) as u64
+}
+
+fn mul(a: u64, b: u64, p: u64) -> u64 {
+    ((a as u128 * b as u128) % p as u128) as u64
+}
+
+fn pow(mut base: u64, mut exp: u64, p: u64) -> u64 {
+    let mut acc = 1;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            acc = mul(acc, base, p);
+        }
+        base = mul(base, base, p);
+        exp >>= 1;
+    }
+    acc
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+impl<'a> Permutation<'a> {
+    fn new(params: &'a PoseidonParams) -> Result<Self, VerifyError> {
+        params.validate()?;
+        let p = params.prime;
+        let t = params.width;
+        let alpha = (3..)
+            .step_by(2)
+            .take(64)
+            .find(|a| gcd(*a, p - 1) == 1)
+            .ok_or_else(|| VerifyError::Serde("poseidon params: no S-box exponent coprime to p - 1".into()))?;
+        let rounds = params.full_rounds + params.partial_rounds;
+        let mut seed = b"receipt_verifier.poseidon".to_vec();
+        for v in [p, t as u64, params.full_rounds as u64, params.partial_rounds as u64] {
+            seed.extend_from_slice(&v.to_be_bytes());
+        }
+        let constants = (0..(rounds * t) as u64)
+            .map(|i| {
+                let mut input = seed.clone();
+                input.extend_from_slice(&i.to_be_bytes());
+                let h = keccak256(&input);
+                u64::from_be_bytes(h[..8].try_into().expect("8 bytes")) % p
+            })
+            .collect();
+        let mds = (0..t)
+            .map(|i| (0..t).map(|j| pow((i + t + j) as u64 % p, p - 2, p)).collect())
+            .collect();
+        Ok(Self { params, alpha, constants, mds })
+    }
+
+    fn permute(&self, state: &mut [u64]) {
+        let p = self.params.prime;
+        let half = self.params.full_rounds / 2;
+        let rounds = self.params.full_rounds + self.params.partial_rounds;
+        let t = state.len();
+        for r in 0..rounds {
+            for (s, c) in state.iter_mut().zip(&self.constants[r * t..]) {
+                *s = add(*s, *c, p);
+            }
+            if r < half || r >= half + self.params.partial_rounds {
+                for s in state.iter_mut() {
+                    *s = pow(*s, self.alpha, p);
+                }
+            } else {
+                state[0] = pow(state[0], self.alpha, p);
+            }
+            let mixed: Vec<u64> = self
+                .mds
+                .iter()
+                .map(|row| row.iter().zip(state.iter()).fold(0, |acc, (m, s)| add(acc, mul(*m, *s, p), p)))
+                .collect();
+            state.copy_from_slice(&mixed);
+        }
+    }
+}
+
+/// Pack bytes into field elements as described in the module docs (length prefix included).
+pub fn pack_bytes(bytes: &[u8], params: &PoseidonParams) -> Result<Vec<u64>, VerifyError> {
+    params.validate()?;
+    Field { prime: params.prime }.pack(bytes)
+}
+
+/// Poseidon sponge hash of `bytes`, hex-encoded without `0x`.
+pub fn poseidon_hash_bytes(bytes: &[u8], params: &PoseidonParams) -> Result<String, VerifyError> {
+    let perm = Permutation::new(params)?;
+    let p = params.prime;
+    let rate = params.width - 1;
+    let mut input = pack_bytes(bytes, params)?;
+    input.push(1);
+    input.resize(input.len().div_ceil(rate) * rate, 0);
+    let mut state = vec![0u64; params.width];
+    for block in input.chunks(rate) {
+        for (s, x) in state[1..].iter_mut().zip(block) {
+            *s = add(*s, *x, p);
+        }
+        perm.permute(&mut state);
+    }
+    let mut out: Vec<u64> = Vec::with_capacity(params.output_len);
+    loop {
+        out.extend(state[1..].iter().take(params.output_len - out.len()));
+        if out.len() == params.output_len {
+            break;
+        }
+        perm.permute(&mut state);
+    }
+    let width = params.felt_bytes();
+    Ok(out.iter().map(|f| hex::encode(&f.to_be_bytes()[8 - width..])).collect())
+}
@@ -0,0 +1,684 @@
+//! Proof generation hooks: the built-in `receipt_sig` stub and the external prover subprocess.
+
+use crate::telemetry::observe_prove;
+use crate::{keccak256, parse_gateway, sha256_hex, verify_receipt_full, HashHex, CANONICALIZATION_VERSION, PROOF_VERSION, Proof, PublicInputs, VerifyError, VerifyOptions, Witness, WitnessSummary};
+#[cfg(feature = "subprocess")]
+use std::io::{Read, Write};
+#[cfg(feature = "subprocess")]
+use std::process::{Command, Stdio};
+#[cfg(feature = "subprocess")]
+use std::thread;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::time::Duration;
+#[cfg(feature = "subprocess")]
+use std::time::Instant;
+
+/// Deterministic proof id for a verification context: `proof_` followed by the hex
+/// `sha256("receipt_verifier.proof_id.v1" || f(receipt_hash) || f(policy_hash) ||
+/// f(consent_hash) || f(gateway_address) || f(prover))`, where `f(s)` is `s`'s UTF-8 length as
+/// a big-endian `u32` followed by its bytes, and the fields are the public-input strings as-is.
+/// When the public inputs carry `anchor`, `f(anchor_block_number) || f(anchor_timestamp)` follow
+/// (decimal, empty if absent), so ids of unanchored receipts are unchanged.
+/// Proofs over the same receipt under different policy/consent contexts, provers or anchoring
+/// epochs get different ids. The encoding is stable; a change would bump the `v1` tag.
+pub fn proof_id(pub_inputs: &PublicInputs, prover: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(b"receipt_verifier.proof_id.v1");
+    for field in [
+        pub_inputs.receipt_hash.as_str(),
+        pub_inputs.policy_hash.as_str(),
+        pub_inputs.consent_hash.as_str(),
+        pub_inputs.gateway_address.as_str(),
+        prover,
+    ] {
+        hasher.update((field.len() as u32).to_be_bytes());
+        hasher.update(field.as_bytes());
+    }
+    if let Some(anchor) = &pub_inputs.anchor {
+        for field in [anchor.anchor_block_number, anchor.anchor_timestamp] {
+            let field = field.map(|n| n.to_string()).unwrap_or_default();
+            hasher.update((field.len() as u32).to_be_bytes());
+            hasher.update(field.as_bytes());
+        }
+    }
+    format!("proof_{}", hex::encode(hasher.finalize()))
+}
+
+/// Mock prover hook: hashes public inputs + witness to emit a proof stub.
+/// Replace with Stwo/LuminAIR prover when ready. The proof payload is the signature itself;
+/// `SigProver` refuses witnesses that withhold it.
+pub fn mock_prove(pub_inputs: &PublicInputs, witness: &Witness) -> Proof {
+    Proof {
+        version: PROOF_VERSION,
+        // Use the actual receipt signature as the “proof” payload to avoid the prior hash stub.
+        proof_id: proof_id(pub_inputs, "receipt_sig"),
+        proof: witness.signature_hex.clone(),
+        public_inputs: pub_inputs.clone(),
+        witness_summary: WitnessSummary {
+            receipt_id: witness.receipt_id.clone(),
+            anchor_tx_hash: witness.anchor_tx_hash.clone(),
+            canonical_len: witness.canonical_receipt.len(),
+        },
+        prover: "receipt_sig".into(),
+    }
+}
+
+/// Verify a `receipt_sig` proof offline against the receipt it was produced from.
+///
+/// The proof only carries the signature, so the receipt is needed to rebuild the signed
+/// digest. Checks the public policy/consent hashes, recovers the signer from `proof.proof`
+/// and requires it to be `public_inputs.gateway_address`, checks the receipt's own
+/// policy/consent fields, and requires the recomputed receipt hash to match the public one.
+/// A proof stamped with a different `canonicalization_version` fails with `VersionMismatch`
+/// before any of that. Stamped proofs must also carry the receipt's anchoring epoch in
+/// `anchor` (else `Anchor`) and a `proof_id` that `proof_id` reproduces (else `Prover`);
+/// unstamped ones may have the old `proof_<receipt hash>` id and no `anchor`.
+pub fn verify_proof(
+    proof: &Proof,
+    receipt_val: &serde_json::Value,
+    expected_policy_hash: &str,
+    expected_consent_hash: &str,
+) -> Result<(), VerifyError> {
+    verify_proof_with(proof, receipt_val, expected_policy_hash, expected_consent_hash, &VerifyOptions::default())
+}
+
+/// `verify_proof` with the verification options the receipt was originally checked with. The
+/// hash scheme and digest mode recorded in the proof's public inputs take precedence over `opts`.
+pub fn verify_proof_with(
+    proof: &Proof,
+    receipt_val: &serde_json::Value,
+    expected_policy_hash: &str,
+    expected_consent_hash: &str,
+    opts: &VerifyOptions,
+) -> Result<(), VerifyError> {
+    if proof.backend() != "receipt_sig" {
+        return Err(VerifyError::Prover(format!("cannot verify {} proofs offline", proof.prover)));
+    }
+    let pi = &proof.public_inputs;
+    // Unstamped proofs predate versioning and are checked as-is.
+    if let Some(actual) = pi.canonicalization_version.filter(|v| *v != CANONICALIZATION_VERSION) {
+        return Err(VerifyError::VersionMismatch { expected: CANONICALIZATION_VERSION, actual });
+    }
+    if !pi.policy_hash.matches(expected_policy_hash) {
+        return Err(VerifyError::PolicyMismatch { expected: expected_policy_hash.to_string(), actual: pi.policy_hash.to_string() });
+    }
+    if !pi.consent_hash.matches(expected_consent_hash) {
+        return Err(VerifyError::ConsentMismatch { expected: expected_consent_hash.to_string(), actual: pi.consent_hash.to_string() });
+    }
+    let gateway = parse_gateway(&pi.gateway_address)?;
+    // Re-checking a proof is not a new submission of the receipt; recompute with the schemes the
+    // proof records, falling back to `opts` for proofs that predate them.
+    let opts = VerifyOptions {
+        replay: None,
+        hash: pi.hash_scheme.clone().unwrap_or_else(|| opts.hash.clone()),
+        digest: pi.digest_mode.unwrap_or(opts.digest),
+        ..opts.clone()
+    };
+    let verified = verify_receipt_full(receipt_val, &proof.proof, Some(gateway), expected_policy_hash, expected_consent_hash, &opts)?;
+    if !pi.receipt_hash.matches(&verified.receipt_hash) {
+        return Err(VerifyError::ReceiptHashMismatch {
+            expected: pi.receipt_hash.to_string(),
+            actual: format!("0x{}", verified.receipt_hash),
+        });
+    }
+    let legacy = pi.canonicalization_version.is_none();
+    let anchor = verified.anchor_info();
+    if pi.anchor != anchor && !(legacy && pi.anchor.is_none()) {
+        return Err(VerifyError::Anchor(format!(
+            "public inputs carry anchor {:?}, the receipt has {:?}",
+            pi.anchor, anchor
+        )));
+    }
+    let expected_id = proof_id(pi, &proof.prover);
+    if proof.proof_id != expected_id && !(legacy && proof.proof_id == format!("proof_{}", pi.receipt_hash.digits())) {
+        return Err(VerifyError::Prover(format!("proof_id {} does not match its public inputs ({})", proof.proof_id, expected_id)));
+    }
+    // The secondary digests are checked too, so consumers can rely on whichever they read.
+    let canonical = verified.canonical.as_bytes();
+    for (claimed, actual) in [
+        (&pi.receipt_hash_sha256, HashHex::normalize(&sha256_hex(canonical))),
+        (&pi.receipt_hash_keccak, HashHex::from_bytes(&keccak256(canonical))),
+    ] {
+        if let Some(claimed) = claimed.as_ref().filter(|c| **c != actual) {
+            return Err(VerifyError::ReceiptHashMismatch { expected: claimed.to_string(), actual: actual.to_string() });
+        }
+    }
+    Ok(())
+}
+
+/// A proof backend selectable at runtime through a `ProverRegistry`.
+pub trait Prover: Send + Sync {
+    fn prove(&self, pub_inputs: &PublicInputs, witness: &Witness) -> Result<Proof, VerifyError>;
+}
+
+/// The built-in `receipt_sig` stub (`mock_prove`); fails only for witnesses that withhold the signature.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SigProver;
+
+impl Prover for SigProver {
+    fn prove(&self, pub_inputs: &PublicInputs, witness: &Witness) -> Result<Proof, VerifyError> {
+        let _span = tracing::debug_span!("prove", backend = "receipt_sig", receipt_hash = %pub_inputs.receipt_hash).entered();
+        if witness.signature_hex.is_empty() {
+            return Err(VerifyError::Prover("the receipt_sig prover puts the signature in the proof; this witness withholds it".into()));
+        }
+        observe_prove("receipt_sig", || Ok(mock_prove(pub_inputs, witness)))
+    }
+}
+
+/// External prover subprocess (`external_prove_with_opts`).
+#[cfg(feature = "subprocess")]
+#[derive(Debug, Clone, Default)]
+pub struct SubprocessProver {
+    pub opts: ProveOpts,
+}
+
+#[cfg(feature = "subprocess")]
+impl Prover for SubprocessProver {
+    fn prove(&self, pub_inputs: &PublicInputs, witness: &Witness) -> Result<Proof, VerifyError> {
+        observe_prove("subprocess", || external_prove_with_opts(pub_inputs, witness, &self.opts))
+    }
+}
+
+/// Remote prover service (`http` feature): POSTs `{public_inputs, witness}` as JSON to
+/// `endpoint` and expects a `Proof` back.
+#[cfg(feature = "http")]
+#[derive(Debug, Clone)]
+pub struct HttpProver {
+    pub endpoint: String,
+    /// Sent in `auth_header`; as `Bearer <api_key>` when that is `Authorization`, verbatim otherwise.
+    pub api_key: Option<String>,
+    pub auth_header: String,
+    /// Whole-request limit, reported as `ProverTimeout`.
+    pub timeout: Duration,
+}
+
+#[cfg(feature = "http")]
+impl HttpProver {
+    /// No API key, `Authorization` header, 600 s timeout.
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            api_key: None,
+            auth_header: "Authorization".into(),
+            timeout: Duration::from_secs(600),
+        }
+    }
+}
+
+#[cfg(feature = "http")]
+impl Prover for HttpProver {
+    fn prove(&self, pub_inputs: &PublicInputs, witness: &Witness) -> Result<Proof, VerifyError> {
+        observe_prove("http", || {
+            let _span = tracing::info_span!("prove", backend = "http", endpoint = %self.endpoint, receipt_hash = %pub_inputs.receipt_hash).entered();
+            let client = reqwest::blocking::Client::builder()
+                .timeout(self.timeout)
+                .build()
+                .map_err(|e| VerifyError::Prover(e.to_string()))?;
+            let mut req = client
+                .post(&self.endpoint)
+                .json(&serde_json::json!({ "public_inputs": pub_inputs, "witness": witness }));
+            if let Some(key) = &self.api_key {
+                let value = if self.auth_header.eq_ignore_ascii_case("authorization") {
+                    format!("Bearer {}", key)
+                } else {
+                    key.clone()
+                };
+                req = req.header(self.auth_header.as_str(), value);
+            }
+            let to_err = |e: reqwest::Error| {
+                if e.is_timeout() {
+                    VerifyError::ProverTimeout(self.timeout)
+                } else {
+                    VerifyError::Prover(format!("{}: {}", self.endpoint, e))
+                }
+            };
+            let resp = req.send().map_err(to_err)?;
+            let status = resp.status();
+            let body = resp.text().map_err(to_err)?;
+            if !status.is_success() {
+                return Err(VerifyError::Prover(format!("prover returned {}: {}", status, body.trim())));
+            }
+            serde_json::from_str(&body).map_err(|e| VerifyError::Prover(format!("invalid proof from {}: {}", self.endpoint, e)))
+        })
+    }
+}
+
+/// Appended to `Proof::prover` by `FallbackProver` (`receipt_sig:fallback`), so consumers can
+/// tell a stand-in from the primary prover's proof.
+pub const FALLBACK_SUFFIX: &str = ":fallback";
+
+/// Tries `primary` and, if it fails, `fallback`; `on_fallback` is told why the primary failed.
+/// The fallback's proofs are stamped with `FALLBACK_SUFFIX`, and their `proof_id` is
+/// recomputed for the stamped prover name.
+pub struct FallbackProver {
+    pub primary: Box<dyn Prover>,
+    pub fallback: Box<dyn Prover>,
+    pub on_fallback: Option<fn(&VerifyError)>,
+}
+
+impl Prover for FallbackProver {
+    fn prove(&self, pub_inputs: &PublicInputs, witness: &Witness) -> Result<Proof, VerifyError> {
+        self.primary.prove(pub_inputs, witness).or_else(|e| {
+            tracing::info!(error = %e, "primary prover failed; using the fallback");
+            if let Some(report) = self.on_fallback {
+                report(&e);
+            }
+            let mut proof = self.fallback.prove(pub_inputs, witness)?;
+            proof.prover.push_str(FALLBACK_SUFFIX);
+            proof.proof_id = proof_id(&proof.public_inputs, &proof.prover);
+            Ok(proof)
+        })
+    }
+}
+
+/// Receipt behind `canned_prover_input`: zero policy/consent hashes, signed with the
+/// throwaway key `0x11..11` (address `0x19E7E376E7C213B7E7e7e46cc70A5dD086DAff2A`), which no
+/// gateway uses.
+const CANNED_RECEIPT: &str = r#"{"receipt_id":"rcpt_check_prover","policy_hash":"0x0000000000000000000000000000000000000000000000000000000000000000","consent_snapshot_hash":"0x0000000000000000000000000000000000000000000000000000000000000000"}"#;
+const CANNED_SIGNATURE: &str = "0x0d1caa776615f952f6ca68dd0541116efba6654f5c5ebe266ffd8a886bbf72242edd36e2b412fde149cfb98a5d3c5a6213582cf16e945676cb8282c24cf80e621c";
+
+/// Public inputs and witness of a tiny built-in receipt that verifies under default options,
+/// for exercising a prover without real receipts.
+pub fn canned_prover_input() -> (PublicInputs, Witness) {
+    let receipt: serde_json::Value = serde_json::from_str(CANNED_RECEIPT).expect("canned receipt is valid JSON");
+    let zero = format!("0x{}", "00".repeat(32));
+    let verified = verify_receipt_full(&receipt, CANNED_SIGNATURE, None, &zero, &zero, &VerifyOptions::default())
+        .expect("canned receipt verifies");
+    verified.public_and_witness(CANNED_SIGNATURE)
+}
+
+/// Run `prover` on `canned_prover_input` and check that the proof it returns is for those
+/// public inputs, e.g. to catch a misconfigured external prover before a long batch. A proof
+/// from a `FallbackProver`'s fallback counts as a failure of the prover being checked.
+pub fn check_prover(prover: &dyn Prover) -> Result<Proof, VerifyError> {
+    let (pub_inputs, witness) = canned_prover_input();
+    let proof = prover.prove(&pub_inputs, &witness)?;
+    if proof.public_inputs.receipt_hash != pub_inputs.receipt_hash {
+        return Err(VerifyError::Prover(format!(
+            "prover returned a proof for receipt hash {}, expected {}",
+            proof.public_inputs.receipt_hash, pub_inputs.receipt_hash
+        )));
+    }
+    if proof.is_fallback() {
+        return Err(VerifyError::Prover(format!("the primary prover failed; the proof came from the {} fallback", proof.backend())));
+    }
+    Ok(proof)
+}
+
+/// Named provers.
+#[derive(Default)]
+pub struct ProverRegistry {
+    provers: BTreeMap<String, Box<dyn Prover>>,
+}
+
+impl ProverRegistry {
+    /// Empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `receipt_sig` (`SigProver`), and with the `subprocess` feature `subprocess` and `auto`
+    /// (both `SubprocessProver` with `opts`). `auto` fails when the external prover does; see
+    /// `allow_fallback`. Without the feature only `receipt_sig` is registered.
+    pub fn with_defaults(opts: ProveOpts) -> Self {
+        let mut registry = Self::new();
+        registry.register("receipt_sig", SigProver);
+        #[cfg(feature = "subprocess")]
+        {
+            registry.register("subprocess", SubprocessProver { opts: opts.clone() });
+            registry.register("auto", SubprocessProver { opts });
+        }
+        #[cfg(not(feature = "subprocess"))]
+        let _ = opts;
+        registry
+    }
+
+    /// Opt in to `auto` falling back to `receipt_sig` when its prover fails, through a
+    /// `FallbackProver` (so those proofs are stamped with `FALLBACK_SUFFIX`) told about the
+    /// failure through `on_fallback`. Does nothing if `auto` is not registered.
+    pub fn allow_fallback(&mut self, on_fallback: Option<fn(&VerifyError)>) {
+        if let Some(primary) = self.provers.remove("auto") {
+            self.provers.insert(
+                "auto".into(),
+                Box::new(FallbackProver { primary, fallback: Box::new(SigProver), on_fallback }),
+            );
+        }
+    }
+
+    /// Add a prover, replacing any registered under the same name.
+    pub fn register(&mut self, name: impl Into<String>, prover: impl Prover + 'static) {
+        self.provers.insert(name.into(), Box::new(prover));
+    }
+
+    pub fn get(&self, name: &str) -> Result<&dyn Prover, VerifyError> {
+        self.provers.get(name).map(|p| p.as_ref()).ok_or_else(|| {
+            VerifyError::Prover(format!("unknown prover `{}` (available: {})", name, self.names().join(", ")))
+        })
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.provers.keys().map(|k| k.as_str()).collect()
+    }
+}
+
+/// Timeout and retry policy for the external prover.
+#[derive(Debug, Clone)]
+pub struct ProveOpts {
+    /// Wall-clock limit per attempt; the child is killed when it is exceeded.
+    pub timeout: Duration,
+    /// Extra attempts after a timeout or non-zero exit.
+    pub retries: u32,
+    /// Delay before the first retry, doubled for each following one.
+    pub backoff: Duration,
+    /// Prover command line; `None` reads `LUMINAIR_PROVER_CMD`.
+    pub cmd: Option<String>,
+}
+
+impl Default for ProveOpts {
+    fn default() -> Self {
+        Self { timeout: Duration::from_secs(600), retries: 0, backoff: Duration::from_millis(500), cmd: None }
+    }
+}
+
+/// External prover hook: call command in LUMINAIR_PROVER_CMD with stdin JSON {public_inputs, witness}.
+/// The command must return a JSON Proof on stdout.
+#[cfg(feature = "subprocess")]
+pub fn external_prove(pub_inputs: &PublicInputs, witness: &Witness) -> Result<Proof, VerifyError> {
+    external_prove_with_opts(pub_inputs, witness, &ProveOpts::default())
+}
+
+/// `external_prove` with a per-attempt timeout and retries (exponential backoff) on
+/// timeouts and non-zero exits. Spawn and output-parse errors are not retried.
+#[cfg(feature = "subprocess")]
+pub fn external_prove_with_opts(
+    pub_inputs: &PublicInputs,
+    witness: &Witness,
+    opts: &ProveOpts,
+) -> Result<Proof, VerifyError> {
+    let cmd_str = match &opts.cmd {
+        Some(cmd) => cmd.clone(),
+        None => std::env::var("LUMINAIR_PROVER_CMD")
+            .map_err(|_| VerifyError::Prover("LUMINAIR_PROVER_CMD not set".into()))?,
+    };
+    let _span = tracing::info_span!("prove", backend = "subprocess", cmd = %cmd_str, receipt_hash = %pub_inputs.receipt_hash).entered();
+    let payload = serde_json::to_vec(&serde_json::json!({ "public_inputs": pub_inputs, "witness": witness }))
+        .map_err(|e| VerifyError::Serde(e.to_string()))?;
+    let mut delay = opts.backoff;
+    let mut attempt = 0;
+    loop {
+        match run_prover_cmd(&cmd_str, &payload, opts.timeout) {
+            Err(e) if attempt < opts.retries && is_transient(&e) => {
+                tracing::warn!(error = %e, attempt, retry_in_ms = delay.as_millis() as u64, "prover attempt failed");
+                attempt += 1;
+                thread::sleep(delay);
+                delay *= 2;
+            }
+            res => return res,
+        }
+    }
+}
+
+/// Async wrapper that runs `external_prove_with_opts` on tokio's blocking pool.
+#[cfg(feature = "async")]
+pub async fn external_prove_async(
+    pub_inputs: PublicInputs,
+    witness: Witness,
+    opts: ProveOpts,
+) -> Result<Proof, VerifyError> {
+    tokio::task::spawn_blocking(move || external_prove_with_opts(&pub_inputs, &witness, &opts))
+        .await
+        .map_err(|e| VerifyError::Prover(e.to_string()))?
+}
+
+/// Failures worth retrying: the child ran but timed out or exited non-zero.
+#[cfg(feature = "subprocess")]
+fn is_transient(e: &VerifyError) -> bool {
+    match e {
+        VerifyError::ProverTimeout(_) => true,
+        VerifyError::Prover(msg) => msg.starts_with("prover failed"),
+        _ => false,
+    }
+}
+
+#[cfg(feature = "subprocess")]
+fn run_prover_cmd(cmd_str: &str, payload: &[u8], timeout: Duration) -> Result<Proof, VerifyError> {
+    let mut parts = cmd_str.split_whitespace();
+    let bin = parts.next().ok_or_else(|| VerifyError::Prover("empty LUMINAIR_PROVER_CMD".into()))?;
+    let args: Vec<&str> = parts.collect();
+    let mut child = Command::new(bin)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| VerifyError::Prover(e.to_string()))?;
+    // Feed stdin from its own thread and close it when done, while stdout/stderr are drained
+    // concurrently; a child that fills its stdout pipe before reading all input cannot deadlock us.
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = payload.to_vec();
+    let writer = thread::spawn(move || stdin.write_all(&input));
+    let stdout = drain(child.stdout.take().expect("stdout is piped"));
+    let stderr = drain(child.stderr.take().expect("stderr is piped"));
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait().map_err(|e| VerifyError::Prover(e.to_string()))? {
+            Some(status) => break status,
+            None if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(VerifyError::ProverTimeout(timeout));
+            }
+            None => thread::sleep(Duration::from_millis(10)),
+        }
+    };
+    // A child may legitimately exit without reading all of stdin; only its exit status matters.
+    let _ = writer.join();
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    if !status.success() {
+        return Err(VerifyError::Prover(format!(
+            "prover failed: {}",
+            String::from_utf8_lossy(&stderr)
+        )));
+    }
+    Proof::from_slice(&stdout).map_err(|e| VerifyError::Prover(e.to_string()))
+}
+
+#[cfg(feature = "subprocess")]
+fn drain(mut r: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = r.read_to_end(&mut buf);
+        buf
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AnchorInfo, HashScheme};
+
+    const RECEIPT: &str = include_str!("../../scripts/receipts/rcpt_1765778272_0cbf2a85.json");
+    const PROOF_V0: &str = include_str!("../../scripts/proofs/proof_v0.json");
+    const PROOF_V1: &str = include_str!("../../scripts/proofs/proof_v1.json");
+    const PROOF_V2: &str = include_str!("../../scripts/proofs/proof_v2.json");
+
+    fn check(proof: &Proof) -> Result<(), VerifyError> {
+        let receipt: serde_json::Value = serde_json::from_str(RECEIPT).unwrap();
+        verify_proof(proof, &receipt, &format!("0x{}", "ab".repeat(32)), &format!("0x{}", "cd".repeat(32)))
+    }
+
+    struct Failing;
+
+    impl Prover for Failing {
+        fn prove(&self, _: &PublicInputs, _: &Witness) -> Result<Proof, VerifyError> {
+            Err(VerifyError::Prover("unavailable".into()))
+        }
+    }
+
+    #[test]
+    fn proof_id_is_deterministic() {
+        let (pi, _) = canned_prover_input();
+        let (again, _) = canned_prover_input();
+        assert_eq!(proof_id(&pi, "receipt_sig"), proof_id(&again, "receipt_sig"));
+        assert!(proof_id(&pi, "receipt_sig").starts_with("proof_"));
+    }
+
+    #[test]
+    fn proof_id_changes_with_context() {
+        let (pi, _) = canned_prover_input();
+        let base = proof_id(&pi, "receipt_sig");
+        let other = HashHex::from_bytes(&[0x01; 32]);
+        let mut policy = pi.clone();
+        policy.policy_hash = other.clone();
+        let mut consent = pi.clone();
+        consent.consent_hash = other;
+        let mut anchored = pi.clone();
+        anchored.anchor = Some(AnchorInfo { anchor_block_number: Some(7), anchor_timestamp: None });
+        let mut later = anchored.clone();
+        later.anchor = Some(AnchorInfo { anchor_block_number: Some(8), anchor_timestamp: None });
+        let ids = [
+            base.clone(),
+            proof_id(&policy, "receipt_sig"),
+            proof_id(&consent, "receipt_sig"),
+            proof_id(&pi, "subprocess"),
+            proof_id(&anchored, "receipt_sig"),
+            proof_id(&later, "receipt_sig"),
+        ];
+        for (i, a) in ids.iter().enumerate() {
+            for b in &ids[i + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn proof_with_wrong_anchor_is_rejected() {
+        let mut proof = Proof::from_json(PROOF_V1).unwrap();
+        check(&proof).unwrap();
+        proof.public_inputs.anchor = Some(AnchorInfo { anchor_block_number: None, anchor_timestamp: Some(1) });
+        proof.proof_id = proof_id(&proof.public_inputs, &proof.prover);
+        assert!(matches!(check(&proof), Err(VerifyError::Anchor(_))));
+        proof.public_inputs.anchor = None;
+        proof.proof_id = proof_id(&proof.public_inputs, &proof.prover);
+        assert!(matches!(check(&proof), Err(VerifyError::Anchor(_))));
+    }
+
+    #[test]
+    fn proof_with_foreign_proof_id_is_rejected() {
+        let mut proof = Proof::from_json(PROOF_V1).unwrap();
+        proof.proof_id = proof_id(&proof.public_inputs, "subprocess");
+        assert!(matches!(check(&proof), Err(VerifyError::Prover(_))));
+    }
+
+    #[test]
+    fn fallback_proof_is_stamped_and_reidentified() {
+        let (pi, witness) = canned_prover_input();
+        let prover = FallbackProver { primary: Box::new(Failing), fallback: Box::new(SigProver), on_fallback: None };
+        let proof = prover.prove(&pi, &witness).unwrap();
+        assert_eq!(proof.prover, "receipt_sig:fallback");
+        assert!(proof.is_fallback());
+        assert_eq!(proof.proof_id, proof_id(&pi, "receipt_sig:fallback"));
+        assert_ne!(proof.proof_id, SigProver.prove(&pi, &witness).unwrap().proof_id);
+    }
+
+    const SHA256_VECTOR: &str = "0xa9154ef5e5691825ff1be2a1da3dabe375263493da745f00e1519279a8af3d56";
+    const KECCAK_VECTOR: &str = "0x811113683721f9f8f6d568d0c9d81ad66189b85595efe6b88cbccbdb1d50c80b";
+
+    /// A `receipt_sig` proof of the fixture receipt verified with `hash`.
+    fn prove_under(hash: HashScheme) -> Proof {
+        let receipt: serde_json::Value = serde_json::from_str(RECEIPT).unwrap();
+        let sig = receipt["receipt_sig"].as_str().unwrap();
+        let opts = VerifyOptions { hash, ..Default::default() };
+        let gateway = parse_gateway("0x3A66A80Ac10721e69b6A9Cd56B07282D9a094813").unwrap();
+        let (policy, consent) = (format!("0x{}", "ab".repeat(32)), format!("0x{}", "cd".repeat(32)));
+        let verified = verify_receipt_full(&receipt, sig, Some(gateway), &policy, &consent, &opts).unwrap();
+        let (pi, witness) = verified.public_and_witness(sig);
+        SigProver.prove(&pi, &witness).unwrap()
+    }
+
+    #[test]
+    fn receipt_hash_matches_the_fixture_vectors() {
+        for (hash, vector) in [(HashScheme::Sha256, SHA256_VECTOR), (HashScheme::Keccak, KECCAK_VECTOR)] {
+            let proof = prove_under(hash.clone());
+            assert_eq!(proof.public_inputs.receipt_hash.as_str(), vector);
+            assert_eq!(proof.public_inputs.hash_scheme, Some(hash));
+            check(&proof).unwrap();
+        }
+    }
+
+    #[test]
+    fn proof_relabelled_to_another_hash_scheme_is_rejected() {
+        let mut proof = prove_under(HashScheme::Keccak);
+        proof.public_inputs.hash_scheme = Some(HashScheme::Sha256);
+        match check(&proof) {
+            Err(VerifyError::ReceiptHashMismatch { expected, actual }) => {
+                assert_eq!((expected.as_str(), actual.as_str()), (KECCAK_VECTOR, SHA256_VECTOR));
+            }
+            other => panic!("expected ReceiptHashMismatch, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn every_proof_version_loads_and_verifies() {
+        for json in [PROOF_V0, PROOF_V1, PROOF_V2] {
+            let proof = Proof::from_json(json).unwrap();
+            assert_eq!(proof.version, PROOF_VERSION);
+            check(&proof).unwrap();
+            assert_eq!(Proof::from_slice(json.as_bytes()).unwrap().proof_id, proof.proof_id);
+        }
+    }
+
+    #[test]
+    fn older_proofs_tolerate_missing_and_unknown_fields() {
+        let mut v0: serde_json::Value = serde_json::from_str(PROOF_V0).unwrap();
+        v0.as_object_mut().unwrap().remove("witness_summary");
+        v0["added_by_a_later_version"] = serde_json::json!({ "x": 1 });
+        v0["public_inputs"]["also_unknown"] = true.into();
+        let proof = Proof::from_value(v0).unwrap();
+        assert_eq!(proof.version, PROOF_VERSION);
+        check(&proof).unwrap();
+    }
+
+    #[test]
+    fn newer_or_malformed_versions_are_rejected() {
+        let mut proof: serde_json::Value = serde_json::from_str(PROOF_V1).unwrap();
+        proof["version"] = (PROOF_VERSION + 1).into();
+        let err = Proof::from_value(proof.clone()).unwrap_err();
+        assert!(matches!(&err, VerifyError::Serde(m) if m.contains("newer than this verifier")), "{err}");
+        proof["version"] = "1".into();
+        assert!(Proof::from_value(proof).is_err());
+    }
+
+    #[test]
+    fn both_digests_are_carried_and_checked() {
+        for hash in [HashScheme::Sha256, HashScheme::Keccak] {
+            let proof = prove_under(hash);
+            let pi = &proof.public_inputs;
+            assert_eq!(pi.receipt_hash_sha256.as_ref().map(HashHex::as_str), Some(SHA256_VECTOR));
+            assert_eq!(pi.receipt_hash_keccak.as_ref().map(HashHex::as_str), Some(KECCAK_VECTOR));
+            assert_eq!(pi.receipt_hash_as(&HashScheme::Sha256).map(HashHex::as_str), Some(SHA256_VECTOR));
+            assert_eq!(pi.receipt_hash_as(&HashScheme::Keccak).map(HashHex::as_str), Some(KECCAK_VECTOR));
+
+            for edit_sha256 in [true, false] {
+                let mut edited = proof.clone();
+                let field = match edit_sha256 {
+                    true => &mut edited.public_inputs.receipt_hash_sha256,
+                    false => &mut edited.public_inputs.receipt_hash_keccak,
+                };
+                *field = Some(HashHex::from_bytes(&[0x01; 32]));
+                edited.proof_id = proof_id(&edited.public_inputs, &edited.prover);
+                let claimed = HashHex::from_bytes(&[0x01; 32]).to_string();
+                assert!(matches!(check(&edited), Err(VerifyError::ReceiptHashMismatch { expected, .. }) if expected == claimed));
+            }
+        }
+
+        // Proofs from before the secondary digests carry neither.
+        let v0 = Proof::from_json(PROOF_V0).unwrap();
+        assert!(v0.public_inputs.receipt_hash_sha256.is_none() && v0.public_inputs.receipt_hash_keccak.is_none());
+        assert!(v0.public_inputs.receipt_hash_as(&HashScheme::Keccak).is_none());
+        check(&v0).unwrap();
+    }
+}
@@ -0,0 +1,429 @@
+//! Typed view of a receipt. Unknown keys are kept in `extra` so newer receipts still parse.
+
+use crate::{HashHex, VerifyError};
+use serde::de::{Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Receipt {
+    #[serde(flatten)]
+    pub rest: serde_json::Value,
+}
+
+/// Version-1 receipt as emitted by the gateway.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReceiptV1 {
+    pub receipt_id: Option<String>,
+    pub policy_hash: String,
+    pub consent_snapshot_hash: String,
+    pub gateway: Option<String>,
+    pub anchor: Option<Anchor>,
+    pub receipt_sig: Option<String>,
+    pub issued_at: Option<u64>,
+    pub expires_at: Option<u64>,
+    pub nonce: Option<u64>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// `anchor` section added after signing when the receipt hash is anchored on L2.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Anchor {
+    pub l2_tx: Option<L2Tx>,
+    /// Unix seconds when the receipt was anchored.
+    pub timestamp: Option<u64>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct L2Tx {
+    pub tx_hash: Option<String>,
+    /// Block the anchoring transaction was included in.
+    pub block_number: Option<u64>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl ReceiptV1 {
+    /// Issuance time in unix seconds: `issued_at`, or the gateway's `timestamp` for receipts
+    /// that predate the field.
+    pub fn issued_at(&self) -> Option<u64> {
+        self.issued_at.or_else(|| self.extra.get("timestamp").and_then(|v| v.as_u64()))
+    }
+
+    pub fn anchor_tx_hash(&self) -> Option<&str> {
+        self.anchor.as_ref()?.l2_tx.as_ref()?.tx_hash.as_deref()
+    }
+
+    /// `anchor.l2_tx.block_number`.
+    pub fn anchor_block_number(&self) -> Option<u64> {
+        self.anchor.as_ref()?.l2_tx.as_ref()?.block_number
+    }
+
+    /// `anchor.timestamp`.
+    pub fn anchor_timestamp(&self) -> Option<u64> {
+        self.anchor.as_ref()?.timestamp
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    String,
+    /// A string `HashHex` accepts.
+    Hex,
+    Object,
+    Uint,
+}
+
+impl Kind {
+    fn matches(self, v: &serde_json::Value) -> bool {
+        match self {
+            Kind::String => v.is_string(),
+            Kind::Hex => v.as_str().is_some_and(|s| HashHex::parse(s).is_ok()),
+            Kind::Object => v.is_object(),
+            Kind::Uint => v.is_u64(),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Kind::String => "a string",
+            Kind::Hex => "a hex string",
+            Kind::Object => "an object",
+            Kind::Uint => "an unsigned integer",
+        }
+    }
+}
+
+// (path, kind, required); nested paths are only checked when their parent is present.
+const FIELDS: &[(&str, Kind, bool)] = &[
+    ("receipt_id", Kind::String, false),
+    ("policy_hash", Kind::Hex, true),
+    ("consent_snapshot_hash", Kind::Hex, true),
+    ("gateway", Kind::String, false),
+    ("anchor", Kind::Object, false),
+    ("anchor.l2_tx", Kind::Object, false),
+    ("anchor.timestamp", Kind::Uint, false),
+    ("anchor.l2_tx.tx_hash", Kind::String, false),
+    ("anchor.l2_tx.block_number", Kind::Uint, false),
+    ("receipt_sig", Kind::String, false),
+    ("issued_at", Kind::Uint, false),
+    ("expires_at", Kind::Uint, false),
+    ("timestamp", Kind::Uint, false),
+    ("nonce", Kind::Uint, false),
+];
+
+fn json_type(v: &serde_json::Value) -> &'static str {
+    match v {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "a bool",
+        serde_json::Value::Number(_) => "a number",
+        serde_json::Value::String(_) => "a string",
+        serde_json::Value::Array(_) => "an array",
+        serde_json::Value::Object(_) => "an object",
+    }
+}
+
+fn field_err(field: &str, reason: impl Into<String>) -> VerifyError {
+    VerifyError::ReceiptField { field: field.into(), reason: reason.into() }
+}
+
+/// Type-check `v`, the value of the receipt field named `path`.
+fn check_field(path: &str, kind: Kind, required: bool, v: Option<&serde_json::Value>) -> Result<(), VerifyError> {
+    match v {
+        None | Some(serde_json::Value::Null) if required => Err(field_err(path, "missing")),
+        None | Some(serde_json::Value::Null) => Ok(()),
+        Some(serde_json::Value::String(v)) if matches!(kind, Kind::Hex) && HashHex::parse(v).is_err() => {
+            Err(field_err(path, format!("expected {}, got {:?}", kind.name(), v)))
+        }
+        Some(v) if !kind.matches(v) => Err(field_err(path, format!("expected {}, got {}", kind.name(), json_type(v)))),
+        Some(_) => Ok(()),
+    }
+}
+
+impl Receipt {
+    /// Validate and convert a raw receipt into `ReceiptV1`, naming the first missing or
+    /// wrong-typed field.
+    pub fn parse(value: &serde_json::Value) -> Result<ReceiptV1, VerifyError> {
+        Receipt::parse_with(value, None, None)
+    }
+
+    /// `parse` for layouts that keep the policy and/or consent hash elsewhere: `policy_path` and
+    /// `consent_path` are JSON pointers (RFC 6901, e.g. `/meta/policy_hash`) replacing the
+    /// top-level `policy_hash` / `consent_snapshot_hash`, which are then not required.
+    pub fn parse_with(value: &serde_json::Value, policy_path: Option<&str>, consent_path: Option<&str>) -> Result<ReceiptV1, VerifyError> {
+        if !value.is_object() {
+            return Err(field_err("$", format!("receipt must be an object, got {}", json_type(value))));
+        }
+        let moved = [("policy_hash", policy_path), ("consent_snapshot_hash", consent_path)];
+        for (path, kind, required) in FIELDS {
+            if moved.iter().any(|(field, ptr)| field == path && ptr.is_some()) {
+                continue;
+            }
+            let (parent, key) = match path.rsplit_once('.') {
+                Some((p, k)) => (p.split('.').try_fold(value, |v, seg| v.get(seg)), k),
+                None => (Some(value), *path),
+            };
+            let Some(parent) = parent else { continue };
+            check_field(path, *kind, *required, parent.get(key))?;
+        }
+        if moved.iter().all(|(_, ptr)| ptr.is_none()) {
+            return deserialize_v1(value);
+        }
+        // Deserialize a copy with the moved hashes at the top level, where `ReceiptV1` has them.
+        let mut flat = value.clone();
+        for (field, ptr) in moved {
+            let Some(ptr) = ptr else { continue };
+            if !ptr.is_empty() && !ptr.starts_with('/') {
+                return Err(VerifyError::Config(format!("{} path {:?} is not a JSON pointer", field, ptr)));
+            }
+            let hash = value.pointer(ptr);
+            check_field(ptr, Kind::Hex, true, hash)?;
+            flat[field] = hash.cloned().unwrap_or_default();
+        }
+        deserialize_v1(&flat)
+    }
+}
+
+/// `ReceiptV1::deserialize`, also for receipts holding integers beyond u64/i64. serde buffers
+/// `#[serde(flatten)]` fields and cannot buffer 128-bit integers, so those are deserialized as
+/// `null` and the `extra` maps then copied from `value`. Typed fields never hold such
+/// integers: `FIELDS` requires them to be u64.
+fn deserialize_v1(value: &serde_json::Value) -> Result<ReceiptV1, VerifyError> {
+    let serde_err = |e: serde_json::Error| VerifyError::Serde(e.to_string());
+    if !has_wide_integer(value) {
+        return ReceiptV1::deserialize(value).map_err(serde_err);
+    }
+    let mut narrowed = value.clone();
+    narrow_integers(&mut narrowed);
+    let mut receipt = ReceiptV1::deserialize(&narrowed).map_err(serde_err)?;
+    restore_extra(&mut receipt.extra, value);
+    let anchor = value.get("anchor");
+    if let (Some(a), Some(anchor)) = (receipt.anchor.as_mut(), anchor) {
+        restore_extra(&mut a.extra, anchor);
+        if let (Some(tx), Some(l2_tx)) = (a.l2_tx.as_mut(), anchor.get("l2_tx")) {
+            restore_extra(&mut tx.extra, l2_tx);
+        }
+    }
+    Ok(receipt)
+}
+
+fn is_wide_integer(n: &serde_json::Number) -> bool {
+    !n.is_u64() && !n.is_i64() && crate::is_integer_literal(n)
+}
+
+fn has_wide_integer(v: &serde_json::Value) -> bool {
+    match v {
+        serde_json::Value::Number(n) => is_wide_integer(n),
+        serde_json::Value::Array(arr) => arr.iter().any(has_wide_integer),
+        serde_json::Value::Object(map) => map.values().any(has_wide_integer),
+        _ => false,
+    }
+}
+
+fn narrow_integers(v: &mut serde_json::Value) {
+    match v {
+        serde_json::Value::Number(n) if is_wide_integer(n) => *v = serde_json::Value::Null,
+        serde_json::Value::Array(arr) => arr.iter_mut().for_each(narrow_integers),
+        serde_json::Value::Object(map) => map.values_mut().for_each(narrow_integers),
+        _ => {}
+    }
+}
+
+fn restore_extra(extra: &mut serde_json::Map<String, serde_json::Value>, original: &serde_json::Value) {
+    for (key, v) in extra.iter_mut() {
+        if let Some(orig) = original.get(key) {
+            *v = orig.clone();
+        }
+    }
+}
+
+/// Parse receipt JSON straight from a reader, without buffering the whole document as text.
+pub fn read_receipt(reader: impl Read) -> Result<serde_json::Value, VerifyError> {
+    serde_json::from_reader(BufReader::new(reader)).map_err(|e| VerifyError::Serde(e.to_string()))
+}
+
+/// `read_receipt` over a file.
+pub fn read_receipt_file(path: impl AsRef<Path>) -> Result<serde_json::Value, VerifyError> {
+    let path = path.as_ref();
+    let file = std::fs::File::open(path).map_err(|e| VerifyError::Serde(format!("{}: {}", path.display(), e)))?;
+    read_receipt(file)
+}
+
+/// Key serde_json's `arbitrary_precision` numbers are deserialized under.
+const NUMBER_TOKEN: &str = "$serde_json::private::Number";
+
+/// `serde_json::Value` that refuses duplicate object keys instead of keeping the last one.
+struct StrictValue(serde_json::Value);
+
+impl<'de> Deserialize<'de> for StrictValue {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        d.deserialize_any(StrictVisitor).map(StrictValue)
+    }
+}
+
+struct StrictVisitor;
+
+impl<'de> Visitor<'de> for StrictVisitor {
+    type Value = serde_json::Value;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(v.into())
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(v.into())
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(v.into())
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(serde_json::Number::from_f64(v).map_or(serde_json::Value::Null, serde_json::Value::Number))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(v.into())
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E> {
+        Ok(v.into())
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E> {
+        Ok(serde_json::Value::Null)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut out = Vec::new();
+        while let Some(StrictValue(v)) = seq.next_element()? {
+            out.push(v);
+        }
+        Ok(serde_json::Value::Array(out))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut out = serde_json::Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            // With `arbitrary_precision`, serde_json hands over numbers outside u64/i64 (and all
+            // non-integers) as a one-entry map holding the literal.
+            if key == NUMBER_TOKEN && out.is_empty() {
+                let literal: String = map.next_value()?;
+                return literal.parse().map(serde_json::Value::Number).map_err(serde::de::Error::custom);
+            }
+            if out.contains_key(&key) {
+                return Err(serde::de::Error::custom(format!("duplicate key: {}", key)));
+            }
+            let StrictValue(v) = map.next_value()?;
+            out.insert(key, v);
+        }
+        Ok(serde_json::Value::Object(out))
+    }
+}
+
+/// Parse receipt JSON, rejecting duplicate object keys at any depth. Plain `serde_json`
+/// keeps the last duplicate, so a reviewer and the verifier could read different values.
+pub fn parse_receipt_strict(json: &str) -> Result<serde_json::Value, VerifyError> {
+    serde_json::from_str::<StrictValue>(json).map(|v| v.0).map_err(|e| VerifyError::Serde(e.to_string()))
+}
+
+/// `parse_receipt_strict` straight from a reader.
+pub fn read_receipt_strict(reader: impl Read) -> Result<serde_json::Value, VerifyError> {
+    serde_json::from_reader::<_, StrictValue>(BufReader::new(reader))
+        .map(|v| v.0)
+        .map_err(|e| VerifyError::Serde(e.to_string()))
+}
+
+/// A receipt as loaded: the bytes it was read from next to the value they parse to, so one
+/// load serves both canonical verification and verification over exactly what was signed
+/// (`VerifyOptions::signed_raw`).
+#[derive(Debug, Clone)]
+pub struct RawReceipt {
+    pub bytes: Vec<u8>,
+    pub value: serde_json::Value,
+}
+
+impl RawReceipt {
+    /// Parse receipt JSON `bytes`, keeping them; `strict` rejects duplicate keys as
+    /// `parse_receipt_strict` does.
+    pub fn from_json(bytes: Vec<u8>, strict: bool) -> Result<Self, VerifyError> {
+        let value = if strict { read_receipt_strict(&bytes[..])? } else { read_receipt(&bytes[..])? };
+        Ok(RawReceipt { bytes, value })
+    }
+
+    /// `from_json` over a file.
+    pub fn read_file(path: impl AsRef<Path>, strict: bool) -> Result<Self, VerifyError> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|e| VerifyError::Serde(format!("{}: {}", path.display(), e)))?;
+        RawReceipt::from_json(bytes, strict)
+    }
+
+    /// The bytes as text, for `VerifyOptions::signed_raw`.
+    pub fn signed_raw(&self) -> Result<Arc<str>, VerifyError> {
+        std::str::from_utf8(&self.bytes)
+            .map(Arc::from)
+            .map_err(|_| VerifyError::ReceiptField { field: "receipt_raw".into(), reason: "is not UTF-8 text".into() })
+    }
+
+    /// A receipt carrying its signed bytes in a top-level `receipt_raw` string, as the receipt
+    /// those bytes parse to (duplicate keys rejected) plus the wrapper's other top-level fields
+    /// (`receipt_sig`, `anchor`). Receipts without `receipt_raw` are returned as they are.
+    pub fn unwrap_embedded(self) -> Result<Self, VerifyError> {
+        let Some(raw) = self.value.get("receipt_raw").and_then(|v| v.as_str()) else { return Ok(self) };
+        let mut value = parse_receipt_strict(raw)?;
+        if let (Some(inner), Some(outer)) = (value.as_object_mut(), self.value.as_object()) {
+            for (k, v) in outer {
+                if k != "receipt_raw" && !inner.contains_key(k) {
+                    inner.insert(k.clone(), v.clone());
+                }
+            }
+        }
+        Ok(RawReceipt { bytes: raw.as_bytes().to_vec(), value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strict_parse_rejects_duplicate_keys_at_any_depth() {
+        for json in [
+            r#"{"policy_hash":"0x01","policy_hash":"0x02"}"#,
+            r#"{"a":{"b":1,"b":1}}"#,
+            r#"{"a":[{"ok":true},{"c":1,"d":2,"c":3}]}"#,
+        ] {
+            let err = parse_receipt_strict(json).unwrap_err();
+            assert!(matches!(&err, VerifyError::Serde(m) if m.contains("duplicate key")), "{json}: {err}");
+            assert!(read_receipt_strict(json.as_bytes()).is_err());
+            assert!(RawReceipt::from_json(json.as_bytes().to_vec(), true).is_err());
+            // Plain parsing keeps the last value instead.
+            RawReceipt::from_json(json.as_bytes().to_vec(), false).unwrap();
+        }
+        assert_eq!(read_receipt(&br#"{"k":1,"k":2}"#[..]).unwrap()["k"], 2);
+    }
+
+    #[test]
+    fn strict_parse_matches_plain_parse_otherwise() {
+        let json = r#"{"a":{"b":[1,-2,3.5,123456789012345678901,null,true,"s"]},"b":{"a":{}},"c":[]}"#;
+        let strict = parse_receipt_strict(json).unwrap();
+        assert_eq!(strict, read_receipt(json.as_bytes()).unwrap());
+        assert_eq!(strict["a"]["b"][3].to_string(), "123456789012345678901");
+    }
+
+    #[test]
+    fn embedded_raw_receipt_is_parsed_strictly() {
+        let wrapper = serde_json::json!({ "receipt_raw": r#"{"receipt_id":"r","receipt_id":"s"}"#, "receipt_sig": "0x00" });
+        let raw = RawReceipt { bytes: wrapper.to_string().into_bytes(), value: wrapper };
+        assert!(matches!(raw.unwrap_embedded(), Err(VerifyError::Serde(m)) if m.contains("duplicate key")));
+    }
+}
@@ -0,0 +1,297 @@
+//! Selective disclosure for witnesses handed to third-party provers.
+//!
+//! `redact_witness` replaces the sub-trees at the given JSON pointers of
+//! `Witness.canonical_receipt` with `{"$redacted": "<commitment>"}`, where the commitment is
+//! `sha256(salt || canonical(sub-tree))` with a fresh 32-byte salt per pointer. Every other byte
+//! of the canonical form is unchanged. The salts stay with the caller: together with the
+//! original witness they let anyone holding them check (`verify_redaction`) that a redacted
+//! witness was derived from the receipt whose hash is `PublicInputs.receipt_hash`.
+//!
+//! A redacted witness no longer hashes to `receipt_hash` on its own: the circuit must be built to
+//! accept commitments in place of the redacted values and to open them (or prove statements
+//! about them) rather than hash the canonical bytes directly.
+
+use crate::{canonical_string_mode, ct_eq_bytes, hex_array, hex_bytes, sha256_hex, CanonMode, VerifyError, Witness};
+use serde::{Deserialize, Serialize};
+
+/// Key of the placeholder object that stands in for a redacted sub-tree.
+pub const REDACTED_KEY: &str = "$redacted";
+
+/// One redacted sub-tree: its pointer, salt and commitment (hex, no `0x`). Keep these private;
+/// the salt is what prevents guessing low-entropy values from the commitment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "zeroize", derive(zeroize::Zeroize, zeroize::ZeroizeOnDrop))]
+pub struct Redaction {
+    pub pointer: String,
+    pub salt: String,
+    pub commitment: String,
+}
+
+/// A redacted witness and the openings for its placeholders.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "zeroize", derive(zeroize::Zeroize, zeroize::ZeroizeOnDrop))]
+pub struct RedactedWitness {
+    pub witness: Witness,
+    pub redactions: Vec<Redaction>,
+}
+
+/// `sha256(salt || canonical(subtree))` as hex.
+pub fn redaction_commitment(subtree: &serde_json::Value, salt: &[u8; 32], mode: CanonMode) -> Result<String, VerifyError> {
+    let canonical = canonical_string_mode(subtree, mode)?;
+    let mut preimage = salt.to_vec();
+    preimage.extend_from_slice(canonical.as_bytes());
+    Ok(sha256_hex(&preimage))
+}
+
+/// Redact `pointers` from a witness built with the default (sorted) canonicalization.
+pub fn redact_witness(witness: &Witness, pointers: &[&str]) -> Result<RedactedWitness, VerifyError> {
+    redact_witness_mode(witness, pointers, CanonMode::Sorted)
+}
+
+/// `redact_witness` for a witness canonicalized in `mode`.
+pub fn redact_witness_mode(witness: &Witness, pointers: &[&str], mode: CanonMode) -> Result<RedactedWitness, VerifyError> {
+    let mut salts = Vec::with_capacity(pointers.len());
+    for _ in pointers {
+        let mut salt = [0u8; 32];
+        getrandom::getrandom(&mut salt).map_err(|e| VerifyError::Config(format!("redaction salt: {}", e)))?;
+        salts.push(salt);
+    }
+    redact_with_salts(witness, pointers, &salts, mode)
+}
+
+fn redact_with_salts(
+    witness: &Witness,
+    pointers: &[&str],
+    salts: &[[u8; 32]],
+    mode: CanonMode,
+) -> Result<RedactedWitness, VerifyError> {
+    let mut tree: serde_json::Value =
+        serde_json::from_str(&witness.canonical_receipt).map_err(|e| VerifyError::Serde(e.to_string()))?;
+    let mut redactions = Vec::with_capacity(pointers.len());
+    for (pointer, salt) in pointers.iter().zip(salts) {
+        if pointer.is_empty() {
+            return Err(VerifyError::Serde("cannot redact the whole receipt".into()));
+        }
+        let slot = tree
+            .pointer_mut(pointer)
+            .ok_or_else(|| VerifyError::Serde(format!("redaction pointer `{}` does not resolve in the witness", pointer)))?;
+        let commitment = redaction_commitment(slot, salt, mode)?;
+        *slot = serde_json::json!({ REDACTED_KEY: commitment });
+        redactions.push(Redaction { pointer: pointer.to_string(), salt: hex::encode(salt), commitment });
+    }
+    // Field by field rather than `..witness.clone()`, which `ZeroizeOnDrop` rules out.
+    let witness = Witness {
+        canonical_receipt: canonical_string_mode(&tree, mode)?,
+        signature_hex: witness.signature_hex.clone(),
+        receipt_id: witness.receipt_id.clone(),
+        anchor_tx_hash: witness.anchor_tx_hash.clone(),
+        signature_commitment: witness.signature_commitment.clone(),
+    };
+    Ok(RedactedWitness { witness, redactions })
+}
+
+/// Check that `redacted` is `original` with exactly `redactions` applied (same salts, same
+/// order), so it commits to the same receipt. Fails with `ReceiptHashMismatch` over the
+/// sha256 of the redacted canonical forms otherwise.
+pub fn verify_redaction(
+    original: &Witness,
+    redacted: &Witness,
+    redactions: &[Redaction],
+    mode: CanonMode,
+) -> Result<(), VerifyError> {
+    let salts = redactions
+        .iter()
+        .map(|r| {
+            hex_array::<32>(&r.salt, &format!("redaction salt for `{}`", r.pointer))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let pointers: Vec<&str> = redactions.iter().map(|r| r.pointer.as_str()).collect();
+    let expected = redact_with_salts(original, &pointers, &salts, mode)?;
+    let mismatched_commitment = expected.redactions.iter().zip(redactions).any(|(e, r)| !ct_eq_bytes(e.commitment.as_bytes(), r.commitment.as_bytes()));
+    if mismatched_commitment || expected.witness.canonical_receipt != redacted.canonical_receipt {
+        return Err(VerifyError::ReceiptHashMismatch {
+            expected: sha256_hex(expected.witness.canonical_receipt.as_bytes()),
+            actual: sha256_hex(redacted.canonical_receipt.as_bytes()),
+        });
+    }
+    Ok(())
+}
+
+/// What `withhold_signature` took out of a witness: the signature and the salt of its
+/// commitment, for the prover to receive over its own secure channel. Keep it private.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "zeroize", derive(zeroize::Zeroize, zeroize::ZeroizeOnDrop))]
+pub struct SignatureOpening {
+    pub signature_hex: String,
+    pub salt: String,
+    pub commitment: String,
+}
+
+/// `sha256(salt || signature bytes)` as hex.
+pub fn signature_commitment(signature_hex: &str, salt: &[u8; 32]) -> Result<String, VerifyError> {
+    let sig = hex_bytes(signature_hex, false).map_err(|e| VerifyError::Hex(format!("signature_hex: {}", e)))?;
+    let mut preimage = salt.to_vec();
+    preimage.extend_from_slice(&sig);
+    Ok(sha256_hex(&preimage))
+}
+
+/// `witness` without its signature: `signature_hex` is emptied (and left out of the JSON) and
+/// `signature_commitment` set under a fresh random salt. The `receipt_sig` prover cannot prove
+/// such a witness; provers that can get the returned opening separately.
+pub fn withhold_signature(witness: &Witness) -> Result<(Witness, SignatureOpening), VerifyError> {
+    if witness.signature_hex.is_empty() {
+        return Err(VerifyError::Sig("witness signature is already withheld".into()));
+    }
+    let mut salt = [0u8; 32];
+    getrandom::getrandom(&mut salt).map_err(|e| VerifyError::Config(format!("signature salt: {}", e)))?;
+    let commitment = signature_commitment(&witness.signature_hex, &salt)?;
+    let withheld = Witness {
+        canonical_receipt: witness.canonical_receipt.clone(),
+        signature_hex: String::new(),
+        receipt_id: witness.receipt_id.clone(),
+        anchor_tx_hash: witness.anchor_tx_hash.clone(),
+        signature_commitment: Some(commitment.clone()),
+    };
+    let opening = SignatureOpening { signature_hex: witness.signature_hex.clone(), salt: hex::encode(salt), commitment };
+    Ok((withheld, opening))
+}
+
+/// Put a withheld signature back, after checking `opening` against the witness's
+/// `signature_commitment` (`Sig` if it does not match).
+pub fn restore_signature(witness: &Witness, opening: &SignatureOpening) -> Result<Witness, VerifyError> {
+    let Some(expected) = witness.signature_commitment.as_deref() else {
+        return Err(VerifyError::Sig("witness has no signature_commitment".into()));
+    };
+    let salt = hex_array::<32>(&opening.salt, "signature salt")?;
+    let commitment = signature_commitment(&opening.signature_hex, &salt)?;
+    if !ct_eq_bytes(commitment.as_bytes(), expected.as_bytes()) {
+        return Err(VerifyError::Sig("signature does not match the witness's signature_commitment".into()));
+    }
+    Ok(Witness {
+        canonical_receipt: witness.canonical_receipt.clone(),
+        signature_hex: opening.signature_hex.clone(),
+        receipt_id: witness.receipt_id.clone(),
+        anchor_tx_hash: witness.anchor_tx_hash.clone(),
+        signature_commitment: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "zeroize")]
+    use zeroize::{Zeroize, ZeroizeOnDrop};
+
+    fn witness() -> Witness {
+        Witness {
+            canonical_receipt: r#"{"args":{"q":"secret","n":1},"k":"v"}"#.into(),
+            signature_hex: format!("0x{}", "11".repeat(65)),
+            receipt_id: Some("rcpt_1".into()),
+            anchor_tx_hash: Some("0x22".into()),
+            signature_commitment: Some("33".repeat(32)),
+        }
+    }
+
+    fn flip_last_digit(hex: &str) -> String {
+        let (head, last) = hex.split_at(hex.len() - 1);
+        format!("{head}{}", if last == "0" { "1" } else { "0" })
+    }
+
+    #[test]
+    fn redaction_verifies_only_with_its_salts_and_commitments() {
+        let original = witness();
+        let redacted = redact_witness(&original, &["/args/q", "/k"]).unwrap();
+        assert!(!redacted.witness.canonical_receipt.contains("secret"));
+        assert!(redacted.witness.canonical_receipt.contains(REDACTED_KEY));
+        assert_ne!(redacted.redactions[0].salt, redacted.redactions[1].salt);
+        verify_redaction(&original, &redacted.witness, &redacted.redactions, CanonMode::Sorted).unwrap();
+
+        let mismatch = |redactions: &[Redaction], witness: &Witness| {
+            matches!(verify_redaction(&original, witness, redactions, CanonMode::Sorted), Err(VerifyError::ReceiptHashMismatch { .. }))
+        };
+        let mut salted = redacted.redactions.clone();
+        salted[0].salt = flip_last_digit(&salted[0].salt);
+        assert!(mismatch(&salted, &redacted.witness));
+        let mut committed = redacted.redactions.clone();
+        committed[1].commitment = flip_last_digit(&committed[1].commitment);
+        assert!(mismatch(&committed, &redacted.witness));
+        // A redacted witness with a different placeholder, or with a redaction left out.
+        let mut edited = redacted.witness.clone();
+        edited.canonical_receipt = edited.canonical_receipt.replace(&redacted.redactions[0].commitment, &committed[1].commitment);
+        assert!(mismatch(&redacted.redactions, &edited));
+        assert!(mismatch(&redacted.redactions[..1], &redacted.witness));
+
+        assert!(matches!(redact_witness(&original, &["/missing"]), Err(VerifyError::Serde(_))));
+        assert!(matches!(redact_witness(&original, &[""]), Err(VerifyError::Serde(_))));
+    }
+
+    #[test]
+    fn withheld_signature_is_restored_only_with_its_opening() {
+        let original = witness();
+        let (withheld, opening) = withhold_signature(&original).unwrap();
+        assert!(withheld.signature_hex.is_empty());
+        assert_eq!(withheld.signature_commitment.as_deref(), Some(opening.commitment.as_str()));
+        assert_eq!(withheld.canonical_receipt, original.canonical_receipt);
+        assert!(serde_json::to_value(&withheld).unwrap().get("signature_hex").is_none());
+        assert!(matches!(withhold_signature(&withheld), Err(VerifyError::Sig(_))));
+
+        let restored = restore_signature(&withheld, &opening).unwrap();
+        assert_eq!(restored.signature_hex, original.signature_hex);
+        assert!(restored.signature_commitment.is_none());
+
+        // Field by field: `ZeroizeOnDrop` rules out `..opening.clone()`.
+        let wrong_signature = SignatureOpening {
+            signature_hex: format!("0x{}", "12".repeat(65)),
+            salt: opening.salt.clone(),
+            commitment: opening.commitment.clone(),
+        };
+        let wrong_salt = SignatureOpening {
+            signature_hex: opening.signature_hex.clone(),
+            salt: flip_last_digit(&opening.salt),
+            commitment: opening.commitment.clone(),
+        };
+        // Another withholding of the same signature commits under a different salt.
+        let (_, other) = withhold_signature(&original).unwrap();
+        for wrong in [wrong_signature, wrong_salt, other] {
+            assert!(matches!(restore_signature(&withheld, &wrong), Err(VerifyError::Sig(_))));
+        }
+        assert!(matches!(restore_signature(&restored, &opening), Err(VerifyError::Sig(_))));
+    }
+
+    #[cfg(feature = "zeroize")]
+    fn assert_cleared(w: &Witness) {
+        assert!(w.canonical_receipt.is_empty() && w.signature_hex.is_empty());
+        assert!(w.receipt_id.is_none() && w.anchor_tx_hash.is_none() && w.signature_commitment.is_none());
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn zeroize_clears_every_field() {
+        let mut w = witness();
+        w.zeroize();
+        assert_cleared(&w);
+
+        let mut redacted = redact_with_salts(&witness(), &["/k"], &[[7; 32]], CanonMode::Sorted).unwrap();
+        assert_eq!(redacted.redactions.len(), 1);
+        let mut redaction = redacted.redactions[0].clone();
+        redacted.zeroize();
+        assert_cleared(&redacted.witness);
+        assert!(redacted.redactions.is_empty());
+        redaction.zeroize();
+        assert!(redaction.pointer.is_empty() && redaction.salt.is_empty() && redaction.commitment.is_empty());
+
+        let mut opening = SignatureOpening { signature_hex: "0x11".into(), salt: "22".into(), commitment: "33".into() };
+        opening.zeroize();
+        assert!(opening.signature_hex.is_empty() && opening.salt.is_empty() && opening.commitment.is_empty());
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn secrets_are_zeroized_on_drop() {
+        fn zeroized_on_drop<T: ZeroizeOnDrop>() {}
+        zeroized_on_drop::<Witness>();
+        zeroized_on_drop::<Redaction>();
+        zeroized_on_drop::<RedactedWitness>();
+        zeroized_on_drop::<SignatureOpening>();
+    }
+}
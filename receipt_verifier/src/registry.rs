@@ -0,0 +1,86 @@
+//! On-chain gateway allowlists (`rpc` feature).
+//!
+//! With `VerifyOptions::registry` set, every verification path asks the registry whether the
+//! recovered signer was an authorized gateway at the receipt's anchor block (the latest block
+//! for unanchored receipts), after the signature checks out. Stark gateways are looked up by
+//! the low 20 bytes of their public key felt.
+
+use crate::rpc::RpcClient;
+use crate::{keccak256, to_checksum_address, EthCall, ReceiptV1, VerifyError};
+use serde_json::json;
+use std::fmt;
+use std::sync::Arc;
+
+/// Source of truth for which gateway keys may sign receipts.
+pub trait GatewayRegistry: fmt::Debug + Send + Sync {
+    /// Whether `addr` was authorized at block `at_block` (`None`: the latest block).
+    fn is_authorized(&self, addr: [u8; 20], at_block: Option<u64>) -> Result<bool, VerifyError>;
+}
+
+/// Default registry view function: `isAuthorized(address) returns (bool)`.
+pub const DEFAULT_REGISTRY_FUNCTION: &str = "isAuthorized(address)";
+
+/// A registry contract queried with `eth_call` at the requested block.
+pub struct RpcGatewayRegistry {
+    rpc: Arc<dyn EthCall + Send + Sync>,
+    pub contract: [u8; 20],
+    /// `bytes4(keccak256(function))` of the view function; it takes one address and returns a bool.
+    selector: [u8; 4],
+}
+
+impl RpcGatewayRegistry {
+    /// Registry `contract` on the node at `url`, queried through `DEFAULT_REGISTRY_FUNCTION`.
+    pub fn new(url: impl Into<String>, contract: [u8; 20]) -> Self {
+        Self::with_function(url, contract, DEFAULT_REGISTRY_FUNCTION)
+    }
+
+    /// Query through `function` (a Solidity signature such as `isGateway(address)`) instead.
+    pub fn with_function(url: impl Into<String>, contract: [u8; 20], function: &str) -> Self {
+        Self::with_rpc(Arc::new(RpcClient::new(url)), contract, function)
+    }
+
+    /// Query through `function` over an existing client, e.g. a clone of the `RpcClient` the
+    /// application already shares.
+    pub fn with_rpc(rpc: Arc<dyn EthCall + Send + Sync>, contract: [u8; 20], function: &str) -> Self {
+        let selector = keccak256(function.as_bytes())[..4].try_into().expect("4 bytes");
+        Self { rpc, contract, selector }
+    }
+}
+
+impl fmt::Debug for RpcGatewayRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RpcGatewayRegistry")
+            .field("contract", &to_checksum_address(self.contract))
+            .field("selector", &hex::encode(self.selector))
+            .finish()
+    }
+}
+
+impl GatewayRegistry for RpcGatewayRegistry {
+    fn is_authorized(&self, addr: [u8; 20], at_block: Option<u64>) -> Result<bool, VerifyError> {
+        let mut data = self.selector.to_vec();
+        data.extend_from_slice(&[0u8; 12]);
+        data.extend_from_slice(&addr);
+        let block = at_block.map_or_else(|| "latest".to_string(), |b| format!("0x{:x}", b));
+        let params = json!([
+            { "to": format!("0x{}", hex::encode(self.contract)), "data": format!("0x{}", hex::encode(data)) },
+            block
+        ]);
+        let ret = crate::rpc::decode_data(&self.rpc.request("eth_call", params)?)?;
+        // A bool comes back as one ABI word; anything else means the wrong contract or function.
+        if ret.len() != 32 {
+            return Err(VerifyError::Rpc(format!("registry returned {} bytes, expected a bool", ret.len())));
+        }
+        Ok(ret.iter().any(|b| *b != 0))
+    }
+}
+
+/// `UnauthorizedGateway` unless `registry` lists `signer` at `receipt`'s anchor block.
+pub(crate) fn check_registry(registry: &dyn GatewayRegistry, signer: [u8; 20], receipt: &ReceiptV1) -> Result<(), VerifyError> {
+    let at_block = receipt.anchor_block_number();
+    if registry.is_authorized(signer, at_block)? {
+        return Ok(());
+    }
+    let at = at_block.map_or_else(|| "the latest block".to_string(), |b| format!("block {}", b));
+    Err(VerifyError::UnauthorizedGateway(format!("{} is not in the registry at {}", to_checksum_address(signer), at)))
+}
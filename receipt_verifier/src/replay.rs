@@ -0,0 +1,293 @@
+//! Replay protection: remember which receipts (or signer nonces) were already accepted.
+
+use crate::VerifyError;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// What identifies a receipt for replay purposes.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SeenKey {
+    /// Receipts without a `nonce` are keyed on their receipt hash.
+    ReceiptHash(String),
+    /// Receipts with a `nonce` are keyed on `(signer, nonce)`.
+    SignerNonce([u8; 20], u64),
+}
+
+impl fmt::Display for SeenKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SeenKey::ReceiptHash(h) => write!(f, "receipt:{}", h),
+            SeenKey::SignerNonce(addr, n) => write!(f, "nonce:0x{}:{}", hex::encode(addr), n),
+        }
+    }
+}
+
+impl std::str::FromStr for SeenKey {
+    type Err = VerifyError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad = || VerifyError::Serde(format!("bad seen-store entry: {}", s));
+        if let Some(h) = s.strip_prefix("receipt:") {
+            return Ok(SeenKey::ReceiptHash(h.to_string()));
+        }
+        let rest = s.strip_prefix("nonce:").ok_or_else(bad)?;
+        let (addr, n) = rest.rsplit_once(':').ok_or_else(bad)?;
+        let bytes = hex::decode(addr.trim_start_matches("0x")).map_err(|_| bad())?;
+        let addr: [u8; 20] = bytes.try_into().map_err(|_| bad())?;
+        Ok(SeenKey::SignerNonce(addr, n.parse().map_err(|_| bad())?))
+    }
+}
+
+/// Storage for accepted receipts. Methods take `&self` so one store can be shared across
+/// threads; `insert` must be atomic so concurrent duplicates cannot both succeed.
+pub trait SeenStore: Send + Sync + fmt::Debug {
+    /// Record `key`; returns `false` if it had already been seen.
+    fn insert(&self, key: &SeenKey) -> Result<bool, VerifyError>;
+    fn contains(&self, key: &SeenKey) -> Result<bool, VerifyError>;
+    /// Highest nonce recorded for `signer`.
+    fn max_nonce(&self, signer: &[u8; 20]) -> Result<Option<u64>, VerifyError>;
+
+    /// Record `(signer, nonce)` only if `nonce` is above the highest nonce recorded for
+    /// `signer`; returns that nonce, recording nothing, when it is not. The default checks
+    /// `max_nonce` and then calls `insert`; stores override it to do both atomically, so two
+    /// concurrent receipts cannot both pass against the same maximum.
+    fn insert_above_max(&self, signer: &[u8; 20], nonce: u64) -> Result<Option<u64>, VerifyError> {
+        match self.max_nonce(signer)? {
+            Some(max) if nonce <= max => Ok(Some(max)),
+            _ => self.insert(&SeenKey::SignerNonce(*signer, nonce)).map(|_| None),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct SeenSet {
+    keys: BTreeMap<SeenKey, ()>,
+    max_nonce: BTreeMap<[u8; 20], u64>,
+}
+
+impl SeenSet {
+    fn above_max(&self, signer: &[u8; 20], nonce: u64) -> Option<u64> {
+        self.max_nonce.get(signer).copied().filter(|max| nonce <= *max)
+    }
+
+    fn insert(&mut self, key: &SeenKey) -> bool {
+        if let SeenKey::SignerNonce(addr, n) = key {
+            let max = self.max_nonce.entry(*addr).or_insert(*n);
+            *max = (*max).max(*n);
+        }
+        self.keys.insert(key.clone(), ()).is_none()
+    }
+}
+
+/// Process-local store.
+#[derive(Debug, Default)]
+pub struct MemorySeenStore {
+    inner: Mutex<SeenSet>,
+}
+
+impl MemorySeenStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SeenStore for MemorySeenStore {
+    fn insert(&self, key: &SeenKey) -> Result<bool, VerifyError> {
+        Ok(self.inner.lock().expect("seen store poisoned").insert(key))
+    }
+
+    fn contains(&self, key: &SeenKey) -> Result<bool, VerifyError> {
+        Ok(self.inner.lock().expect("seen store poisoned").keys.contains_key(key))
+    }
+
+    fn max_nonce(&self, signer: &[u8; 20]) -> Result<Option<u64>, VerifyError> {
+        Ok(self.inner.lock().expect("seen store poisoned").max_nonce.get(signer).copied())
+    }
+
+    fn insert_above_max(&self, signer: &[u8; 20], nonce: u64) -> Result<Option<u64>, VerifyError> {
+        let mut set = self.inner.lock().expect("seen store poisoned");
+        if let Some(max) = set.above_max(signer, nonce) {
+            return Ok(Some(max));
+        }
+        set.insert(&SeenKey::SignerNonce(*signer, nonce));
+        Ok(None)
+    }
+}
+
+/// Append-only text file, one key per line, loaded fully on open. The file is held under an
+/// exclusive lock while the store is open, since keys appended by another process would not
+/// be seen; a second `open` of the same file, in this or another process, fails with `Io`.
+#[derive(Debug)]
+pub struct FileSeenStore {
+    path: PathBuf,
+    inner: Mutex<(SeenSet, File)>,
+}
+
+impl FileSeenStore {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, VerifyError> {
+        let path = path.as_ref().to_path_buf();
+        let io = |e: std::io::Error| VerifyError::Io(format!("seen store {}: {}", path.display(), e));
+        let file = OpenOptions::new().create(true).read(true).append(true).open(&path).map_err(io)?;
+        file.try_lock().map_err(|e| match e {
+            TryLockError::WouldBlock => {
+                VerifyError::Io(format!("seen store {} is in use by another verifier", path.display()))
+            }
+            TryLockError::Error(e) => io(e),
+        })?;
+        let mut set = SeenSet::default();
+        for line in BufReader::new(&file).lines() {
+            let line = line.map_err(io)?;
+            if !line.trim().is_empty() {
+                set.insert(&line.trim().parse()?);
+            }
+        }
+        Ok(Self { path, inner: Mutex::new((set, file)) })
+    }
+
+    fn append(&self, file: &mut File, key: &SeenKey) -> Result<(), VerifyError> {
+        writeln!(file, "{}", key)
+            .and_then(|_| file.flush())
+            .map_err(|e| VerifyError::Io(format!("seen store {}: {}", self.path.display(), e)))
+    }
+}
+
+impl SeenStore for FileSeenStore {
+    fn insert(&self, key: &SeenKey) -> Result<bool, VerifyError> {
+        let mut guard = self.inner.lock().expect("seen store poisoned");
+        let (set, file) = &mut *guard;
+        if !set.insert(key) {
+            return Ok(false);
+        }
+        self.append(file, key)?;
+        Ok(true)
+    }
+
+    fn contains(&self, key: &SeenKey) -> Result<bool, VerifyError> {
+        Ok(self.inner.lock().expect("seen store poisoned").0.keys.contains_key(key))
+    }
+
+    fn max_nonce(&self, signer: &[u8; 20]) -> Result<Option<u64>, VerifyError> {
+        Ok(self.inner.lock().expect("seen store poisoned").0.max_nonce.get(signer).copied())
+    }
+
+    fn insert_above_max(&self, signer: &[u8; 20], nonce: u64) -> Result<Option<u64>, VerifyError> {
+        let mut guard = self.inner.lock().expect("seen store poisoned");
+        let (set, file) = &mut *guard;
+        if let Some(max) = set.above_max(signer, nonce) {
+            return Ok(Some(max));
+        }
+        let key = SeenKey::SignerNonce(*signer, nonce);
+        set.insert(&key);
+        self.append(file, &key)?;
+        Ok(None)
+    }
+}
+
+/// Replay policy applied once a receipt has passed every other check.
+#[derive(Debug, Clone)]
+pub struct ReplayGuard {
+    pub store: Arc<dyn SeenStore>,
+    /// Reject receipts whose nonce is below this (e.g. after a key rotation).
+    pub min_nonce: Option<u64>,
+    /// Require each signer's nonces to strictly increase. Only meaningful when receipts are
+    /// verified in issuance order, not in parallel batches.
+    pub monotonic: bool,
+}
+
+impl ReplayGuard {
+    pub fn new(store: Arc<dyn SeenStore>) -> Self {
+        Self { store, min_nonce: None, monotonic: false }
+    }
+
+    /// Check and record the receipt. Receipts with a nonce are keyed on `(signer, nonce)`,
+    /// others on their receipt hash.
+    pub fn check(&self, rcpt_hash: &str, signer: [u8; 20], nonce: Option<u64>) -> Result<(), VerifyError> {
+        let key = match nonce {
+            Some(n) => {
+                if let Some(min) = self.min_nonce {
+                    if n < min {
+                        return Err(VerifyError::ReplayDetected(format!("nonce {} below minimum {}", n, min)));
+                    }
+                }
+                if self.monotonic {
+                    return match self.store.insert_above_max(&signer, n)? {
+                        None => Ok(()),
+                        Some(max) => Err(VerifyError::ReplayDetected(format!(
+                            "nonce {} not above last accepted {} for 0x{}",
+                            n,
+                            max,
+                            hex::encode(signer)
+                        ))),
+                    };
+                }
+                SeenKey::SignerNonce(signer, n)
+            }
+            None => SeenKey::ReceiptHash(rcpt_hash.to_string()),
+        };
+        if !self.store.insert(&key)? {
+            return Err(VerifyError::ReplayDetected(format!("{} already seen", key)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("receipt_verifier_{}_{}.seen", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn monotonic_nonces_are_enforced_and_recorded() {
+        let guard = ReplayGuard { monotonic: true, ..ReplayGuard::new(Arc::new(MemorySeenStore::new())) };
+        let signer = [0x11; 20];
+        guard.check("aa", signer, Some(3)).unwrap();
+        assert!(matches!(guard.check("bb", signer, Some(3)), Err(VerifyError::ReplayDetected(_))));
+        assert!(matches!(guard.check("bb", signer, Some(2)), Err(VerifyError::ReplayDetected(_))));
+        guard.check("cc", signer, Some(4)).unwrap();
+        guard.check("dd", [0x22; 20], Some(1)).unwrap();
+        assert_eq!(guard.store.max_nonce(&signer).unwrap(), Some(4));
+    }
+
+    #[test]
+    fn file_store_survives_reopen() {
+        let path = temp_path("reopen");
+        let signer = [0x11; 20];
+        {
+            let store = FileSeenStore::open(&path).unwrap();
+            assert_eq!(store.insert_above_max(&signer, 5).unwrap(), None);
+            assert!(store.insert(&SeenKey::ReceiptHash("aa".into())).unwrap());
+        }
+        let store = FileSeenStore::open(&path).unwrap();
+        assert_eq!(store.insert_above_max(&signer, 5).unwrap(), Some(5));
+        assert!(!store.insert(&SeenKey::ReceiptHash("aa".into())).unwrap());
+        drop(store);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn file_store_cannot_be_shared() {
+        let path = temp_path("shared");
+        let store = FileSeenStore::open(&path).unwrap();
+        let err = FileSeenStore::open(&path).unwrap_err();
+        assert!(matches!(err, VerifyError::Io(_)));
+        assert_eq!(err.exit_code(), 5);
+        drop(store);
+        FileSeenStore::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn file_store_errors_are_io() {
+        let err = FileSeenStore::open(std::env::temp_dir()).unwrap_err();
+        assert!(matches!(err, VerifyError::Io(_)));
+        assert_eq!(err.exit_code(), 5);
+    }
+}
@@ -0,0 +1,294 @@
+//! Audit report of one verification (`--report`): every intermediate value and the outcome of
+//! every check, produced even when verification fails.
+//!
+//! Unlike `verify_receipt_full`, a failed check does not stop the run. Each later check still
+//! runs if its inputs exist. Checks whose inputs could not be produced (e.g. the policy hash
+//! check when the receipt does not parse) are `skipped`, with the reason. The replay check
+//! runs only when everything before it passed, so a failing receipt is never recorded.
+//! Covers EOA signatures (personal_sign and EIP-712); EIP-1271, Stark and co-signed receipts
+//! are not reported.
+
+use crate::{
+    canonical_and_hash, check_allowlist, check_chain_id, check_consent_hash, check_embedded_sig, check_expected_receipt_hash,
+    check_policy_hash, check_self_gateway, check_time_window, ct, digest_of, parse_receipt, receipt_id_of, recover_expecting, sha256_hex,
+    to_checksum_address, DigestMode, HashHex, HashScheme, VerifiedReceipt, VerifyError, VerifyOptions,
+};
+use serde::{Serialize, Serializer};
+use serde_json::json;
+
+/// Outcome of one check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Passed,
+    Failed,
+    /// Not applicable under the options, or its inputs could not be produced.
+    Skipped,
+}
+
+/// One check of a `VerificationReport`.
+#[derive(Debug, Serialize)]
+pub struct CheckOutcome {
+    pub check: &'static str,
+    pub status: CheckStatus,
+    /// Why the check was skipped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Serialized as `{ code, numeric_code, message }`.
+    #[serde(serialize_with = "error_json", skip_serializing_if = "Option::is_none")]
+    pub error: Option<VerifyError>,
+}
+
+fn error_json<S: Serializer>(err: &Option<VerifyError>, s: S) -> Result<S::Ok, S::Error> {
+    err.as_ref()
+        .map(|e| json!({ "code": e.code(), "numeric_code": e.numeric_code(), "message": e.to_string() }))
+        .serialize(s)
+}
+
+/// Every stage of a verification and its outcome; `ok` when no check failed.
+#[derive(Debug, Serialize)]
+pub struct VerificationReport {
+    pub ok: bool,
+    pub receipt_id: Option<String>,
+    /// Top-level fields left out of the signed form (none with `sign_path`).
+    pub excluded_fields: Vec<String>,
+    pub sign_path: Option<String>,
+    /// Length of the canonical signed form, in bytes.
+    pub canonical_bytes: Option<usize>,
+    /// sha256 of the canonical signed form, whatever `hash_scheme` is.
+    pub canonical_sha256: Option<HashHex>,
+    /// Receipt hash under `hash_scheme`.
+    pub receipt_hash: Option<HashHex>,
+    pub hash_scheme: HashScheme,
+    /// personal_sign digest mode; `None` for EIP-712.
+    pub digest_mode: Option<DigestMode>,
+    /// Digest the signature was recovered over (EIP-191 or EIP-712).
+    pub signing_digest: Option<HashHex>,
+    pub recovered_address: Option<String>,
+    pub chain_id: Option<u64>,
+    pub checks: Vec<CheckOutcome>,
+    /// The verified receipt, when every check passed.
+    #[serde(skip)]
+    pub verified: Option<VerifiedReceipt>,
+}
+
+impl VerificationReport {
+    /// Errors of the failed checks, in check order.
+    pub fn errors(&self) -> impl Iterator<Item = &VerifyError> {
+        self.checks.iter().filter_map(|c| c.error.as_ref())
+    }
+
+    /// Errors of the failed checks, in check order (empty when `ok`).
+    pub fn into_errors(self) -> Vec<VerifyError> {
+        self.checks.into_iter().filter_map(|c| c.error).collect()
+    }
+
+    /// The verified receipt, or the first failed check's error (what `verify_receipt_full`
+    /// would have returned).
+    pub fn into_result(self) -> Result<VerifiedReceipt, VerifyError> {
+        if let Some(verified) = self.verified {
+            return Ok(verified);
+        }
+        let first = self.checks.into_iter().find_map(|c| c.error);
+        Err(first.unwrap_or_else(|| VerifyError::Config("verification report has no verified receipt".into())))
+    }
+
+    fn record(&mut self, check: &'static str, res: Result<(), VerifyError>) {
+        let (status, error) = match res {
+            Ok(()) => (CheckStatus::Passed, None),
+            Err(e) => (CheckStatus::Failed, Some(e)),
+        };
+        self.checks.push(CheckOutcome { check, status, reason: None, error });
+    }
+
+    fn skip(&mut self, check: &'static str, reason: &str) {
+        self.checks.push(CheckOutcome { check, status: CheckStatus::Skipped, reason: Some(reason.to_string()), error: None });
+    }
+}
+
+/// `verify_receipt_with` that runs every check instead of stopping at the first failure, and
+/// returns all failures in check order (e.g. a bad signature and a policy mismatch together).
+/// Meant for debugging receipts; hot paths keep the fail-fast `verify_receipt_*` functions.
+pub fn verify_receipt_all(
+    receipt_val: &serde_json::Value,
+    receipt_sig_hex: &str,
+    expected_gateway: Option<[u8; 20]>,
+    expected_policy_hash: &str,
+    expected_consent_hash: &str,
+    opts: &VerifyOptions,
+) -> Result<(), Vec<VerifyError>> {
+    let gateways = expected_gateway.as_slice();
+    let report = verify_receipt_report(receipt_val, receipt_sig_hex, gateways, expected_policy_hash, expected_consent_hash, opts);
+    if report.ok {
+        return Ok(());
+    }
+    Err(report.into_errors())
+}
+
+/// Run every check of `verify_receipt_full` and report each stage. `expected_gateways` may
+/// be empty to accept any signer. The report is returned in every case; use
+/// `VerificationReport::into_result` for the verification outcome.
+pub fn verify_receipt_report(
+    receipt_val: &serde_json::Value,
+    receipt_sig_hex: &str,
+    expected_gateways: &[[u8; 20]],
+    expected_policy_hash: &str,
+    expected_consent_hash: &str,
+    opts: &VerifyOptions,
+) -> VerificationReport {
+    let _span = tracing::info_span!("verify_report", receipt_id = receipt_id_of(receipt_val)).entered();
+    let mut report = VerificationReport {
+        ok: false,
+        receipt_id: receipt_val.get("receipt_id").and_then(|v| v.as_str()).map(str::to_string),
+        excluded_fields: match opts.sign_path {
+            Some(_) => Vec::new(),
+            None => opts.excluded().into_iter().map(str::to_string).collect(),
+        },
+        sign_path: opts.sign_path.clone(),
+        canonical_bytes: None,
+        canonical_sha256: None,
+        receipt_hash: None,
+        hash_scheme: opts.hash.clone(),
+        digest_mode: opts.personal_digest_mode(),
+        signing_digest: None,
+        recovered_address: None,
+        chain_id: None,
+        checks: Vec::new(),
+        verified: None,
+    };
+
+    let receipt = parse_receipt(receipt_val, opts);
+    let receipt = match receipt {
+        Ok(receipt) => {
+            report.record("receipt_fields", Ok(()));
+            Some(receipt)
+        }
+        Err(e) => {
+            report.record("receipt_fields", Err(e));
+            None
+        }
+    };
+    report.record("embedded_sig", check_embedded_sig(receipt_val, receipt_sig_hex, opts));
+
+    let canonical = match canonical_and_hash(receipt_val, opts) {
+        Ok((canonical, rcpt_hash)) => {
+            report.record("canonicalize", Ok(()));
+            report.canonical_bytes = Some(canonical.len());
+            report.canonical_sha256 = Some(HashHex::normalize(&sha256_hex(canonical.as_bytes())));
+            report.receipt_hash = Some(HashHex::normalize(&rcpt_hash));
+            Some((canonical, rcpt_hash))
+        }
+        Err(e) => {
+            report.record("canonicalize", Err(e));
+            None
+        }
+    };
+    match (&canonical, &opts.expected_receipt_hash) {
+        (_, None) => report.skip("expected_receipt_hash", "no expected receipt hash"),
+        (None, Some(_)) => report.skip("expected_receipt_hash", "canonicalization failed"),
+        (Some((_, rcpt_hash)), Some(_)) => {
+            report.record("expected_receipt_hash", check_expected_receipt_hash(rcpt_hash, opts));
+        }
+    }
+
+    let recovered = match &canonical {
+        None => {
+            report.skip("signature", "canonicalization failed");
+            None
+        }
+        Some((canonical, _)) => {
+            let recovered = digest_of(receipt_val, canonical, opts).and_then(|digest| {
+                report.signing_digest = Some(HashHex::from_bytes(&digest));
+                recover_expecting(receipt_sig_hex, digest, Some(expected_gateways).filter(|g| !g.is_empty()), opts)
+            });
+            match recovered {
+                Ok((addr, chain_id)) => {
+                    report.record("signature", Ok(()));
+                    report.recovered_address = Some(to_checksum_address(addr));
+                    report.chain_id = chain_id;
+                    Some((addr, chain_id))
+                }
+                Err(e) => {
+                    report.record("signature", Err(e));
+                    None
+                }
+            }
+        }
+    };
+    let signer = recovered.map(|(addr, _)| addr);
+
+    match (recovered, opts.expected_chain_id) {
+        (_, None) => report.skip("chain_id", "no expected chain id"),
+        (None, Some(_)) => report.skip("chain_id", "signature recovery failed"),
+        (Some((_, chain_id)), Some(_)) => {
+            report.record("chain_id", check_chain_id(chain_id, opts));
+        }
+    }
+    match signer {
+        _ if expected_gateways.is_empty() => report.skip("gateway", "any signer accepted"),
+        None => report.skip("gateway", "signature recovery failed"),
+        Some(addr) => {
+            let matched = ct::ct_position(expected_gateways, &addr)
+                .map(|_| ())
+                .ok_or_else(|| VerifyError::AddressMismatch { recovered: to_checksum_address(addr) });
+            report.record("gateway", matched);
+        }
+    }
+
+    match &receipt {
+        Some(receipt) => {
+            report.record("policy_hash", check_policy_hash(receipt, expected_policy_hash));
+            report.record("consent_hash", check_consent_hash(receipt, expected_consent_hash));
+            report.record("time_window", check_time_window(receipt, opts));
+        }
+        None => {
+            for check in ["policy_hash", "consent_hash", "time_window"] {
+                report.skip(check, "receipt fields invalid");
+            }
+        }
+    }
+
+    match signer {
+        _ if opts.gateway_allowlist.is_none() => report.skip("allowlist", "no gateway allowlist"),
+        None => report.skip("allowlist", "signature recovery failed"),
+        Some(addr) => {
+            report.record("allowlist", check_allowlist(addr, opts));
+        }
+    }
+    match (signer, &receipt) {
+        _ if !opts.check_self_gateway => report.skip("self_gateway", "not requested"),
+        (Some(addr), Some(receipt)) => report.record("self_gateway", check_self_gateway(receipt, addr, opts)),
+        _ => report.skip("self_gateway", "signer or receipt unavailable"),
+    }
+    #[cfg(feature = "rpc")]
+    match (&opts.registry, signer, &receipt) {
+        (None, _, _) => report.skip("registry", "no gateway registry"),
+        (Some(registry), Some(addr), Some(receipt)) => {
+            report.record("registry", crate::registry::check_registry(registry.as_ref(), addr, receipt));
+        }
+        (Some(_), _, _) => report.skip("registry", "signer or receipt unavailable"),
+    }
+
+    let passed = report.checks.iter().all(|c| c.status != CheckStatus::Failed);
+    match (&opts.replay, passed, &receipt, &canonical, signer) {
+        (None, ..) => report.skip("replay", "no replay guard"),
+        (Some(guard), true, Some(receipt), Some((_, rcpt_hash)), Some(addr)) => {
+            report.record("replay", guard.check(rcpt_hash, addr, receipt.nonce));
+        }
+        (Some(_), ..) => report.skip("replay", "earlier checks failed; receipt not recorded"),
+    }
+
+    report.ok = report.checks.iter().all(|c| c.status != CheckStatus::Failed);
+    if let (true, Some(receipt), Some((canonical, receipt_hash)), Some((signer, chain_id))) = (report.ok, receipt, canonical, recovered) {
+        report.verified = Some(VerifiedReceipt {
+            receipt,
+            canonical,
+            receipt_hash,
+            signer,
+            chain_id,
+            hash_scheme: opts.hash.clone(),
+            digest_mode: opts.personal_digest_mode(),
+        });
+    }
+    report
+}
@@ -0,0 +1,224 @@
+//! JSON-RPC transport over HTTP for the on-chain checks (`rpc` feature).
+
+use crate::{normalize_hex_even, parse_hex_strict, EthCall, VerifyError};
+use hex::FromHex;
+use serde_json::json;
+use std::fmt;
+use std::thread;
+use std::time::Duration;
+
+/// Blocking JSON-RPC client for an Ethereum-compatible node, with a connection pool, a
+/// per-request timeout and retries on 5xx responses and transport errors. Clones share the
+/// pool: build one client per node and reuse it (or clones of it) across requests and threads
+/// instead of creating one per call.
+#[derive(Clone)]
+pub struct RpcClient {
+    url: String,
+    client: reqwest::blocking::Client,
+    /// Limit for each attempt (default 10 s).
+    pub timeout: Duration,
+    /// Attempts after the first on a 5xx response, timeout or connection error (default 2).
+    /// JSON-RPC errors and other HTTP statuses are not retried.
+    pub retries: u32,
+    /// Delay before the first retry, doubled for each further one (default 200 ms).
+    pub backoff: Duration,
+}
+
+/// `RpcClient` under its original name.
+pub type HttpEthCall = RpcClient;
+
+impl RpcClient {
+    pub fn new(url: impl Into<String>) -> Self {
+        let client = reqwest::blocking::Client::builder()
+            .pool_idle_timeout(Duration::from_secs(90))
+            .build()
+            .unwrap_or_else(|_| reqwest::blocking::Client::new());
+        Self::with_client(url, client)
+    }
+
+    /// Use an existing reqwest client, sharing its pool with the rest of the application.
+    pub fn with_client(url: impl Into<String>, client: reqwest::blocking::Client) -> Self {
+        Self { url: url.into(), client, timeout: Duration::from_secs(10), retries: 2, backoff: Duration::from_millis(200) }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Send one JSON-RPC request and return its `result`.
+    pub fn request(&self, method: &str, params: serde_json::Value) -> Result<serde_json::Value, VerifyError> {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let mut delay = self.backoff;
+        let mut attempt = 0;
+        let resp: serde_json::Value = loop {
+            let sent = self.client.post(&self.url).timeout(self.timeout).json(&body).send();
+            let retryable = match &sent {
+                Ok(r) => r.status().is_server_error(),
+                Err(e) => e.is_timeout() || e.is_connect(),
+            };
+            if retryable && attempt < self.retries {
+                attempt += 1;
+                tracing::debug!(method, attempt, "retrying rpc request");
+                thread::sleep(delay);
+                delay *= 2;
+                continue;
+            }
+            break sent
+                .and_then(|r| r.error_for_status())
+                .and_then(|r| r.json())
+                .map_err(|e| VerifyError::Rpc(format!("{}: {}", method, e)))?;
+        };
+        if let Some(err) = resp.get("error") {
+            return Err(VerifyError::Rpc(format!("{}: {}", method, err)));
+        }
+        resp.get("result")
+            .cloned()
+            .ok_or_else(|| VerifyError::Rpc(format!("{}: missing result", method)))
+    }
+}
+
+impl fmt::Debug for RpcClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RpcClient")
+            .field("url", &self.url)
+            .field("timeout", &self.timeout)
+            .field("retries", &self.retries)
+            .finish()
+    }
+}
+
+pub(crate) fn decode_data(v: &serde_json::Value) -> Result<Vec<u8>, VerifyError> {
+    let s = v.as_str().ok_or_else(|| VerifyError::Rpc("expected hex string result".into()))?;
+    Vec::from_hex(normalize_hex_even(s)).map_err(|e| VerifyError::Hex(e.to_string()))
+}
+
+fn is_revert(err: &VerifyError) -> bool {
+    matches!(err, VerifyError::Rpc(msg) if msg.contains("revert"))
+}
+
+impl EthCall for RpcClient {
+    fn call(&self, to: [u8; 20], data: &[u8]) -> Result<Vec<u8>, VerifyError> {
+        let params = json!([
+            { "to": format!("0x{}", hex::encode(to)), "data": format!("0x{}", hex::encode(data)) },
+            "latest"
+        ]);
+        match self.request("eth_call", params) {
+            Ok(v) => decode_data(&v),
+            Err(e) if is_revert(&e) => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn code(&self, addr: [u8; 20]) -> Result<Vec<u8>, VerifyError> {
+        decode_data(&self.request("eth_getCode", json!([format!("0x{}", hex::encode(addr)), "latest"]))?)
+    }
+
+    fn request(&self, method: &str, params: serde_json::Value) -> Result<serde_json::Value, VerifyError> {
+        RpcClient::request(self, method, params)
+    }
+}
+
+/// On-chain state of a receipt's anchoring transaction.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AnchorStatus {
+    pub tx_hash: String,
+    pub block_number: u64,
+    /// `latest - block_number + 1`; 1 means the tx is in the latest block.
+    pub confirmations: u64,
+}
+
+/// Optional expectations checked by `verify_anchor_with`.
+#[derive(Debug, Clone, Default)]
+pub struct AnchorExpect {
+    /// Anchoring contract the tx must be sent to.
+    pub contract: Option<[u8; 20]>,
+    /// `topics[0]` of the anchoring event, which must be emitted by the tx (by `contract`, if set).
+    pub event_topic: Option<[u8; 32]>,
+    /// Receipt hash the event must carry, as an indexed topic or a word of its data.
+    pub receipt_hash: Option<[u8; 32]>,
+    pub min_confirmations: u64,
+}
+
+/// Check that `anchor.l2_tx.tx_hash` exists on-chain and succeeded.
+pub fn verify_anchor(anchor: &serde_json::Value, rpc_url: &str) -> Result<AnchorStatus, VerifyError> {
+    verify_anchor_with(&RpcClient::new(rpc_url), anchor, &AnchorExpect::default())
+}
+
+/// `verify_anchor` with contract/event/confirmation expectations, over any `EthCall` client
+/// that implements `request` (such as a shared `RpcClient`).
+pub fn verify_anchor_with(
+    rpc: &dyn EthCall,
+    anchor: &serde_json::Value,
+    expect: &AnchorExpect,
+) -> Result<AnchorStatus, VerifyError> {
+    let tx_hash = anchor
+        .pointer("/l2_tx/tx_hash")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| VerifyError::Anchor("missing l2_tx.tx_hash".into()))?
+        .to_string();
+    let receipt = rpc.request("eth_getTransactionReceipt", json!([tx_hash]))?;
+    if receipt.is_null() {
+        return Err(VerifyError::Anchor(format!("{} not found or still pending", tx_hash)));
+    }
+    if receipt.get("status").and_then(|v| v.as_str()) != Some("0x1") {
+        return Err(VerifyError::Anchor(format!("{} reverted", tx_hash)));
+    }
+    if let Some(contract) = expect.contract {
+        let to = receipt.get("to").and_then(|v| v.as_str()).map(decode_addr).transpose()?;
+        if to != Some(contract) {
+            return Err(VerifyError::Anchor(format!("{} was not sent to 0x{}", tx_hash, hex::encode(contract))));
+        }
+    }
+    if expect.event_topic.is_some() || expect.receipt_hash.is_some() {
+        let logs = receipt.get("logs").and_then(|v| v.as_array()).cloned().unwrap_or_default();
+        if !logs.iter().any(|log| log_matches(log, expect)) {
+            return Err(VerifyError::Anchor(format!("{} did not emit the expected anchor event", tx_hash)));
+        }
+    }
+    let block_number = decode_quantity(receipt.get("blockNumber"))?;
+    let latest = decode_quantity(Some(&rpc.request("eth_blockNumber", json!([]))?))?;
+    let confirmations = latest.saturating_sub(block_number) + 1;
+    if confirmations < expect.min_confirmations {
+        return Err(VerifyError::Anchor(format!(
+            "{} has {} confirmations, need {}",
+            tx_hash, confirmations, expect.min_confirmations
+        )));
+    }
+    Ok(AnchorStatus { tx_hash, block_number, confirmations })
+}
+
+fn log_matches(log: &serde_json::Value, expect: &AnchorExpect) -> bool {
+    if let Some(contract) = expect.contract {
+        let addr = log.get("address").and_then(|v| v.as_str()).map(decode_addr);
+        if !matches!(addr, Some(Ok(a)) if a == contract) {
+            return false;
+        }
+    }
+    let topics: Vec<Vec<u8>> = log
+        .get("topics")
+        .and_then(|v| v.as_array())
+        .map(|ts| ts.iter().filter_map(|t| decode_data(t).ok()).collect())
+        .unwrap_or_default();
+    if let Some(topic) = expect.event_topic {
+        if topics.first().map(Vec::as_slice) != Some(&topic[..]) {
+            return false;
+        }
+    }
+    match expect.receipt_hash {
+        Some(h) => {
+            let data = log.get("data").and_then(|d| decode_data(d).ok()).unwrap_or_default();
+            topics.iter().skip(1).any(|t| t[..] == h) || data.chunks(32).any(|w| w == h)
+        }
+        None => true,
+    }
+}
+
+fn decode_addr(s: &str) -> Result<[u8; 20], VerifyError> {
+    let bytes = parse_hex_strict(s)?;
+    bytes.try_into().map_err(|_| VerifyError::Rpc(format!("bad address {}", s)))
+}
+
+fn decode_quantity(v: Option<&serde_json::Value>) -> Result<u64, VerifyError> {
+    let s = v.and_then(|v| v.as_str()).ok_or_else(|| VerifyError::Rpc("expected hex quantity".into()))?;
+    u64::from_str_radix(s.trim_start_matches("0x"), 16).map_err(|e| VerifyError::Rpc(format!("bad quantity {}: {}", s, e)))
+}
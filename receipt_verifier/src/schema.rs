@@ -0,0 +1,47 @@
+//! JSON Schema validation of receipts before hashing (`schema` feature).
+
+use crate::VerifyError;
+
+/// Bundled schema for `ReceiptV1`: the typed fields plus hex checks on hashes and signatures.
+/// Unknown keys are allowed, as in `ReceiptV1::extra`.
+pub const RECEIPT_V1_SCHEMA: &str = include_str!("../schemas/receipt_v1.schema.json");
+
+/// A compiled schema, reusable across receipts (e.g. in `VerifyOptions::schema`).
+#[derive(Debug)]
+pub struct ReceiptSchema(jsonschema::Validator);
+
+impl ReceiptSchema {
+    pub fn new(schema: &serde_json::Value) -> Result<Self, VerifyError> {
+        jsonschema::validator_for(schema)
+            .map(Self)
+            .map_err(|e| VerifyError::Config(format!("invalid JSON schema: {}", e)))
+    }
+
+    /// The bundled `RECEIPT_V1_SCHEMA`.
+    pub fn receipt_v1() -> Self {
+        let schema = serde_json::from_str(RECEIPT_V1_SCHEMA).expect("bundled schema is valid JSON");
+        Self::new(&schema).expect("bundled schema compiles")
+    }
+
+    /// Every violation, as `<instance path>: <message>`, in `VerifyError::SchemaInvalid`.
+    pub fn validate(&self, val: &serde_json::Value) -> Result<(), VerifyError> {
+        let errors: Vec<String> = self
+            .0
+            .iter_errors(val)
+            .map(|e| {
+                let path = e.instance_path.to_string();
+                format!("{}: {}", if path.is_empty() { "/" } else { &path }, e)
+            })
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(VerifyError::SchemaInvalid(errors))
+        }
+    }
+}
+
+/// Validate `val` against a JSON Schema document.
+pub fn validate_schema(val: &serde_json::Value, schema: &serde_json::Value) -> Result<(), VerifyError> {
+    ReceiptSchema::new(schema)?.validate(val)
+}
@@ -0,0 +1,85 @@
+//! Receipt signing for tests and fixtures (`signing` feature): produces the signature a gateway
+//! would, so `sign_receipt` -> `verify_receipt_with` round-trips without external tooling.
+//! Not meant for production keys; the key is taken as hex and not zeroized.
+
+use crate::{address_of_key, hex_array, signing_digest, VerifyError, VerifyOptions};
+use k256::ecdsa::SigningKey;
+
+fn signing_key(privkey_hex: &str) -> Result<SigningKey, VerifyError> {
+    let bytes: [u8; 32] = hex_array(privkey_hex, "private key")?;
+    SigningKey::from_slice(&bytes).map_err(|e| VerifyError::Sig(format!("private key: {}", e)))
+}
+
+/// Address of the gateway holding secp256k1 key `privkey_hex` (32 bytes of hex).
+pub fn signer_address(privkey_hex: &str) -> Result<[u8; 20], VerifyError> {
+    Ok(address_of_key(signing_key(privkey_hex)?.verifying_key()))
+}
+
+/// Sign `receipt_val` the way a gateway with key `privkey_hex` would under `opts`: same scheme,
+/// digest mode, canonicalization and excluded fields or sign path. Returns the 65-byte low-S
+/// `r || s || v` signature (`v` = 27/28) as `0x` hex.
+pub fn sign_receipt(receipt_val: &serde_json::Value, privkey_hex: &str, opts: &VerifyOptions) -> Result<String, VerifyError> {
+    let key = signing_key(privkey_hex)?;
+    let (_, _, digest) = signing_digest(receipt_val, opts)?;
+    let (sig, rec_id) = key.sign_prehash_recoverable(&digest).map_err(|e| VerifyError::Sig(e.to_string()))?;
+    let mut out = sig.to_bytes().to_vec();
+    out.push(27 + rec_id.to_byte());
+    Ok(format!("0x{}", hex::encode(out)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{verify_receipt_with, CanonMode, DigestMode};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn verify_inverts_sign(
+            key in any::<[u8; 32]>(),
+            policy in any::<[u8; 32]>(),
+            consent in any::<[u8; 32]>(),
+            payload in proptest::collection::btree_map("[a-z_]{1,8}", prop_oneof![
+                any::<i64>().prop_map(serde_json::Value::from),
+                "\\PC{0,16}".prop_map(serde_json::Value::from),
+            ], 0..6),
+            canon in prop_oneof![Just(CanonMode::Sorted), Just(CanonMode::Jcs)],
+            digest in prop_oneof![Just(DigestMode::RawCanonical), Just(DigestMode::Keccak), Just(DigestMode::Sha256)],
+        ) {
+            let key = hex::encode(key);
+            // Not every 32-byte string is a valid scalar.
+            let Ok(signer) = signer_address(&key) else { return Ok(()) };
+            let (policy, consent) = (format!("0x{}", hex::encode(policy)), format!("0x{}", hex::encode(consent)));
+            let mut receipt = serde_json::json!({
+                "receipt_id": "rcpt_prop",
+                "policy_hash": policy,
+                "consent_snapshot_hash": consent,
+                "payload": payload,
+            });
+            let opts = VerifyOptions { canon, digest, ..Default::default() };
+            let sig = sign_receipt(&receipt, &key, &opts).unwrap();
+            prop_assert_eq!(verify_receipt_with(&receipt, &sig, Some(signer), &policy, &consent, &opts).unwrap().1, signer);
+
+            // Embedding the signature does not change what was signed.
+            receipt["receipt_sig"] = sig.clone().into();
+            prop_assert!(verify_receipt_with(&receipt, &sig, Some(signer), &policy, &consent, &opts).is_ok());
+
+            // Any edit to a signed field recovers some other address.
+            receipt["payload"]["__edited"] = true.into();
+            let edited = verify_receipt_with(&receipt, &sig, Some(signer), &policy, &consent, &opts);
+            prop_assert!(matches!(edited, Err(VerifyError::AddressMismatch { .. })), "{:?}", edited);
+        }
+    }
+
+    #[test]
+    fn malformed_keys_are_rejected() {
+        let receipt = serde_json::json!({ "policy_hash": "0x00", "consent_snapshot_hash": "0x00" });
+        for key in ["", "0x11", &"00".repeat(32), &"gg".repeat(32)] {
+            assert!(sign_receipt(&receipt, key, &VerifyOptions::default()).is_err(), "{key}");
+        }
+        assert_eq!(
+            signer_address(&"11".repeat(32)).unwrap(),
+            crate::parse_gateway("0x19E7E376E7C213B7E7e7e46cc70A5dD086DAff2A").unwrap()
+        );
+    }
+}
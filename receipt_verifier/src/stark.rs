@@ -0,0 +1,167 @@
+//! Stark-curve signatures for gateways on Starknet (`starknet` feature).
+//!
+//! The gateway is identified by its 32-byte Stark public key felt rather than a 20-byte
+//! address. The signed message is a single felt derived from the canonical receipt (same
+//! exclusions and canonicalization as for secp256k1 receipts):
+//! - the canonical bytes are packed as `[len, 31-byte big-endian chunks..]` (the `Field`
+//!   packing with 31 bytes per element, so every chunk is below the Stark prime);
+//! - `Poseidon` hashes that sequence with `poseidon_hash_many`; `Pedersen` uses Starknet's
+//!   `compute_hash_on_elements` (`h = pedersen(h, x)` from `h = 0`, then `pedersen(h, n)`).
+//!
+//! The signature is `(r, s)`, given as `r,s` felts or as 64 bytes of hex `r || s`.
+
+use crate::{
+    canonical_and_hash, check_claims, check_embedded_sig, check_expected_receipt_hash, hex_bytes, parse_hex, parse_receipt, receipt_id_of, HashHex, HashScheme, PublicInputs, ReceiptV1, VerifiedReceipt,
+    VerifyError, VerifyOptions, Witness,
+};
+use starknet_crypto::{pedersen_hash, poseidon_hash_many, Felt};
+
+/// Hash used to turn the canonical receipt into the signed felt.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StarkHash {
+    #[default]
+    Poseidon,
+    Pedersen,
+}
+
+impl std::str::FromStr for StarkHash {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "poseidon" => Ok(StarkHash::Poseidon),
+            "pedersen" => Ok(StarkHash::Pedersen),
+            other => Err(format!("unknown stark hash {other} (expected poseidon|pedersen)")),
+        }
+    }
+}
+
+const BYTES_PER_FELT: usize = 31;
+
+/// Parse a felt given as hex (at most 32 bytes, below the Stark prime).
+pub fn parse_felt(hex_str: &str) -> Result<Felt, VerifyError> {
+    let bytes = parse_hex(hex_str)?;
+    if bytes.len() > 32 {
+        return Err(VerifyError::Hex(format!("felt must be at most 32 bytes, got {}", bytes.len())));
+    }
+    let felt = Felt::from_bytes_be_slice(&bytes);
+    if felt.to_bytes_be()[32 - bytes.len()..] != bytes[..] {
+        return Err(VerifyError::Hex(format!("{} is not below the Stark prime", hex_str)));
+    }
+    Ok(felt)
+}
+
+/// The felt a gateway signs for canonical receipt bytes `canonical`.
+pub fn stark_message_hash(canonical: &[u8], hash: StarkHash) -> Felt {
+    let mut elements = vec![Felt::from(canonical.len() as u64)];
+    elements.extend(canonical.chunks(BYTES_PER_FELT).map(Felt::from_bytes_be_slice));
+    match hash {
+        StarkHash::Poseidon => poseidon_hash_many(&elements),
+        StarkHash::Pedersen => {
+            let h = elements.iter().fold(Felt::ZERO, |h, x| pedersen_hash(&h, x));
+            pedersen_hash(&h, &Felt::from(elements.len() as u64))
+        }
+    }
+}
+
+fn parse_stark_sig(sig: &str) -> Result<(Felt, Felt), VerifyError> {
+    if let Some((r, s)) = sig.split_once(',') {
+        return Ok((parse_felt(r.trim())?, parse_felt(s.trim())?));
+    }
+    let bytes = hex_bytes(sig, false).map_err(|e| VerifyError::Sig(format!("stark signature is not valid hex: {}", e)))?;
+    if bytes.len() != 64 {
+        return Err(VerifyError::Sig(format!("stark signature must be `r,s` or 64 bytes r || s, got {} bytes", bytes.len())));
+    }
+    Ok((Felt::from_bytes_be_slice(&bytes[..32]), Felt::from_bytes_be_slice(&bytes[32..])))
+}
+
+/// Check a Stark signature `(r, s)` by `pubkey` over the receipt's canonical form. Returns the
+/// signed message felt as hex.
+pub fn verify_stark_sig(
+    receipt_val: &serde_json::Value,
+    sig: (&str, &str),
+    pubkey: &str,
+    hash: StarkHash,
+) -> Result<String, VerifyError> {
+    let (canonical, _) = canonical_and_hash(receipt_val, &VerifyOptions::default())?;
+    check_stark_sig(&canonical, (parse_felt(sig.0)?, parse_felt(sig.1)?), &parse_felt(pubkey)?, hash)
+}
+
+fn check_stark_sig(canonical: &str, (r, s): (Felt, Felt), pubkey: &Felt, hash: StarkHash) -> Result<String, VerifyError> {
+    let message = stark_message_hash(canonical.as_bytes(), hash);
+    match starknet_crypto::verify(pubkey, &message, &r, &s) {
+        Ok(true) => Ok(format!("0x{}", hex::encode(message.to_bytes_be()))),
+        Ok(false) => Err(VerifyError::Sig("stark signature does not verify against the public key".into())),
+        Err(e) => Err(VerifyError::Sig(format!("stark signature: {}", e))),
+    }
+}
+
+/// A receipt whose Stark signature and claims checked out.
+#[derive(Debug, Clone)]
+pub struct StarkVerifiedReceipt {
+    pub receipt: ReceiptV1,
+    pub canonical: String,
+    /// Receipt hash (hex, no `0x`) under the options' `HashScheme`.
+    pub receipt_hash: String,
+    /// The gateway's Stark public key.
+    pub public_key: [u8; 32],
+    /// Scheme `receipt_hash` was computed with.
+    pub hash_scheme: HashScheme,
+}
+
+impl StarkVerifiedReceipt {
+    /// `receipt_hash` in the `0x` form used in public inputs and outputs.
+    pub fn receipt_hash_hex(&self) -> HashHex {
+        HashHex::normalize(&self.receipt_hash)
+    }
+
+    /// Public inputs and witness; `gateway_address` is the full 32-byte public key felt.
+    pub fn public_and_witness(&self, signature: &str) -> (PublicInputs, Witness) {
+        let (mut pub_inputs, witness) = self.as_verified().public_and_witness(signature);
+        pub_inputs.gateway_address = format!("0x{}", hex::encode(self.public_key));
+        pub_inputs.gateway_address_checksum = None;
+        (pub_inputs, witness)
+    }
+
+    fn as_verified(&self) -> VerifiedReceipt {
+        VerifiedReceipt {
+            receipt: self.receipt.clone(),
+            canonical: self.canonical.clone(),
+            receipt_hash: self.receipt_hash.clone(),
+            signer: low_address(&self.public_key),
+            chain_id: None,
+            hash_scheme: self.hash_scheme.clone(),
+            digest_mode: None,
+        }
+    }
+}
+
+/// Low 20 bytes of a felt: the `parse_gateway` reading of a 32-byte gateway, used where a
+/// 20-byte signer is needed (replay keys).
+fn low_address(felt: &[u8; 32]) -> [u8; 20] {
+    felt[12..].try_into().expect("20 bytes")
+}
+
+/// `verify_receipt_full` for a Stark-signed receipt: `gateway` is the expected public key felt;
+/// `opts.scheme`, `digest`, `allow_high_s` and `expected_chain_id` do not apply.
+pub fn verify_receipt_stark(
+    receipt_val: &serde_json::Value,
+    signature: &str,
+    gateway: &str,
+    expected_policy_hash: &str,
+    expected_consent_hash: &str,
+    hash: StarkHash,
+    opts: &VerifyOptions,
+) -> Result<StarkVerifiedReceipt, VerifyError> {
+    crate::telemetry::observe_verify(|| {
+        let _span = tracing::info_span!("verify_stark", receipt_id = receipt_id_of(receipt_val), gateway).entered();
+        let receipt = parse_receipt(receipt_val, opts)?;
+        check_embedded_sig(receipt_val, signature, opts)?;
+        let (canonical, receipt_hash) = canonical_and_hash(receipt_val, opts)?;
+        check_expected_receipt_hash(&receipt_hash, opts)?;
+        let pubkey = parse_felt(gateway).map_err(|e| VerifyError::GatewayParse(e.to_string()))?;
+        check_stark_sig(&canonical, parse_stark_sig(signature)?, &pubkey, hash)?;
+        let public_key = pubkey.to_bytes_be();
+        check_claims(&receipt, &receipt_hash, low_address(&public_key), expected_policy_hash, expected_consent_hash, opts)?;
+        Ok(StarkVerifiedReceipt { receipt, canonical, receipt_hash, public_key, hash_scheme: opts.hash.clone() })
+    })
+}
@@ -0,0 +1,56 @@
+//! Verification and proving metrics through the `metrics` crate (`metrics` feature); without
+//! the feature `observe_*` just run the operation. Any `metrics` recorder collects them.
+//!
+//! - `receipt_verifier_verify_total{outcome, code}`: `outcome` is `ok` or `failed`, `code` the
+//!   `VerifyError::code()` of a failure (`OK` on success).
+//! - `receipt_verifier_verify_duration_seconds`: histogram over every verification.
+//! - `receipt_verifier_prove_total{backend, outcome, code}` and
+//!   `receipt_verifier_prove_duration_seconds{backend}`: the same per prover backend.
+
+use crate::VerifyError;
+
+pub const VERIFY_TOTAL: &str = "receipt_verifier_verify_total";
+pub const VERIFY_DURATION: &str = "receipt_verifier_verify_duration_seconds";
+pub const PROVE_TOTAL: &str = "receipt_verifier_prove_total";
+pub const PROVE_DURATION: &str = "receipt_verifier_prove_duration_seconds";
+
+#[cfg(feature = "metrics")]
+fn outcome<T>(res: &Result<T, VerifyError>) -> (&'static str, &'static str) {
+    match res {
+        Ok(_) => ("ok", "OK"),
+        Err(e) => ("failed", e.code()),
+    }
+}
+
+/// Run one verification, counting its outcome and timing it.
+pub(crate) fn observe_verify<T>(verify: impl FnOnce() -> Result<T, VerifyError>) -> Result<T, VerifyError> {
+    #[cfg(feature = "metrics")]
+    {
+        let start = std::time::Instant::now();
+        let res = verify();
+        let (outcome, code) = outcome(&res);
+        metrics::counter!(VERIFY_TOTAL, "outcome" => outcome, "code" => code).increment(1);
+        metrics::histogram!(VERIFY_DURATION).record(start.elapsed().as_secs_f64());
+        res
+    }
+    #[cfg(not(feature = "metrics"))]
+    verify()
+}
+
+/// Run one proof on `backend`, counting its outcome and timing it.
+pub(crate) fn observe_prove<T>(backend: &'static str, prove: impl FnOnce() -> Result<T, VerifyError>) -> Result<T, VerifyError> {
+    #[cfg(feature = "metrics")]
+    {
+        let start = std::time::Instant::now();
+        let res = prove();
+        let (outcome, code) = outcome(&res);
+        metrics::counter!(PROVE_TOTAL, "backend" => backend, "outcome" => outcome, "code" => code).increment(1);
+        metrics::histogram!(PROVE_DURATION, "backend" => backend).record(start.elapsed().as_secs_f64());
+        res
+    }
+    #[cfg(not(feature = "metrics"))]
+    {
+        let _ = backend;
+        prove()
+    }
+}
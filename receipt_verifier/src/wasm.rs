@@ -0,0 +1,50 @@
+//! Browser / Workers entry point (`wasm` feature). Build with
+//! `cargo build --target wasm32-unknown-unknown --no-default-features --features wasm`.
+
+use crate::{parse_gateway, verify_receipt_full, VerifyError, VerifyOptions};
+use serde_json::json;
+use wasm_bindgen::prelude::*;
+
+/// Verify a receipt and return `{ ok, receipt_hash, recovered_address, public_inputs }`, or
+/// `{ ok: false, error: { code, numeric_code, message } }`, as a JS object.
+///
+/// `gateway` is `"auto"`, a `0x` address, or a `did:pkh` / `did:ethr` DID. The time-window
+/// checks use `Date.now()`, since wasm32 has no system clock.
+#[wasm_bindgen]
+pub fn verify_receipt_wasm(
+    receipt_json: &str,
+    sig_hex: &str,
+    gateway: &str,
+    policy_hash: &str,
+    consent_hash: &str,
+) -> JsValue {
+    let result = verify(receipt_json, sig_hex, gateway, policy_hash, consent_hash).unwrap_or_else(|e| {
+        json!({ "ok": false, "error": { "code": e.code(), "numeric_code": e.numeric_code(), "message": e.to_string() } })
+    });
+    js_sys::JSON::parse(&result.to_string()).unwrap_or(JsValue::NULL)
+}
+
+fn verify(
+    receipt_json: &str,
+    sig_hex: &str,
+    gateway: &str,
+    policy_hash: &str,
+    consent_hash: &str,
+) -> Result<serde_json::Value, VerifyError> {
+    let receipt_val: serde_json::Value =
+        serde_json::from_str(receipt_json).map_err(|e| VerifyError::Serde(e.to_string()))?;
+    let expected_gateway = if gateway.eq_ignore_ascii_case("auto") {
+        None
+    } else {
+        Some(parse_gateway(gateway)?)
+    };
+    let opts = VerifyOptions { now: Some((js_sys::Date::now() / 1000.0) as u64), ..Default::default() };
+    let verified = verify_receipt_full(&receipt_val, sig_hex, expected_gateway, policy_hash, consent_hash, &opts)?;
+    let (pub_inputs, _) = verified.public_and_witness(sig_hex);
+    Ok(json!({
+        "ok": true,
+        "receipt_hash": verified.receipt_hash_hex(),
+        "recovered_address": crate::to_checksum_address(verified.signer),
+        "public_inputs": pub_inputs,
+    }))
+}
@@ -0,0 +1,51 @@
+//! Golden canonicalization vectors. Each `tests/golden/*.json` fixture holds a signed receipt
+//! with its expected signed canonical string, sha256 and keccak receipt hashes and recovered
+//! signer, under default options. Any change to that output fails here; if it is intended,
+//! bump `CANONICALIZATION_VERSION` and regenerate the fixtures with
+//! `GOLDEN_BLESS=1 cargo test --test golden`.
+
+use receipt_verifier::{to_checksum_address, verify_receipt_full, VerifyOptions, CANONICALIZATION_VERSION};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+
+/// The expected fields of a fixture, computed from its `receipt`.
+fn expected(receipt: &Value) -> Value {
+    let sig = receipt["receipt_sig"].as_str().expect("fixture receipt carries receipt_sig");
+    let policy = receipt["policy_hash"].as_str().unwrap();
+    let consent = receipt["consent_snapshot_hash"].as_str().unwrap();
+    let verified = verify_receipt_full(receipt, sig, None, policy, consent, &VerifyOptions::default())
+        .unwrap_or_else(|e| panic!("fixture receipt does not verify: {e}"));
+    let (pub_inputs, _) = verified.public_and_witness(sig);
+    json!({
+        "canonicalization_version": CANONICALIZATION_VERSION,
+        "canonical": verified.canonical,
+        "receipt_hash_sha256": pub_inputs.receipt_hash_sha256,
+        "receipt_hash_keccak": pub_inputs.receipt_hash_keccak,
+        "signer": to_checksum_address(verified.signer),
+    })
+}
+
+#[test]
+fn golden_fixtures() {
+    let bless = std::env::var_os("GOLDEN_BLESS").is_some();
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let mut paths: Vec<_> = fs::read_dir(&dir).unwrap().map(|e| e.unwrap().path()).collect();
+    paths.retain(|p| p.extension().is_some_and(|e| e == "json"));
+    paths.sort();
+    assert!(!paths.is_empty(), "no fixtures in {}", dir.display());
+    for path in paths {
+        let mut fixture: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let actual = expected(&fixture["receipt"]);
+        if bless {
+            for (key, value) in actual.as_object().unwrap() {
+                fixture[key] = value.clone();
+            }
+            fs::write(&path, serde_json::to_string_pretty(&fixture).unwrap() + "\n").unwrap();
+            continue;
+        }
+        for (key, value) in actual.as_object().unwrap() {
+            assert_eq!(&fixture[key], value, "{}: `{}` changed", path.display(), key);
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//! Property tests for signature recovery: random keys and messages, with `v` in every
+//! encoding `recover_address_ext` accepts.
+#![cfg(feature = "signing")]
+
+use k256::ecdsa::SigningKey;
+use proptest::prelude::*;
+use receipt_verifier::{personal_hash_keccak, recover_address_ext, signer_address};
+
+/// `v` as 0/1, 27/28 or EIP-155 (`chain_id * 2 + 35 + parity`, big-endian, as many bytes as
+/// it needs), with the chain id the signature should report.
+fn encode_v(parity: u8, encoding: u8, chain_id: u64) -> (Vec<u8>, Option<u64>) {
+    match encoding {
+        0 => (vec![parity], None),
+        1 => (vec![27 + parity], None),
+        _ => {
+            let v = chain_id * 2 + 35 + u64::from(parity);
+            let bytes = v.to_be_bytes();
+            let first = bytes.iter().position(|b| *b != 0).unwrap_or(7);
+            (bytes[first..].to_vec(), Some(chain_id))
+        }
+    }
+}
+
+fn sign(key: &[u8; 32], msg: &[u8]) -> Option<([u8; 64], u8, [u8; 32])> {
+    let key = SigningKey::from_slice(key).ok()?;
+    let digest = personal_hash_keccak(msg);
+    let (sig, rec_id) = key.sign_prehash_recoverable(&digest).ok()?;
+    Some((sig.to_bytes().into(), rec_id.to_byte(), digest))
+}
+
+proptest! {
+    #[test]
+    fn recovers_signer_and_chain_id(
+        key in any::<[u8; 32]>(),
+        msg in proptest::collection::vec(any::<u8>(), 0..256),
+        encoding in 0u8..3,
+        chain_id in prop_oneof![1u64..=110, 111u64..=u32::MAX as u64, Just(u64::MAX / 4)],
+    ) {
+        let Some((rs, parity, digest)) = sign(&key, &msg) else { return Ok(()) };
+        let (v, expected_chain) = encode_v(parity, encoding, chain_id);
+        let sig = format!("0x{}{}", hex::encode(rs), hex::encode(v));
+        let signer = signer_address(&hex::encode(key)).unwrap();
+        prop_assert_eq!(recover_address_ext(&sig, digest, false).unwrap(), (signer, expected_chain));
+    }
+
+    #[test]
+    fn rejects_tampered_r_or_s(
+        key in any::<[u8; 32]>(),
+        msg in proptest::collection::vec(any::<u8>(), 0..256),
+        bit in 0usize..512,
+    ) {
+        let Some((mut rs, parity, digest)) = sign(&key, &msg) else { return Ok(()) };
+        rs[bit / 8] ^= 1 << (bit % 8);
+        let sig = format!("0x{}{:02x}", hex::encode(rs), 27 + parity);
+        let signer = signer_address(&hex::encode(key)).unwrap();
+        // Either the edited signature is invalid or it recovers some other key.
+        if let Ok((addr, _)) = recover_address_ext(&sig, digest, false) {
+            prop_assert_ne!(addr, signer);
+        }
+    }
+}
@@ -0,0 +1,42 @@
+//! `verify_receipt_wasm` on the fixture receipt, through the JS boundary. Runs on wasm32 only,
+//! in Node: `CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER=wasm-bindgen-test-runner cargo test
+//! --target wasm32-unknown-unknown --no-default-features --features wasm --test wasm` (the
+//! runner comes with `wasm-bindgen-cli`, at the crate's `wasm-bindgen` version).
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use receipt_verifier::wasm::verify_receipt_wasm;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const RECEIPT: &str = include_str!("../../scripts/receipts/rcpt_1765778272_0cbf2a85.json");
+const GATEWAY: &str = "0x3A66A80Ac10721e69b6A9Cd56B07282D9a094813";
+const POLICY_HASH: &str = "0xabababababababababababababababababababababababababababababababab";
+const CONSENT_HASH: &str = "0xcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcd";
+
+fn signature() -> String {
+    let receipt: serde_json::Value = serde_json::from_str(RECEIPT).unwrap();
+    receipt["receipt_sig"].as_str().unwrap().to_string()
+}
+
+/// The returned JS object, back as JSON.
+fn to_json(value: JsValue) -> serde_json::Value {
+    let text = js_sys::JSON::stringify(&value).unwrap().as_string().unwrap();
+    serde_json::from_str(&text).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn verifies_the_fixture_receipt() {
+    let out = to_json(verify_receipt_wasm(RECEIPT, &signature(), GATEWAY, POLICY_HASH, CONSENT_HASH));
+    assert_eq!(out["ok"], true);
+    assert_eq!(out["recovered_address"], GATEWAY);
+    assert_eq!(out["receipt_hash"], "0xa9154ef5e5691825ff1be2a1da3dabe375263493da745f00e1519279a8af3d56");
+    assert_eq!(out["public_inputs"]["gateway_address"], GATEWAY.to_lowercase());
+}
+
+#[wasm_bindgen_test]
+fn reports_errors_with_their_codes() {
+    let out = to_json(verify_receipt_wasm(RECEIPT, &signature(), GATEWAY, CONSENT_HASH, CONSENT_HASH));
+    assert_eq!(out["ok"], false);
+    assert_eq!(out["error"]["code"], "POLICY_MISMATCH");
+    assert_eq!(out["error"]["numeric_code"], 24);
+}
@@ -64,6 +64,7 @@ def run_cargo(receipt_path: Path, signature: str, gateway: str, policy_hash: str
         "cargo",
         "run",
         "--",
+        "prove",
         "--receipt",
         str(receipt_path),
         "--signature",
@@ -74,7 +75,6 @@ def run_cargo(receipt_path: Path, signature: str, gateway: str, policy_hash: str
         policy_hash,
         "--consent-hash",
         consent_hash,
-        "--prove",
         "--out-public",
         str(out_public),
         "--out-witness",
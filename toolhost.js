@@ -61,6 +61,7 @@ function runProver(receiptPath, signature, policyHash, consentHash, gateway = "a
     const args = [
       "run",
       "--",
+      "prove",
       "--receipt",
       receiptPath,
       "--signature",
@@ -71,7 +72,6 @@ function runProver(receiptPath, signature, policyHash, consentHash, gateway = "a
       policyHash,
       "--consent-hash",
       consentHash,
-      "--prove",
       "--out-public",
       `${PROVER_OUT_DIR}/public.json`,
       "--out-witness",